@@ -0,0 +1,98 @@
+//! # Auto-Deleveraging Logic
+//!
+//! Auto-deleveraging (ADL) calculations that keep trader profits within
+//! what the vault can pay.
+//!
+//! ## Mechanism
+//!
+//! When the unrealized PnL traders hold on an asset crosses a share of the
+//! vault's USDC, keepers partially close the most profitable positions at
+//! the current price, realizing their profit before it outgrows the pool.
+//!
+//! ## Ranking
+//!
+//! Positions are ranked by return on collateral, which combines PnL and
+//! leverage:
+//! ```
+//! adl_score = pnl / collateral = pnl% × leverage
+//! ```
+//! The highest score is deleveraged first.
+
+use noether_common::BASIS_POINTS;
+
+/// Calculate the ADL score of a position.
+///
+/// # Arguments
+/// * `pnl` - Unrealized PnL of the position (7 decimals)
+/// * `collateral` - Collateral of the position (7 decimals)
+///
+/// # Returns
+/// Return on collateral in basis points (0 for losing positions)
+pub fn calculate_adl_score(pnl: i128, collateral: i128) -> i128 {
+    if pnl <= 0 || collateral <= 0 {
+        return 0;
+    }
+
+    pnl * (BASIS_POINTS as i128) / collateral
+}
+
+/// Calculate the PnL exposure above which ADL is allowed.
+///
+/// # Arguments
+/// * `vault_usdc` - Total USDC in the vault (7 decimals)
+/// * `threshold_bps` - Share of vault USDC in basis points
+pub fn calculate_adl_threshold(vault_usdc: i128, threshold_bps: u32) -> i128 {
+    vault_usdc * (threshold_bps as i128) / (BASIS_POINTS as i128)
+}
+
+/// Calculate the fraction of a position to close to realize `excess_pnl`.
+///
+/// # Arguments
+/// * `pnl` - Unrealized PnL of the position (7 decimals)
+/// * `excess_pnl` - PnL exposure above the ADL threshold (7 decimals)
+///
+/// # Returns
+/// Fraction to close in basis points (rounded up, at most 100%)
+pub fn calculate_adl_close_bps(pnl: i128, excess_pnl: i128) -> u32 {
+    if pnl <= 0 || excess_pnl <= 0 {
+        return 0;
+    }
+    if excess_pnl >= pnl {
+        return BASIS_POINTS;
+    }
+
+    let bps = (excess_pnl * (BASIS_POINTS as i128) + pnl - 1) / pnl;
+    bps as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use noether_common::PRECISION;
+
+    #[test]
+    fn test_adl_score() {
+        // $50 profit on $100 collateral = 50% return
+        assert_eq!(calculate_adl_score(50 * PRECISION, 100 * PRECISION), 5000);
+        // Same profit with half the collateral ranks higher
+        assert_eq!(calculate_adl_score(50 * PRECISION, 50 * PRECISION), 10_000);
+        // Losing positions are never deleveraged
+        assert_eq!(calculate_adl_score(-50 * PRECISION, 100 * PRECISION), 0);
+    }
+
+    #[test]
+    fn test_adl_threshold() {
+        assert_eq!(calculate_adl_threshold(1000 * PRECISION, 2000), 200 * PRECISION);
+    }
+
+    #[test]
+    fn test_adl_close_bps() {
+        // Realize $25 of a $100 profit = 25%
+        assert_eq!(calculate_adl_close_bps(100 * PRECISION, 25 * PRECISION), 2500);
+        // Rounds up so the excess is fully covered
+        assert_eq!(calculate_adl_close_bps(3 * PRECISION, PRECISION), 3334);
+        // Excess larger than the profit closes everything
+        assert_eq!(calculate_adl_close_bps(10 * PRECISION, 25 * PRECISION), 10_000);
+        assert_eq!(calculate_adl_close_bps(-10 * PRECISION, 25 * PRECISION), 0);
+    }
+}
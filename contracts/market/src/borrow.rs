@@ -0,0 +1,125 @@
+//! # Borrow Fee Logic
+//!
+//! Borrow fee calculations that pay LPs for the liquidity positions lock up.
+//!
+//! ## Borrow Fee Mechanism
+//!
+//! Every open position pays a borrow fee on its size, whichever side it is
+//! on. Unlike funding, the fee does not depend on the long/short balance, so
+//! a perfectly balanced book still pays the pool.
+//!
+//! ## Rate Curve
+//!
+//! The annual rate follows a kinked curve of vault utilization
+//! (reserved USDC / total USDC):
+//! ```
+//! if utilization <= optimal:
+//!     rate = base + slope * utilization / optimal
+//! else:
+//!     rate = base + slope + kink_slope * (utilization - optimal) / (1 - optimal)
+//! ```
+//!
+//! ## Application
+//!
+//! The fee accrues every second into a per-asset cumulative index. Each
+//! position snapshots the index when opened, and owes:
+//! ```
+//! borrow_fee = position_size * (current_index - snapshot_index)
+//! ```
+
+use noether_common::BASIS_POINTS;
+
+/// Precision of the cumulative borrow index (10^18).
+pub const BORROW_INDEX_PRECISION: i128 = 1_000_000_000_000_000_000;
+
+/// Seconds per year (borrow rates are quoted per year).
+const SECONDS_PER_YEAR: u64 = 365 * 24 * 3600;
+
+/// Calculate the annual borrow rate at a given utilization.
+///
+/// # Arguments
+/// * `utilization_bps` - Vault utilization in basis points
+/// * `base_rate_bps` - Rate at zero utilization
+/// * `slope_bps` - Rate added between zero and optimal utilization
+/// * `kink_slope_bps` - Rate added between optimal and full utilization
+/// * `optimal_utilization_bps` - Utilization at which the curve steepens
+///
+/// # Returns
+/// Borrow rate in basis points per year
+pub fn calculate_borrow_rate_bps(
+    utilization_bps: i128,
+    base_rate_bps: u32,
+    slope_bps: u32,
+    kink_slope_bps: u32,
+    optimal_utilization_bps: u32,
+) -> i128 {
+    let utilization = utilization_bps.clamp(0, BASIS_POINTS as i128);
+    let optimal = optimal_utilization_bps as i128;
+    let base = base_rate_bps as i128;
+    let slope = slope_bps as i128;
+
+    if utilization <= optimal {
+        return base + slope * utilization / optimal;
+    }
+
+    let excess_range = BASIS_POINTS as i128 - optimal;
+    if excess_range == 0 {
+        return base + slope;
+    }
+
+    base + slope + (kink_slope_bps as i128) * (utilization - optimal) / excess_range
+}
+
+/// Calculate how much the borrow index moves over a period.
+///
+/// # Arguments
+/// * `rate_bps` - Borrow rate in basis points per year
+/// * `seconds_elapsed` - Seconds since the index was last updated
+///
+/// # Returns
+/// Index delta scaled by BORROW_INDEX_PRECISION
+pub fn calculate_borrow_index_delta(rate_bps: i128, seconds_elapsed: u64) -> i128 {
+    if seconds_elapsed == 0 || rate_bps <= 0 {
+        return 0;
+    }
+
+    BORROW_INDEX_PRECISION * rate_bps * (seconds_elapsed as i128)
+        / ((BASIS_POINTS as i128) * (SECONDS_PER_YEAR as i128))
+}
+
+/// Calculate borrow fee owed by a position since its index snapshot.
+pub fn calculate_borrow_fee(position_size: i128, entry_index: i128, current_index: i128) -> i128 {
+    position_size * (current_index - entry_index) / BORROW_INDEX_PRECISION
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use noether_common::PRECISION;
+
+    #[test]
+    fn test_borrow_rate_below_kink() {
+        // Half of optimal = half of slope
+        assert_eq!(calculate_borrow_rate_bps(4000, 100, 1000, 10_000, 8000), 600);
+        assert_eq!(calculate_borrow_rate_bps(0, 100, 1000, 10_000, 8000), 100);
+        assert_eq!(calculate_borrow_rate_bps(8000, 100, 1000, 10_000, 8000), 1100);
+    }
+
+    #[test]
+    fn test_borrow_rate_above_kink() {
+        // Halfway from optimal to full utilization
+        assert_eq!(calculate_borrow_rate_bps(9000, 100, 1000, 10_000, 8000), 6100);
+        assert_eq!(calculate_borrow_rate_bps(10_000, 100, 1000, 10_000, 8000), 11_100);
+    }
+
+    #[test]
+    fn test_borrow_fee_per_year() {
+        // 10% per year on $1000 for a full year = $100
+        let delta = calculate_borrow_index_delta(1000, SECONDS_PER_YEAR);
+        assert_eq!(calculate_borrow_fee(1000 * PRECISION, 0, delta), 100 * PRECISION);
+
+        // One second still accrues
+        assert!(calculate_borrow_index_delta(1000, 1) > 0);
+        assert_eq!(calculate_borrow_index_delta(0, 3600), 0);
+    }
+}
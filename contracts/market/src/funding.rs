@@ -19,13 +19,66 @@
 //!
 //! ## Application
 //!
-//! Funding is applied hourly. The payment per position is:
+//! Funding accrues every second into per-asset cumulative indices, one per
+//! side. Each position snapshots its side's index when opened, and owes:
 //! ```
-//! funding_payment = position_size * funding_rate * hours_elapsed
+//! funding_payment = position_size * (current_index - snapshot_index)
 //! ```
+//!
+//! The long index grows while longs pay and the short index falls by the
+//! same amount (shorts receive), and vice versa.
 
 use noether_common::{Direction, BASIS_POINTS};
 
+/// Precision of the cumulative funding indices (10^18).
+/// Much finer than PRECISION so that per-second accrual does not truncate.
+pub const FUNDING_INDEX_PRECISION: i128 = 1_000_000_000_000_000_000;
+
+/// Seconds per funding rate period (rates are quoted per hour).
+const SECONDS_PER_HOUR: u64 = 3600;
+
+/// Calculate how much the funding index moves over a period.
+///
+/// # Arguments
+/// * `total_long_size` - Total size of all long positions
+/// * `total_short_size` - Total size of all short positions
+/// * `base_rate_bps` - Base funding rate in basis points per hour
+/// * `seconds_elapsed` - Seconds since the index was last updated
+///
+/// # Returns
+/// Index delta scaled by FUNDING_INDEX_PRECISION.
+/// Positive = longs pay shorts (add to long index, subtract from short index).
+/// Negative = shorts pay longs.
+pub fn calculate_funding_index_delta(
+    total_long_size: i128,
+    total_short_size: i128,
+    base_rate_bps: u32,
+    seconds_elapsed: u64,
+) -> i128 {
+    if seconds_elapsed == 0 || total_long_size == total_short_size {
+        return 0;
+    }
+
+    let dominant = total_long_size.max(total_short_size);
+    let imbalance = (total_long_size - total_short_size) * FUNDING_INDEX_PRECISION / dominant;
+
+    imbalance * (base_rate_bps as i128) * (seconds_elapsed as i128)
+        / ((BASIS_POINTS as i128) * (SECONDS_PER_HOUR as i128))
+}
+
+/// Calculate funding owed by a position since its index snapshot.
+///
+/// # Arguments
+/// * `position_size` - Size of the position
+/// * `entry_index` - Side index snapshotted by the position
+/// * `current_index` - Current side index
+///
+/// # Returns
+/// Payment amount (positive = pay, negative = receive)
+pub fn calculate_funding_owed(position_size: i128, entry_index: i128, current_index: i128) -> i128 {
+    position_size * (current_index - entry_index) / FUNDING_INDEX_PRECISION
+}
+
 /// Calculate the funding rate based on open interest imbalance.
 ///
 /// # Arguments
@@ -136,6 +189,7 @@ pub fn time_until_next_funding(last_funding_time: u64, current_time: u64, interv
 #[cfg(test)]
 mod tests {
     use super::*;
+    use noether_common::PRECISION;
 
     #[test]
     fn test_balanced_market() {
@@ -195,6 +249,37 @@ mod tests {
         assert_eq!(payment, -PRECISION);
     }
 
+    #[test]
+    fn test_index_delta_per_second() {
+        // 50% imbalance at 10 bps/hour = 5 bps/hour
+        let hour = calculate_funding_index_delta(2000 * PRECISION, 1000 * PRECISION, 10, 3600);
+        let second = calculate_funding_index_delta(2000 * PRECISION, 1000 * PRECISION, 10, 1);
+        assert_eq!(hour, FUNDING_INDEX_PRECISION * 5 / 10_000);
+        assert!(second > 0);
+        // Accrues linearly, no hourly rounding
+        assert!((hour - second * 3600).abs() < 3600);
+    }
+
+    #[test]
+    fn test_index_delta_more_shorts() {
+        let delta = calculate_funding_index_delta(1000 * PRECISION, 2000 * PRECISION, 10, 3600);
+        assert_eq!(delta, -(FUNDING_INDEX_PRECISION * 5 / 10_000));
+    }
+
+    #[test]
+    fn test_index_delta_balanced() {
+        assert_eq!(calculate_funding_index_delta(1000 * PRECISION, 1000 * PRECISION, 10, 3600), 0);
+        assert_eq!(calculate_funding_index_delta(0, 0, 10, 3600), 0);
+    }
+
+    #[test]
+    fn test_funding_owed() {
+        // $1000 position, index moved by 5 bps
+        let delta = FUNDING_INDEX_PRECISION * 5 / 10_000;
+        assert_eq!(calculate_funding_owed(1000 * PRECISION, 0, delta), PRECISION / 2);
+        assert_eq!(calculate_funding_owed(1000 * PRECISION, 0, -delta), -PRECISION / 2);
+    }
+
     #[test]
     fn test_annualized_rate() {
         let hourly = 10i128; // 0.1% per hour
@@ -0,0 +1,72 @@
+//! # Insurance Fund Logic
+//!
+//! Calculations for the insurance fund that absorbs bad debt before it
+//! reaches LPs.
+//!
+//! ## Funding the Pool
+//!
+//! A share of every trading fee and liquidation fee is set aside in the
+//! insurance fund instead of going to the vault or the keeper:
+//! ```
+//! insurance_share = fee * insurance_fee_share_bps / 10000
+//! ```
+//!
+//! ## Bad Debt
+//!
+//! When a liquidated position's losses exceed its collateral, the shortfall
+//! is paid to the vault from the insurance fund, up to its balance. Only the
+//! uncovered remainder is borne by LPs.
+
+use noether_common::BASIS_POINTS;
+
+/// Calculate the share of a fee that goes to the insurance fund.
+///
+/// # Arguments
+/// * `fee` - Fee amount (7 decimals)
+/// * `share_bps` - Insurance share in basis points
+pub fn calculate_insurance_share(fee: i128, share_bps: u32) -> i128 {
+    if fee <= 0 {
+        return 0;
+    }
+
+    fee * (share_bps as i128) / (BASIS_POINTS as i128)
+}
+
+/// Calculate how much bad debt the insurance fund covers.
+///
+/// # Arguments
+/// * `bad_debt` - Loss not covered by the position's collateral (7 decimals)
+/// * `fund_balance` - Current insurance fund balance (7 decimals)
+///
+/// # Returns
+/// Amount paid from the fund (at most its balance)
+pub fn calculate_bad_debt_cover(bad_debt: i128, fund_balance: i128) -> i128 {
+    if bad_debt <= 0 || fund_balance <= 0 {
+        return 0;
+    }
+
+    bad_debt.min(fund_balance)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use noether_common::PRECISION;
+
+    #[test]
+    fn test_insurance_share() {
+        // 10% of a $5 fee
+        assert_eq!(calculate_insurance_share(5 * PRECISION, 1000), PRECISION / 2);
+        assert_eq!(calculate_insurance_share(5 * PRECISION, 0), 0);
+        assert_eq!(calculate_insurance_share(-5 * PRECISION, 1000), 0);
+    }
+
+    #[test]
+    fn test_bad_debt_cover() {
+        // Fund covers the whole shortfall
+        assert_eq!(calculate_bad_debt_cover(20 * PRECISION, 100 * PRECISION), 20 * PRECISION);
+        // Fund is drained, the rest falls on LPs
+        assert_eq!(calculate_bad_debt_cover(20 * PRECISION, 5 * PRECISION), 5 * PRECISION);
+        assert_eq!(calculate_bad_debt_cover(0, 100 * PRECISION), 0);
+    }
+}
@@ -3,11 +3,15 @@
 //! The core trading engine for Noether PerpDex.
 //!
 //! ## Features
+//! - Per-asset market registry (leverage, margins, fees, open interest)
 //! - Open leveraged long/short positions (1-10x)
-//! - Close positions and settle PnL
+//! - Close positions (fully or partially) and settle PnL
 //! - Liquidation mechanism for underwater positions
-//! - Funding rate to balance long/short interest
-//! - Position management (add collateral)
+//! - Funding rate to balance long/short interest (per-second cumulative index)
+//! - Utilization-based borrow fee paid to the vault
+//! - Auto-deleveraging of the most profitable positions
+//! - Insurance fund fed by fees that absorbs liquidation bad debt
+//! - Position management (add collateral, increase size)
 //!
 //! ## Architecture
 //! - Uses Oracle Adapter for price feeds
@@ -29,19 +33,24 @@
 //!
 //! **Liquidation:**
 //! 1. Market calculates remaining equity
-//! 2. Keeper gets reward from remaining
+//! 2. Keeper gets reward from remaining (insurance fund takes its share)
 //! 3. Vault gets rest of collateral
-//! 4. Trader gets nothing
+//! 4. Insurance fund pays the vault any bad debt it can cover
+//! 5. Trader gets nothing
 
 #![no_std]
 
-use soroban_sdk::{contract, contractimpl, token, Address, Env, Symbol, Vec, IntoVal};
+use soroban_sdk::{contract, contractimpl, token, Address, Env, Map, Symbol, Vec, IntoVal};
 use noether_common::{
-    NoetherError, Position, Direction, MarketConfig, MarketStats,
-    Order, OrderType, OrderStatus, TriggerCondition, KeeperFeeConfig,
-    calculate_position_size, calculate_liquidation_price, calculate_pnl,
-    calculate_trading_fee, calculate_funding_rate, calculate_funding_payment,
-    calculate_keeper_reward, should_liquidate,
+    NoetherError, Position, Direction, MarketConfig, MarketStats, FundingLedger, BASIS_POINTS,
+    Order, OrderType, OrderStatus, TriggerCondition, KeeperFeeConfig, BatchResult,
+    LimitOrderOptions, OperatorPermissions, OperatorApproval, MarginAccount,
+    MarginMode, AccountHealth, CollateralConfig, ReferralTier, ReferralCode, ReferralStats,
+    calculate_position_size, calculate_pnl,
+    calculate_trading_fee, calculate_funding_rate,
+    calculate_keeper_reward, calculate_average_entry_price,
+    calculate_price_impact, calculate_execution_price,
+    calculate_position_value,
 };
 
 mod storage;
@@ -49,8 +58,32 @@ mod position;
 mod trading;
 mod liquidation;
 mod funding;
+mod borrow;
+mod adl;
+mod insurance;
+mod trailing;
+mod operator;
+mod margin;
+mod referral;
 
 use storage::*;
+use position::{get_trader_total_collateral, has_open_positions};
+use trading::{calculate_partial_close, calculate_effective_leverage, has_sufficient_margin};
+use funding::{calculate_funding_index_delta, calculate_funding_owed};
+use borrow::{calculate_borrow_rate_bps, calculate_borrow_index_delta, calculate_borrow_fee};
+use liquidation::{
+    is_liquidatable, calculate_partial_liquidation_bps, calculate_liquidation_distribution,
+    calculate_equity_liquidation_price, calculate_liquidation_threshold,
+};
+use insurance::{calculate_insurance_share, calculate_bad_debt_cover};
+use trailing::{calculate_trailing_trigger, update_water_mark};
+use operator::{OperatorScope, is_operator_permitted};
+use margin::{
+    calculate_margin_draw, is_account_liquidatable, calculate_cross_liquidation_cover,
+    calculate_haircut_value, calculate_seize_amount,
+};
+use referral::{apply_referral_discount, calculate_referral_rebate, is_valid_tier};
+use adl::{calculate_adl_score, calculate_adl_threshold, calculate_adl_close_bps};
 
 // ═══════════════════════════════════════════════════════════════════════════
 // Contract Definition
@@ -72,7 +105,7 @@ impl MarketContract {
     /// * `oracle_adapter` - Oracle adapter contract address
     /// * `vault` - Vault contract address
     /// * `usdc_token` - USDC token contract address
-    /// * `config` - Market configuration parameters
+    /// * `config` - Protocol-wide default configuration
     pub fn initialize(
         env: Env,
         admin: Address,
@@ -87,10 +120,7 @@ impl MarketContract {
 
         admin.require_auth();
 
-        // Validate config
-        if config.max_leverage < 1 || config.max_leverage > 100 {
-            return Err(NoetherError::InvalidParameter);
-        }
+        Self::validate_config(&config)?;
 
         // Store addresses
         set_admin(&env, &admin);
@@ -98,14 +128,11 @@ impl MarketContract {
         set_vault(&env, &vault);
         set_usdc_token(&env, &usdc_token);
 
-        // Store configuration
+        // Store protocol-wide defaults (assets are listed via add_market)
         set_config(&env, &config);
 
         // Initialize state
         set_position_counter(&env, 0);
-        set_total_long_size(&env, 0);
-        set_total_short_size(&env, 0);
-        set_last_funding_time(&env, env.ledger().timestamp());
         init_position_index(&env);
 
         set_initialized(&env, true);
@@ -128,6 +155,7 @@ impl MarketContract {
     /// Open a new leveraged position.
     ///
     /// # Arguments
+    /// * `caller` - Trader or an operator approved to open for them
     /// * `trader` - Address of the trader
     /// * `asset` - Asset symbol (e.g., "XLM")
     /// * `collateral` - USDC collateral amount (7 decimals)
@@ -138,15 +166,16 @@ impl MarketContract {
     /// The created Position
     ///
     /// # Flow
-    /// 1. Validate parameters
-    /// 2. Check Vault liquidity for potential payout
-    /// 3. Fetch price from oracle
+    /// 1. Validate parameters against the asset's market config
+    /// 2. Check open interest caps and reserve Vault liquidity for potential payout
+    /// 3. Fetch price from oracle and apply skew-based price impact
     /// 4. Calculate position size and liquidation price
-    /// 5. Transfer collateral from trader
+    /// 5. Draw collateral from the margin account (wallet covers any shortfall)
     /// 6. Deduct trading fee
     /// 7. Store position
     pub fn open_position(
         env: Env,
+        caller: Address,
         trader: Address,
         asset: Symbol,
         collateral: i128,
@@ -156,9 +185,15 @@ impl MarketContract {
         require_initialized(&env)?;
         require_not_paused(&env)?;
 
-        trader.require_auth();
+        let via_operator = Self::require_trader_auth(
+            &env,
+            &caller,
+            &trader,
+            OperatorScope::Open,
+            NoetherError::OperatorNotApproved,
+        )?;
 
-        let config = get_config(&env);
+        let config = require_market(&env, &asset)?;
 
         // Validate parameters
         if collateral < config.min_collateral {
@@ -174,36 +209,39 @@ impl MarketContract {
             return Err(NoetherError::PositionTooLarge);
         }
 
-        // Check Vault has enough liquidity for potential payout
+        // Check the asset and global open interest caps
+        Self::check_open_interest(&env, &asset, &config, direction, size)?;
+
+        // Reserve Vault liquidity for potential payout
         // Maximum potential payout is the position size (100% gain)
         let vault_address = get_vault(&env);
-        Self::check_vault_liquidity(&env, &vault_address, size)?;
-
-        // Fetch current price
-        let entry_price = Self::get_oracle_price(&env, &asset)?;
+        Self::reserve_vault_liquidity(&env, &vault_address, size)?;
 
-        // Calculate liquidation price
-        let liquidation_price = calculate_liquidation_price(
-            entry_price,
-            leverage,
-            direction.clone(),
-            config.maintenance_margin_bps,
-        );
+        // Fetch current price and apply price impact
+        let oracle_price = Self::get_oracle_price(&env, &asset)?;
+        let (entry_price, price_impact) =
+            Self::get_execution_price(&env, &asset, &config, oracle_price, direction, size)?;
 
         // Calculate and deduct trading fee
-        let fee = calculate_trading_fee(size, config.trading_fee_bps);
+        let fee = Self::trading_fee(&env, &trader, size, &config);
         let net_collateral = collateral - fee;
 
-        // Transfer collateral from trader to market contract
-        let usdc_token = get_usdc_token(&env);
-        let token_client = token::Client::new(&env, &usdc_token);
-        token_client.transfer(&trader, &env.current_contract_address(), &collateral);
+        // Draw collateral from the margin account, then the wallet
+        Self::debit_margin(&env, &trader, collateral, via_operator);
+
+        // Accrue funding and borrow fees before open interest changes and snapshot the indices
+        let (long_index, short_index) = Self::accrue_funding(&env, &asset)?;
+        let funding_index = match direction {
+            Direction::Long => long_index,
+            Direction::Short => short_index,
+        };
+        let borrow_index = Self::accrue_borrow(&env, &asset)?;
 
         // Generate position ID
         let position_id = next_position_id(&env);
 
         // Create position
-        let position = Position {
+        let mut position = Position {
             id: position_id,
             trader: trader.clone(),
             asset: asset.clone(),
@@ -212,34 +250,31 @@ impl MarketContract {
             entry_price,
             direction: direction.clone(),
             leverage,
-            liquidation_price,
+            liquidation_price: 0,
             timestamp: env.ledger().timestamp(),
             last_funding_time: env.ledger().timestamp(),
             accumulated_funding: 0,
+            funding_index,
+            accumulated_borrow_fee: 0,
+            borrow_index,
+            reserved: size,
         };
+        position.liquidation_price =
+            calculate_equity_liquidation_price(&position, config.maintenance_margin_bps);
 
         // Store position
         save_position(&env, &position);
 
         // Update market stats
-        match direction {
-            Direction::Long => {
-                let total = get_total_long_size(&env);
-                set_total_long_size(&env, total + size);
-            }
-            Direction::Short => {
-                let total = get_total_short_size(&env);
-                set_total_short_size(&env, total + size);
-            }
-        }
+        adjust_open_interest(&env, &asset, direction, size);
 
-        // Transfer fee to vault
-        token_client.transfer(&env.current_contract_address(), &vault_address, &fee);
+        // Transfer fee to vault (insurance fund keeps its share)
+        Self::collect_trading_fee(&env, &vault_address, &config, &trader, fee);
 
         // Emit event
         env.events().publish(
             (Symbol::new(&env, "position_opened"),),
-            (position.id, trader, asset, size, direction, leverage, entry_price),
+            (position.id, trader, asset, size, direction, leverage, entry_price, price_impact),
         );
 
         extend_instance_ttl(&env);
@@ -250,109 +285,134 @@ impl MarketContract {
     /// Close an existing position.
     ///
     /// # Arguments
-    /// * `trader` - Address of the trader (must own position)
+    /// * `caller` - Trader (must own position) or an operator approved to close
     /// * `position_id` - ID of position to close
     ///
     /// # Returns
     /// Final PnL amount (positive = profit, negative = loss)
     ///
     /// # Flow
-    /// 1. Verify ownership
+    /// 1. Verify ownership or operator approval
     /// 2. Apply any pending funding
-    /// 3. Calculate PnL at current price
+    /// 3. Calculate PnL at current price after price impact
     /// 4. Settle with vault (handles fund transfer for wins)
     /// 5. Transfer loss to vault if trader lost
-    /// 6. Return collateral +/- PnL to trader
+    /// 6. Credit collateral +/- PnL to the trader's margin account
     pub fn close_position(
         env: Env,
-        trader: Address,
+        caller: Address,
         position_id: u64,
     ) -> Result<i128, NoetherError> {
         require_initialized(&env)?;
         require_not_paused(&env)?;
 
-        trader.require_auth();
-
         // Get position
         let mut position = get_position(&env, position_id)
             .ok_or(NoetherError::PositionNotFound)?;
 
-        // Verify ownership
-        if position.trader != trader {
-            return Err(NoetherError::NotPositionOwner);
-        }
+        // Verify ownership (or an operator allowed to close)
+        Self::require_trader_auth(
+            &env,
+            &caller,
+            &position.trader,
+            OperatorScope::Close,
+            NoetherError::NotPositionOwner,
+        )?;
+        let trader = position.trader.clone();
 
-        // Apply pending funding
-        Self::apply_funding_to_position(&env, &mut position)?;
+        let config = require_market(&env, &position.asset)?;
 
-        // Get current price
-        let current_price = Self::get_oracle_price(&env, &position.asset)?;
+        // Settle at the current price after price impact and remove the position
+        let (pnl, current_price, price_impact) =
+            Self::close_at_market(&env, &mut position, &config, "position_closed")?;
 
-        // Calculate PnL
-        let pnl = calculate_pnl(&position, current_price)?;
+        // Emit comprehensive event with full trade data for frontend history
+        env.events().publish(
+            (Symbol::new(&env, "position_closed"),),
+            (
+                position_id,
+                trader,
+                position.asset,
+                position.direction,
+                position.size,
+                position.entry_price,
+                current_price,  // exit_price
+                pnl,
+                position.accumulated_funding,
+                price_impact,
+            ),
+        );
 
-        // Calculate amount to return to trader
-        let to_trader = position.collateral + pnl - position.accumulated_funding;
+        extend_instance_ttl(&env);
 
-        // Settle with vault
-        // - If pnl > 0: Vault transfers profit to Market
-        // - If pnl < 0: Vault just updates accounting
-        let vault_address = get_vault(&env);
-        Self::settle_with_vault(&env, &vault_address, pnl)?;
+        Ok(pnl)
+    }
 
-        // Get token client for transfers
-        let usdc_token = get_usdc_token(&env);
-        let token_client = token::Client::new(&env, &usdc_token);
+    /// Close part of an existing position.
+    ///
+    /// # Arguments
+    /// * `caller` - Trader (must own position) or an operator approved to close
+    /// * `position_id` - ID of position to reduce
+    /// * `close_bps` - Fraction to close in basis points (1-9999)
+    ///
+    /// # Returns
+    /// PnL realized on the closed fraction
+    ///
+    /// # Flow
+    /// 1. Apply pending funding
+    /// 2. Split collateral, size and funding by `close_bps`
+    /// 3. Settle the closed slice with vault
+    /// 4. Shrink the stored position (leverage and liquidation price are unchanged)
+    ///
+    /// Attached SL/TP orders stay linked and act on the remaining size.
+    pub fn close_position_partial(
+        env: Env,
+        caller: Address,
+        position_id: u64,
+        close_bps: u32,
+    ) -> Result<i128, NoetherError> {
+        require_initialized(&env)?;
+        require_not_paused(&env)?;
 
-        // If trader lost, transfer the loss amount to Vault
-        // (Vault's settle_pnl already updated accounting, now transfer actual tokens)
-        if pnl < 0 {
-            let loss = -pnl;
-            token_client.transfer(&env.current_contract_address(), &vault_address, &loss);
+        // Full closes go through close_position
+        if close_bps == 0 || close_bps >= BASIS_POINTS {
+            return Err(NoetherError::InvalidParameter);
         }
 
-        // Transfer remaining funding to vault (if any)
-        if position.accumulated_funding > 0 {
-            token_client.transfer(
-                &env.current_contract_address(),
-                &vault_address,
-                &position.accumulated_funding,
-            );
-        }
+        // Get position
+        let mut position = get_position(&env, position_id)
+            .ok_or(NoetherError::PositionNotFound)?;
 
-        // Transfer to trader (if positive)
-        if to_trader > 0 {
-            token_client.transfer(&env.current_contract_address(), &trader, &to_trader);
-        }
+        // Verify ownership (or an operator allowed to close)
+        Self::require_trader_auth(
+            &env,
+            &caller,
+            &position.trader,
+            OperatorScope::Close,
+            NoetherError::NotPositionOwner,
+        )?;
+        let trader = position.trader.clone();
 
-        // Update market stats
-        match position.direction {
-            Direction::Long => {
-                let total = get_total_long_size(&env);
-                set_total_long_size(&env, total - position.size);
-            }
-            Direction::Short => {
-                let total = get_total_short_size(&env);
-                set_total_short_size(&env, total - position.size);
-            }
-        }
+        let config = require_market(&env, &position.asset)?;
 
-        // Delete position
-        delete_position(&env, position_id, &trader);
+        // Settle the closed slice and shrink the position
+        let (pnl, close_size, close_funding, current_price, price_impact) =
+            Self::reduce_position(&env, &mut position, &config, close_bps)?;
 
-        // Emit comprehensive event with full trade data for frontend history
         env.events().publish(
-            (Symbol::new(&env, "position_closed"),),
+            (Symbol::new(&env, "position_reduced"),),
             (
                 position_id,
                 trader,
                 position.asset,
                 position.direction,
-                position.size,
+                close_size,
                 position.entry_price,
                 current_price,  // exit_price
                 pnl,
-                position.accumulated_funding,
+                close_funding,
+                position.size,  // remaining size
+                price_impact,
             ),
         );
 
@@ -361,9 +421,205 @@ impl MarketContract {
         Ok(pnl)
     }
 
+    /// Add size to an existing position.
+    ///
+    /// # Arguments
+    /// * `caller` - Trader (must own position) or an operator approved to open
+    /// * `position_id` - ID of position to increase
+    /// * `extra_collateral` - Additional USDC collateral (7 decimals)
+    /// * `leverage` - Leverage applied to the extra collateral
+    ///
+    /// # Returns
+    /// The updated Position
+    ///
+    /// # Flow
+    /// 1. Apply pending funding on the existing size
+    /// 2. Check open interest caps and reserve Vault liquidity for the added size
+    /// 3. Average the entry price at the current price after price impact
+    /// 4. Charge trading fee on the added size
+    /// 5. Recompute leverage and liquidation price
+    pub fn increase_position(
+        env: Env,
+        caller: Address,
+        position_id: u64,
+        extra_collateral: i128,
+        leverage: u32,
+    ) -> Result<Position, NoetherError> {
+        require_initialized(&env)?;
+        require_not_paused(&env)?;
+
+        if extra_collateral <= 0 {
+            return Err(NoetherError::InvalidAmount);
+        }
+
+        // Get position
+        let mut position = get_position(&env, position_id)
+            .ok_or(NoetherError::PositionNotFound)?;
+
+        // Verify ownership (or an operator allowed to open)
+        let via_operator = Self::require_trader_auth(
+            &env,
+            &caller,
+            &position.trader,
+            OperatorScope::Open,
+            NoetherError::NotPositionOwner,
+        )?;
+        let trader = position.trader.clone();
+
+        let config = require_market(&env, &position.asset)?;
+
+        if leverage < 1 || leverage > config.max_leverage {
+            return Err(NoetherError::InvalidLeverage);
+        }
+
+        // Calculate added and resulting size
+        let added_size = calculate_position_size(extra_collateral, leverage);
+        let new_size = position.size + added_size;
+        if new_size > config.max_position_size {
+            return Err(NoetherError::PositionTooLarge);
+        }
+
+        // Check open interest caps and reserve Vault liquidity for the added size
+        Self::check_open_interest(&env, &position.asset, &config, position.direction, added_size)?;
+        let vault_address = get_vault(&env);
+        Self::reserve_vault_liquidity(&env, &vault_address, added_size)?;
+
+        // Settle funding on the old size before it changes
+        Self::apply_pending_fees(&env, &mut position)?;
+
+        // Fetch current price and apply price impact on the added size
+        let oracle_price = Self::get_oracle_price(&env, &position.asset)?;
+        let (current_price, price_impact) = Self::get_execution_price(
+            &env,
+            &position.asset,
+            &config,
+            oracle_price,
+            position.direction,
+            added_size,
+        )?;
+
+        // Calculate and deduct trading fee on the added notional
+        let fee = Self::trading_fee(&env, &trader, added_size, &config);
+        let new_collateral = position.collateral + extra_collateral - fee;
+
+        let effective_leverage = calculate_effective_leverage(new_size, new_collateral);
+        if effective_leverage > config.max_leverage {
+            return Err(NoetherError::InvalidLeverage);
+        }
+
+        // Draw collateral from the margin account, then the wallet
+        Self::debit_margin(&env, &trader, extra_collateral, via_operator);
+
+        // Update position
+        position.entry_price = calculate_average_entry_price(
+            position.size,
+            position.entry_price,
+            added_size,
+            current_price,
+        )?;
+        position.size = new_size;
+        position.collateral = new_collateral;
+        position.reserved += added_size;
+        position.leverage = effective_leverage;
+        position.liquidation_price =
+            calculate_equity_liquidation_price(&position, config.maintenance_margin_bps);
+
+        save_position(&env, &position);
+
+        // Update market stats
+        adjust_open_interest(&env, &position.asset, position.direction, added_size);
+
+        // Transfer fee to vault (insurance fund keeps its share)
+        Self::collect_trading_fee(&env, &vault_address, &config, &trader, fee);
+
+        env.events().publish(
+            (Symbol::new(&env, "position_increased"),),
+            (
+                position_id,
+                trader,
+                position.asset.clone(),
+                added_size,
+                current_price,
+                position.size,
+                position.entry_price,
+                position.leverage,
+                price_impact,
+            ),
+        );
+
+        extend_instance_ttl(&env);
+
+        Ok(position)
+    }
+
     /// Add collateral to an existing position.
-    /// Reduces liquidation risk.
+    /// Reduces liquidation risk. Callable by the trader or an operator
+    /// approved to add collateral.
     pub fn add_collateral(
+        env: Env,
+        caller: Address,
+        position_id: u64,
+        amount: i128,
+    ) -> Result<(), NoetherError> {
+        require_initialized(&env)?;
+        require_not_paused(&env)?;
+
+        if amount <= 0 {
+            return Err(NoetherError::InvalidAmount);
+        }
+
+        // Get position
+        let mut position = get_position(&env, position_id)
+            .ok_or(NoetherError::PositionNotFound)?;
+
+        let via_operator = Self::require_trader_auth(
+            &env,
+            &caller,
+            &position.trader,
+            OperatorScope::AddCollateral,
+            NoetherError::NotPositionOwner,
+        )?;
+
+        // Draw additional collateral from the margin account, then the wallet
+        Self::debit_margin(&env, &position.trader, amount, via_operator);
+
+        // Apply pending funding so the new liquidation price reflects it
+        let config = require_market(&env, &position.asset)?;
+        Self::apply_pending_fees(&env, &mut position)?;
+
+        // Update position
+        position.collateral += amount;
+
+        // Recalculate leverage and liquidation price from the new equity
+        position.leverage = calculate_effective_leverage(position.size, position.collateral);
+        position.liquidation_price =
+            calculate_equity_liquidation_price(&position, config.maintenance_margin_bps);
+
+        // Save updated position
+        save_position(&env, &position);
+
+        env.events().publish(
+            (Symbol::new(&env, "collateral_added"),),
+            (position_id, amount, position.collateral),
+        );
+
+        Ok(())
+    }
+
+    /// Withdraw excess collateral from a position.
+    ///
+    /// # Arguments
+    /// * `trader` - Address of the trader (must own position)
+    /// * `position_id` - ID of the position
+    /// * `amount` - USDC collateral to withdraw (7 decimals)
+    ///
+    /// # Flow
+    /// 1. Apply pending funding and borrow fees
+    /// 2. Value the position at the current oracle price
+    /// 3. Require the remaining equity to cover the initial margin, keep
+    ///    leverage <= max_leverage and stay above maintenance margin
+    /// 4. Recompute liquidation price and credit USDC to the margin account
+    pub fn remove_collateral(
         env: Env,
         trader: Address,
         position_id: u64,
@@ -386,34 +642,54 @@ impl MarketContract {
             return Err(NoetherError::NotPositionOwner);
         }
 
-        // Transfer additional collateral
-        let usdc_token = get_usdc_token(&env);
-        let token_client = token::Client::new(&env, &usdc_token);
-        token_client.transfer(&trader, &env.current_contract_address(), &amount);
+        let config = require_market(&env, &position.asset)?;
 
-        // Update position
-        position.collateral += amount;
+        let new_collateral = position.collateral - amount;
+        if new_collateral < config.min_collateral {
+            return Err(NoetherError::InsufficientCollateral);
+        }
 
-        // Recalculate liquidation price with new effective leverage
-        let config = get_config(&env);
-        let new_leverage = (position.size / position.collateral) as u32;
-        let effective_leverage = new_leverage.max(1).min(config.max_leverage);
+        // Apply pending funding
+        Self::apply_pending_fees(&env, &mut position)?;
 
-        position.liquidation_price = calculate_liquidation_price(
-            position.entry_price,
-            effective_leverage,
-            position.direction.clone(),
-            config.maintenance_margin_bps,
-        );
+        // Value the position at the current price, net of pending fees
+        let current_price = Self::get_oracle_price(&env, &position.asset)?;
+        let remaining_equity = calculate_position_value(&position, current_price)? - amount;
+
+        // Remaining equity must cover the initial margin
+        if !has_sufficient_margin(remaining_equity, position.size, config.initial_margin_bps) {
+            return Err(NoetherError::InsufficientMargin);
+        }
+
+        // Leverage on the remaining equity must stay within limits
+        let effective_leverage = calculate_effective_leverage(position.size, remaining_equity);
+        if effective_leverage == 0 || effective_leverage > config.max_leverage {
+            return Err(NoetherError::InvalidLeverage);
+        }
+
+        // Update position
+        position.collateral = new_collateral;
+        position.leverage = calculate_effective_leverage(position.size, new_collateral);
+        position.liquidation_price =
+            calculate_equity_liquidation_price(&position, config.maintenance_margin_bps);
+
+        // The withdrawal must not leave the position liquidatable
+        if is_liquidatable(&position, current_price, config.maintenance_margin_bps) {
+            return Err(NoetherError::InsufficientMargin);
+        }
 
-        // Save updated position
         save_position(&env, &position);
 
+        // Return collateral to the trader's margin account
+        Self::credit_margin(&env, &trader, amount);
+
         env.events().publish(
-            (Symbol::new(&env, "collateral_added"),),
+            (Symbol::new(&env, "collateral_removed"),),
             (position_id, amount, position.collateral),
         );
 
+        extend_instance_ttl(&env);
+
         Ok(())
     }
 
@@ -421,8 +697,9 @@ impl MarketContract {
     // Liquidation Functions
     // ═══════════════════════════════════════════════════════════════════════
 
-    /// Liquidate an underwater position.
+    /// Liquidate an underwater position, partially when possible.
     /// Callable by anyone (keeper). Keeper receives liquidation reward.
+    /// Funding owed is included when checking the position's margin.
     ///
     /// # Arguments
     /// * `keeper` - Address executing the liquidation (receives reward)
@@ -434,9 +711,11 @@ impl MarketContract {
     /// # Flow
     /// 1. Verify position is liquidatable
     /// 2. Calculate remaining equity and keeper reward
-    /// 3. Pay keeper their reward
-    /// 4. Transfer remaining collateral to Vault
-    /// 5. Update Vault accounting
+    /// 3. Close just enough size to restore maintenance margin + buffer,
+    ///    with a pro-rated keeper reward (see `partially_liquidate`)
+    /// 4. Otherwise pay the keeper the full reward
+    /// 5. Transfer remaining collateral to Vault
+    /// 6. Update Vault accounting
     pub fn liquidate(
         env: Env,
         keeper: Address,
@@ -448,108 +727,120 @@ impl MarketContract {
         keeper.require_auth();
 
         // Get position
-        let position = get_position(&env, position_id)
+        let mut position = get_position(&env, position_id)
             .ok_or(NoetherError::PositionNotFound)?;
 
+        // Cross-margin positions are only liquidated with their account
+        if get_margin_mode(&env, &position.trader) == MarginMode::Cross {
+            return Err(NoetherError::NotLiquidatable);
+        }
+
+        let config = require_market(&env, &position.asset)?;
+
+        // Apply pending funding
+        Self::apply_pending_fees(&env, &mut position)?;
+
         // Get current price
         let current_price = Self::get_oracle_price(&env, &position.asset)?;
 
-        // Check if liquidatable
-        if !should_liquidate(&position, current_price) {
+        // Check if liquidatable (live equity vs maintenance margin)
+        if !is_liquidatable(&position, current_price, config.maintenance_margin_bps) {
             return Err(NoetherError::NotLiquidatable);
         }
 
-        let config = get_config(&env);
+        let reward = Self::liquidate_at_price(&env, &keeper, position, &config, current_price)?;
 
-        // Calculate PnL
-        let pnl = calculate_pnl(&position, current_price)?;
+        extend_instance_ttl(&env);
 
-        // Calculate remaining collateral after PnL and funding
-        let remaining = position.collateral + pnl - position.accumulated_funding;
+        Ok(reward)
+    }
 
-        // Calculate keeper reward (only from remaining equity, if positive)
-        let keeper_reward = if remaining > 0 {
-            calculate_keeper_reward(remaining, config.liquidation_fee_bps)
-        } else {
-            0
-        };
+    /// Liquidate several positions in one call (keeper).
+    /// Each asset's price is fetched once. Missing, healthy or cross-margin
+    /// positions are skipped instead of failing the batch. The skip is
+    /// decided before anything is written, so a liquidation that still
+    /// fails fails the whole batch rather than leaving partial state.
+    ///
+    /// # Arguments
+    /// * `keeper` - Address executing the liquidations (receives rewards)
+    /// * `position_ids` - IDs of positions to liquidate
+    ///
+    /// # Returns
+    /// One result per ID, in order, with the keeper reward earned
+    pub fn liquidate_batch(
+        env: Env,
+        keeper: Address,
+        position_ids: Vec<u64>,
+    ) -> Result<Vec<BatchResult>, NoetherError> {
+        require_initialized(&env)?;
+        // Note: Liquidations should work even when paused for safety
 
-        // Ensure keeper_reward doesn't exceed position collateral
-        let actual_keeper_reward = if keeper_reward > position.collateral {
-            position.collateral / 10 // Cap at 10% of collateral as safety
-        } else {
-            keeper_reward
-        };
+        keeper.require_auth();
 
-        // Calculate what goes to Vault (everything except keeper reward)
-        let vault_receives = if position.collateral > actual_keeper_reward {
-            position.collateral - actual_keeper_reward
-        } else {
-            0
-        };
+        let mut prices = Map::new(&env);
+        let mut results = Vec::new(&env);
+
+        for position_id in position_ids.iter() {
+            let mut reward = None;
+
+            if let Some(mut position) = get_position(&env, position_id)
+                .filter(|position| get_margin_mode(&env, &position.trader) == MarginMode::Isolated)
+            {
+                let config = get_market_config(&env, &position.asset);
+                let price = Self::get_cached_price(&env, &mut prices, &position.asset);
+
+                if let (Some(config), Some(current_price)) = (config, price) {
+                    let liquidatable = Self::with_pending_fees(&env, &position)
+                        .map(|live| {
+                            is_liquidatable(&live, current_price, config.maintenance_margin_bps)
+                        })
+                        .unwrap_or(false);
+
+                    if liquidatable {
+                        Self::apply_pending_fees(&env, &mut position)?;
+                        reward = Some(Self::liquidate_at_price(
+                            &env,
+                            &keeper,
+                            position,
+                            &config,
+                            current_price,
+                        )?);
+                    }
+                }
+            }
 
-        // Get addresses and token client
-        let vault_address = get_vault(&env);
-        let usdc_token = get_usdc_token(&env);
-        let token_client = token::Client::new(&env, &usdc_token);
-
-        // Settle with vault - pass the amount Vault is receiving (as negative pnl)
-        // This ensures Vault's total_usdc accounting matches actual token receipt
-        if vault_receives > 0 {
-            Self::settle_with_vault(&env, &vault_address, -vault_receives)?;
-            token_client.transfer(&env.current_contract_address(), &vault_address, &vault_receives);
-        }
-
-        // Pay keeper reward
-        if actual_keeper_reward > 0 {
-            token_client.transfer(&env.current_contract_address(), &keeper, &actual_keeper_reward);
-        }
-
-        // Update market stats
-        match position.direction {
-            Direction::Long => {
-                let total = get_total_long_size(&env);
-                set_total_long_size(&env, total - position.size);
-            }
-            Direction::Short => {
-                let total = get_total_short_size(&env);
-                set_total_short_size(&env, total - position.size);
-            }
-        }
-
-        // Delete position
-        delete_position(&env, position_id, &position.trader);
-
-        // Emit comprehensive event with full trade data for frontend history
-        env.events().publish(
-            (Symbol::new(&env, "position_liquidated"),),
-            (
-                position_id,
-                position.trader,
-                position.asset,
-                position.direction,
-                position.size,
-                position.entry_price,
-                current_price,
-                pnl,
-                keeper.clone(),
-                actual_keeper_reward,
-            ),
-        );
+            results.push_back(BatchResult {
+                id: position_id,
+                executed: reward.is_some(),
+                reward: reward.unwrap_or(0),
+            });
+        }
 
         extend_instance_ttl(&env);
 
-        Ok(actual_keeper_reward)
+        Ok(results)
     }
 
-    /// Check if a position can be liquidated.
+    /// Get the insurance fund balance (USDC held by the market).
+    pub fn get_insurance_fund(env: Env) -> i128 {
+        get_insurance_fund(&env)
+    }
+
+    /// Check if a position can be liquidated (including funding owed).
+    /// Always false for cross-margin positions; see `get_account_health`.
     pub fn is_liquidatable(env: Env, position_id: u64) -> Result<bool, NoetherError> {
         let position = get_position(&env, position_id)
             .ok_or(NoetherError::PositionNotFound)?;
 
+        if get_margin_mode(&env, &position.trader) == MarginMode::Cross {
+            return Ok(false);
+        }
+
+        let config = require_market(&env, &position.asset)?;
+        let position = Self::with_pending_fees(&env, &position)?;
         let current_price = Self::get_oracle_price(&env, &position.asset)?;
 
-        Ok(should_liquidate(&position, current_price))
+        Ok(is_liquidatable(&position, current_price, config.maintenance_margin_bps))
     }
 
     /// Get all liquidatable positions (for keeper).
@@ -557,14 +848,24 @@ impl MarketContract {
     pub fn get_liquidatable_positions(env: Env, asset: Symbol) -> Result<Vec<u64>, NoetherError> {
         require_initialized(&env)?;
 
+        let config = require_market(&env, &asset)?;
         let current_price = Self::get_oracle_price(&env, &asset)?;
+        let indices = Self::current_funding_indices(&env, &asset)?;
+        let borrow_index = Self::current_borrow_index(&env, &asset)?;
         let all_positions = get_all_position_ids(&env);
         let mut liquidatable = Vec::new(&env);
 
         for i in 0..all_positions.len() {
             let pos_id = all_positions.get(i).unwrap();
-            if let Some(position) = get_position(&env, pos_id) {
-                if position.asset == asset && should_liquidate(&position, current_price) {
+            if let Some(mut position) = get_position(&env, pos_id) {
+                if position.asset != asset
+                    || get_margin_mode(&env, &position.trader) == MarginMode::Cross
+                {
+                    continue;
+                }
+                Self::settle_position_funding(&env, &mut position, indices);
+                Self::settle_position_borrow(&mut position, borrow_index);
+                if is_liquidatable(&position, current_price, config.maintenance_margin_bps) {
                     liquidatable.push_back(pos_id);
                 }
             }
@@ -573,33 +874,296 @@ impl MarketContract {
         Ok(liquidatable)
     }
 
+    /// Liquidate a cross-margin account whose equity has fallen to its summed
+    /// maintenance margin (keeper). Also works while paused.
+    ///
+    /// # Arguments
+    /// * `keeper` - Address executing the liquidation (receives rewards)
+    /// * `trader` - Owner of the cross-margin account
+    ///
+    /// # Returns
+    /// Total keeper reward
+    ///
+    /// # Flow
+    /// 1. Value the account: margin balance + position values vs summed maintenance margin
+    /// 2. Close the position with the lowest value, covering its loss and the
+    ///    keeper reward from the margin balance before the insurance fund
+    /// 3. Repeat until the account is healthy or has no positions left
+    pub fn liquidate_account(
+        env: Env,
+        keeper: Address,
+        trader: Address,
+    ) -> Result<i128, NoetherError> {
+        require_initialized(&env)?;
+        // Note: Liquidations should work even when paused for safety
+
+        keeper.require_auth();
+
+        if get_margin_mode(&env, &trader) != MarginMode::Cross {
+            return Err(NoetherError::NotCrossMargin);
+        }
+
+        let (mut health, mut positions, mut prices) = Self::value_account(&env, &trader)?;
+        if !health.liquidatable {
+            return Err(NoetherError::NotLiquidatable);
+        }
+
+        let mut total_reward = 0;
+        let mut closed = 0u32;
+
+        while health.liquidatable {
+            // Worst position first
+            let mut worst = 0;
+            let mut worst_value = i128::MAX;
+            for i in 0..positions.len() {
+                let value =
+                    calculate_position_value(&positions.get(i).unwrap(), prices.get(i).unwrap())?;
+                if value < worst_value {
+                    worst = i;
+                    worst_value = value;
+                }
+            }
+
+            let mut position = get_position(&env, positions.get(worst).unwrap().id)
+                .ok_or(NoetherError::PositionNotFound)?;
+            let config = require_market(&env, &position.asset)?;
+            Self::apply_pending_fees(&env, &mut position)?;
+
+            total_reward += Self::liquidate_cross_position(
+                &env,
+                &keeper,
+                position,
+                &config,
+                prices.get(worst).unwrap(),
+            )?;
+            closed += 1;
+
+            (health, positions, prices) = Self::value_account(&env, &trader)?;
+        }
+
+        env.events().publish(
+            (Symbol::new(&env, "account_liquidated"),),
+            (trader, keeper, closed, health.equity, health.maintenance_margin, total_reward),
+        );
+
+        extend_instance_ttl(&env);
+
+        Ok(total_reward)
+    }
+
+    /// Get the live health of a trader's account at current prices
+    /// (margin balance + position values vs summed margin requirements).
+    pub fn get_account_health(env: Env, trader: Address) -> Result<AccountHealth, NoetherError> {
+        require_initialized(&env)?;
+
+        let (health, _, _) = Self::value_account(&env, &trader)?;
+        Ok(health)
+    }
+
+    /// Get all cross-margin traders whose account can be liquidated (for keeper).
+    pub fn get_liquidatable_accounts(env: Env) -> Result<Vec<Address>, NoetherError> {
+        require_initialized(&env)?;
+
+        let all_positions = get_all_position_ids(&env);
+        let mut checked: Map<Address, bool> = Map::new(&env);
+        let mut liquidatable = Vec::new(&env);
+
+        for pos_id in all_positions.iter() {
+            if let Some(position) = get_position(&env, pos_id) {
+                let trader = position.trader;
+                if checked.contains_key(trader.clone())
+                    || get_margin_mode(&env, &trader) != MarginMode::Cross
+                {
+                    continue;
+                }
+                checked.set(trader.clone(), true);
+
+                let (health, _, _) = Self::value_account(&env, &trader)?;
+                if health.liquidatable {
+                    liquidatable.push_back(trader);
+                }
+            }
+        }
+
+        Ok(liquidatable)
+    }
+
+    // ═══════════════════════════════════════════════════════════════════════
+    // Auto-Deleveraging Functions
+    // ═══════════════════════════════════════════════════════════════════════
+
+    /// Partially close the most profitable positions of an asset when trader
+    /// PnL exceeds the asset's ADL threshold (share of vault USDC).
+    /// Callable by anyone (keeper), also while paused.
+    ///
+    /// # Arguments
+    /// * `keeper` - Address of the keeper
+    /// * `asset` - Asset to deleverage
+    /// * `position_ids` - Positions to deleverage, highest ADL rank first
+    ///
+    /// # Returns
+    /// Total PnL realized by the deleveraged positions
+    ///
+    /// # Flow
+    /// 1. Sum unrealized PnL net of pending fees and rank the profitable positions
+    /// 2. Require PnL above the threshold and check every ID before closing any
+    /// 3. Close just enough of each position to bring PnL back to the threshold
+    ///
+    /// IDs that no longer exist or are not within the top `position_ids.len()`
+    /// are skipped; if none is eligible the call fails with `NotAdlEligible`.
+    /// A position whose remainder would fall below `min_collateral` is closed in full.
+    pub fn auto_deleverage(
+        env: Env,
+        keeper: Address,
+        asset: Symbol,
+        position_ids: Vec<u64>,
+    ) -> Result<i128, NoetherError> {
+        require_initialized(&env)?;
+        // Note: ADL should work even when paused, like liquidations
+
+        keeper.require_auth();
+
+        if position_ids.is_empty() {
+            return Err(NoetherError::InvalidParameter);
+        }
+
+        let config = require_market(&env, &asset)?;
+        let current_price = Self::get_oracle_price(&env, &asset)?;
+
+        // Rank profitable positions and measure trader PnL on the asset
+        let (exposure, scores) = Self::get_adl_scores(&env, &asset, current_price)?;
+
+        let vault_usdc: i128 = env.invoke_contract(
+            &get_vault(&env),
+            &Symbol::new(&env, "get_total_usdc"),
+            Vec::new(&env),
+        );
+        let threshold = calculate_adl_threshold(vault_usdc, config.adl_threshold_bps);
+        if exposure <= threshold {
+            return Err(NoetherError::AdlNotRequired);
+        }
+
+        // Check every ID before closing any. Positions closed since the keeper
+        // ranked them are skipped; only the highest ranked can be deleveraged.
+        let mut eligible = Vec::new(&env);
+        for position_id in position_ids.iter() {
+            let Some(position) = get_position(&env, position_id) else {
+                continue;
+            };
+            if position.asset != asset {
+                return Err(NoetherError::InvalidParameter);
+            }
+
+            let pnl = Self::adl_pnl(&env, &position, current_price)?;
+            let score = calculate_adl_score(pnl, position.collateral);
+            if score > 0 && Self::adl_rank(&scores, score) <= position_ids.len() {
+                eligible.push_back(position_id);
+            }
+        }
+        if eligible.is_empty() {
+            return Err(NoetherError::NotAdlEligible);
+        }
+
+        let mut excess = exposure - threshold;
+        let mut total_pnl = 0i128;
+
+        for position_id in eligible.iter() {
+            if excess <= 0 {
+                break;
+            }
+
+            // Skip repeated IDs already closed in full
+            let Some(mut position) = get_position(&env, position_id) else {
+                continue;
+            };
+
+            // Close just enough to cover the excess
+            let pnl = Self::adl_pnl(&env, &position, current_price)?;
+            let close_bps = calculate_adl_close_bps(pnl, excess);
+            if close_bps == 0 {
+                continue;
+            }
+
+            // Close in full when the remainder would fall below min_collateral
+            let (_, _, remaining_collateral, _) = calculate_partial_close(&position, close_bps);
+            let (realized_pnl, close_size, exit_price) =
+                if close_bps >= BASIS_POINTS || remaining_collateral < config.min_collateral {
+                    let close_size = position.size;
+                    let (realized_pnl, exit_price, _) = Self::close_at_market(
+                        &env,
+                        &mut position,
+                        &config,
+                        "position_deleveraged",
+                    )?;
+                    position.size = 0;
+                    (realized_pnl, close_size, exit_price)
+                } else {
+                    let (realized_pnl, close_size, _, exit_price, _) =
+                        Self::reduce_position(&env, &mut position, &config, close_bps)?;
+                    (realized_pnl, close_size, exit_price)
+                };
+
+            excess -= realized_pnl;
+            total_pnl += realized_pnl;
+
+            env.events().publish(
+                (Symbol::new(&env, "position_deleveraged"),),
+                (
+                    position_id,
+                    position.trader.clone(),
+                    asset.clone(),
+                    close_size,
+                    exit_price,
+                    realized_pnl,
+                    position.size,  // remaining size
+                    keeper.clone(),
+                ),
+            );
+        }
+
+        extend_instance_ttl(&env);
+
+        Ok(total_pnl)
+    }
+
+    /// Get the ADL rank of a position (1 = deleveraged first).
+    /// Returns 0 if the position is not in profit and cannot be deleveraged.
+    pub fn get_adl_rank(env: Env, position_id: u64) -> Result<u32, NoetherError> {
+        let position = get_position(&env, position_id)
+            .ok_or(NoetherError::PositionNotFound)?;
+
+        let current_price = Self::get_oracle_price(&env, &position.asset)?;
+        let pnl = Self::adl_pnl(&env, &position, current_price)?;
+        let score = calculate_adl_score(pnl, position.collateral);
+        if score == 0 {
+            return Ok(0);
+        }
+
+        let (_, scores) = Self::get_adl_scores(&env, &position.asset, current_price)?;
+        Ok(Self::adl_rank(&scores, score))
+    }
+
     // ═══════════════════════════════════════════════════════════════════════
     // Funding Rate Functions
     // ═══════════════════════════════════════════════════════════════════════
 
-    /// Apply funding to all positions (can be called periodically).
+    /// Accrue funding for an asset up to now (can be called periodically).
     /// Funding balances long/short interest:
     /// - If more longs than shorts: longs pay shorts
     /// - If more shorts than longs: shorts pay longs
-    pub fn apply_funding(env: Env) -> Result<(), NoetherError> {
+    ///
+    /// Funding accrues per second into the cumulative indices on every
+    /// position change; calling this only checkpoints the indices and
+    /// refreshes the reference rate.
+    pub fn apply_funding(env: Env, asset: Symbol) -> Result<(), NoetherError> {
         require_initialized(&env)?;
 
-        let current_time = env.ledger().timestamp();
-        let last_funding = get_last_funding_time(&env);
-
-        // Require at least 1 hour between funding applications
-        if current_time < last_funding + 3600 {
-            return Err(NoetherError::FundingIntervalNotElapsed);
-        }
+        let config = require_market(&env, &asset)?;
 
-        let hours_elapsed = (current_time - last_funding) / 3600;
-        if hours_elapsed == 0 {
-            return Ok(());
-        }
+        let (long_index, short_index) = Self::accrue_funding(&env, &asset)?;
 
-        let config = get_config(&env);
-        let total_long = get_total_long_size(&env);
-        let total_short = get_total_short_size(&env);
+        let total_long = get_total_long_size(&env, &asset);
+        let total_short = get_total_short_size(&env, &asset);
 
         // Calculate funding rate
         let funding_rate = calculate_funding_rate(
@@ -609,24 +1173,23 @@ impl MarketContract {
         );
 
         // Store for reference
-        set_current_funding_rate(&env, funding_rate);
-        set_last_funding_time(&env, current_time);
+        set_current_funding_rate(&env, &asset, funding_rate);
 
         env.events().publish(
             (Symbol::new(&env, "funding_applied"),),
-            (funding_rate, hours_elapsed),
+            (asset, funding_rate, long_index, short_index),
         );
 
         Ok(())
     }
 
-    /// Get current funding rate.
-    pub fn get_funding_rate(env: Env) -> i128 {
-        let config = get_config(&env);
-        let total_long = get_total_long_size(&env);
-        let total_short = get_total_short_size(&env);
+    /// Get current funding rate for an asset.
+    pub fn get_funding_rate(env: Env, asset: Symbol) -> Result<i128, NoetherError> {
+        let config = require_market(&env, &asset)?;
+        let total_long = get_total_long_size(&env, &asset);
+        let total_short = get_total_short_size(&env, &asset);
 
-        calculate_funding_rate(total_long, total_short, config.base_funding_rate_bps)
+        Ok(calculate_funding_rate(total_long, total_short, config.base_funding_rate_bps))
     }
 
     // ═══════════════════════════════════════════════════════════════════════
@@ -634,35 +1197,101 @@ impl MarketContract {
     // ═══════════════════════════════════════════════════════════════════════
 
     /// Get a position by ID.
+    /// Funding and borrow fees are accrued to now and the liquidation price
+    /// is derived from the resulting equity.
     pub fn get_position(env: Env, position_id: u64) -> Option<Position> {
         get_position(&env, position_id)
+            .map(|position| Self::with_live_liquidation_price(&env, &position).unwrap_or(position))
     }
 
-    /// Get all positions for a trader.
+    /// Get all positions for a trader (with live fees and liquidation prices).
     pub fn get_positions(env: Env, trader: Address) -> Vec<Position> {
-        get_trader_positions(&env, &trader)
+        let stored = get_trader_positions(&env, &trader);
+        let mut positions = Vec::new(&env);
+        for position in stored.iter() {
+            positions.push_back(
+                Self::with_live_liquidation_price(&env, &position).unwrap_or(position),
+            );
+        }
+        positions
+    }
+
+    /// Get the price at which a position's live equity reaches maintenance margin.
+    pub fn get_liquidation_price(env: Env, position_id: u64) -> Result<i128, NoetherError> {
+        let position = get_position(&env, position_id)
+            .ok_or(NoetherError::PositionNotFound)?;
+
+        Ok(Self::with_live_liquidation_price(&env, &position)?.liquidation_price)
     }
 
-    /// Get position PnL at current price.
+    /// Get position PnL at current price, net of funding and borrow fees owed.
     pub fn get_position_pnl(env: Env, position_id: u64) -> Result<i128, NoetherError> {
         let position = get_position(&env, position_id)
             .ok_or(NoetherError::PositionNotFound)?;
 
+        let position = Self::with_pending_fees(&env, &position)?;
         let current_price = Self::get_oracle_price(&env, &position.asset)?;
-        calculate_pnl(&position, current_price)
-    }
+        let pnl = calculate_pnl(&position, current_price)?;
 
-    /// Get market statistics.
-    pub fn get_market_stats(env: Env) -> MarketStats {
-        let funding_rate = Self::get_funding_rate(env.clone());
+        Ok(pnl - position.accumulated_funding - position.accumulated_borrow_fee)
+    }
 
-        MarketStats {
-            total_long_size: get_total_long_size(&env),
-            total_short_size: get_total_short_size(&env),
-            open_position_count: get_position_count(&env),
+    /// Get market statistics for an asset.
+    pub fn get_market_stats(env: Env, asset: Symbol) -> Result<MarketStats, NoetherError> {
+        let funding_rate = Self::get_funding_rate(env.clone(), asset.clone())?;
+        let (cumulative_funding_long, cumulative_funding_short) =
+            Self::current_funding_indices(&env, &asset)?;
+        let config = require_market(&env, &asset)?;
+        let (utilization_bps, borrow_rate_bps) = Self::current_borrow_rate(&env, &config);
+        let (remaining_long_oi, remaining_short_oi, remaining_global_oi) =
+            Self::remaining_open_interest(&env, &asset, &config);
+
+        Ok(MarketStats {
+            total_long_size: get_total_long_size(&env, &asset),
+            total_short_size: get_total_short_size(&env, &asset),
+            open_position_count: get_position_count(&env, &asset),
             funding_rate,
-            last_funding_time: get_last_funding_time(&env),
+            last_funding_time: get_last_funding_time(&env, &asset),
+            cumulative_funding_long,
+            cumulative_funding_short,
+            utilization_bps,
+            borrow_rate_bps,
+            cumulative_borrow: Self::current_borrow_index(&env, &asset)?,
+            remaining_long_oi,
+            remaining_short_oi,
+            remaining_global_oi,
+        })
+    }
+
+    /// Get the funding ledger for an asset.
+    /// Shows realized funding settled with the vault plus funding still owed
+    /// by open positions, and the net amount owed to (or by) the pool.
+    pub fn get_funding_ledger(env: Env, asset: Symbol) -> Result<FundingLedger, NoetherError> {
+        let indices = Self::current_funding_indices(&env, &asset)?;
+        let (total_paid, total_received) = get_realized_funding(&env, &asset);
+
+        let all_positions = get_all_position_ids(&env);
+        let mut pending = 0i128;
+        for i in 0..all_positions.len() {
+            if let Some(mut position) = get_position(&env, all_positions.get(i).unwrap()) {
+                if position.asset == asset {
+                    Self::settle_position_funding(&env, &mut position, indices);
+                    pending += position.accumulated_funding;
+                }
+            }
         }
+
+        Ok(FundingLedger {
+            total_paid,
+            total_received,
+            pending,
+            net: total_paid - total_received + pending,
+        })
+    }
+
+    /// Get net funding owed to the pool for an asset (negative = pool owes traders).
+    pub fn get_net_funding(env: Env, asset: Symbol) -> Result<i128, NoetherError> {
+        Ok(Self::get_funding_ledger(env, asset)?.net)
     }
 
     /// Get all position IDs (for keeper iteration).
@@ -675,11 +1304,47 @@ impl MarketContract {
         Self::get_oracle_price(&env, &asset)
     }
 
-    /// Get market configuration.
+    /// Get protocol-wide default configuration.
     pub fn get_config(env: Env) -> MarketConfig {
         get_config(&env)
     }
 
+    /// Get the configuration of a listed market.
+    pub fn get_market(env: Env, asset: Symbol) -> Result<MarketConfig, NoetherError> {
+        require_market(&env, &asset)
+    }
+
+    /// Get the symbols of all listed markets.
+    pub fn get_markets(env: Env) -> Vec<Symbol> {
+        get_market_assets(&env)
+    }
+
+    /// Get the configuration of a listed collateral token.
+    pub fn get_collateral_token(env: Env, token: Address) -> Result<CollateralConfig, NoetherError> {
+        require_collateral(&env, &token)
+    }
+
+    /// Get the amount of a collateral token seized from traders and not yet swept.
+    pub fn get_seized_collateral(env: Env, token: Address) -> i128 {
+        get_seized_collateral(&env, &token)
+    }
+
+    /// Get the bad debt LPs absorbed that sweeping seized collateral repays first.
+    pub fn get_unrecovered_bad_debt(env: Env) -> i128 {
+        get_unrecovered_bad_debt(&env)
+    }
+
+    /// Get the configurations of all listed collateral tokens.
+    pub fn get_collateral_tokens(env: Env) -> Vec<CollateralConfig> {
+        let mut configs = Vec::new(&env);
+        for token in get_collateral_assets(&env).iter() {
+            if let Some(config) = get_collateral_config(&env, &token) {
+                configs.push_back(config);
+            }
+        }
+        configs
+    }
+
     /// Get vault address.
     pub fn get_vault(env: Env) -> Result<Address, NoetherError> {
         require_initialized(&env)?;
@@ -704,14 +1369,11 @@ impl MarketContract {
     // Admin Functions
     // ═══════════════════════════════════════════════════════════════════════
 
-    /// Update market configuration.
+    /// Update protocol-wide default configuration.
     pub fn update_config(env: Env, config: MarketConfig) -> Result<(), NoetherError> {
         require_admin(&env)?;
 
-        // Validate config
-        if config.max_leverage < 1 || config.max_leverage > 100 {
-            return Err(NoetherError::InvalidParameter);
-        }
+        Self::validate_config(&config)?;
 
         set_config(&env, &config);
 
@@ -723,71 +1385,325 @@ impl MarketContract {
         Ok(())
     }
 
-    /// Update oracle adapter address.
-    pub fn set_oracle_adapter(env: Env, oracle: Address) -> Result<(), NoetherError> {
+    /// List a new asset for trading.
+    ///
+    /// # Arguments
+    /// * `asset` - Asset symbol (e.g., "BTC", "ETH", "XLM")
+    /// * `config` - Leverage, margin, fee and size parameters for this asset
+    pub fn add_market(env: Env, asset: Symbol, config: MarketConfig) -> Result<(), NoetherError> {
         require_admin(&env)?;
-        let old = get_oracle_adapter(&env);
-        set_oracle_adapter(&env, &oracle);
+
+        if get_market_config(&env, &asset).is_some() {
+            return Err(NoetherError::MarketAlreadyExists);
+        }
+
+        Self::validate_config(&config)?;
+
+        set_market_config(&env, &asset, &config);
+        add_market_asset(&env, &asset);
+        set_last_funding_time(&env, &asset, env.ledger().timestamp());
+        set_cumulative_borrow(&env, &asset, 0, env.ledger().timestamp());
+
+        extend_instance_ttl(&env);
 
         env.events().publish(
-            (Symbol::new(&env, "oracle_updated"),),
-            (old, oracle),
+            (Symbol::new(&env, "market_added"),),
+            (asset,),
         );
 
         Ok(())
     }
 
-    /// Update vault address.
-    pub fn set_vault(env: Env, vault: Address) -> Result<(), NoetherError> {
+    /// Update the configuration of a listed asset.
+    /// Existing positions keep their stored liquidation price.
+    /// Funding and borrow fees accrued so far are settled at the old rates.
+    pub fn update_market(env: Env, asset: Symbol, config: MarketConfig) -> Result<(), NoetherError> {
         require_admin(&env)?;
-        let old = get_vault(&env);
-        set_vault(&env, &vault);
+        require_market(&env, &asset)?;
+
+        Self::validate_config(&config)?;
+
+        Self::accrue_funding(&env, &asset)?;
+        Self::accrue_borrow(&env, &asset)?;
+
+        set_market_config(&env, &asset, &config);
 
         env.events().publish(
-            (Symbol::new(&env, "vault_updated"),),
-            (old, vault),
+            (Symbol::new(&env, "market_updated"),),
+            (asset,),
         );
 
         Ok(())
     }
 
-    /// Pause the market (emergency).
-    pub fn pause(env: Env) -> Result<(), NoetherError> {
+    /// List a non-USDC token as margin collateral.
+    ///
+    /// # Arguments
+    /// * `config` - Token, oracle symbol, haircut and deposit cap
+    pub fn add_collateral_token(env: Env, config: CollateralConfig) -> Result<(), NoetherError> {
         require_admin(&env)?;
-        set_paused(&env, true);
+
+        if get_collateral_config(&env, &config.token).is_some() {
+            return Err(NoetherError::CollateralAlreadyListed);
+        }
+
+        Self::validate_collateral_config(&env, &config)?;
+
+        set_collateral_config(&env, &config.token, &config);
+        add_collateral_asset(&env, &config.token);
+
+        extend_instance_ttl(&env);
 
         env.events().publish(
-            (Symbol::new(&env, "paused"),),
-            (),
+            (Symbol::new(&env, "collateral_token_listed"),),
+            (config.token, config.oracle_symbol, config.haircut_bps, config.deposit_cap),
         );
 
         Ok(())
     }
 
-    /// Unpause the market.
-    pub fn unpause(env: Env) -> Result<(), NoetherError> {
+    /// Update the configuration of a listed collateral token.
+    /// A lower deposit cap only blocks new deposits; existing balances stay.
+    pub fn update_collateral_token(env: Env, config: CollateralConfig) -> Result<(), NoetherError> {
         require_admin(&env)?;
-        set_paused(&env, false);
+        require_collateral(&env, &config.token)?;
+
+        Self::validate_collateral_config(&env, &config)?;
+
+        set_collateral_config(&env, &config.token, &config);
 
         env.events().publish(
-            (Symbol::new(&env, "unpaused"),),
-            (),
+            (Symbol::new(&env, "collateral_updated"),),
+            (config.token, config.oracle_symbol, config.haircut_bps, config.deposit_cap),
         );
 
         Ok(())
     }
 
-    /// Transfer admin role.
-    pub fn set_admin(env: Env, new_admin: Address) -> Result<(), NoetherError> {
+    /// Convert seized collateral tokens to USDC by selling them to the admin
+    /// at the oracle price (no haircut).
+    ///
+    /// # Flow
+    /// 1. Admin pays USDC for the tokens at the oracle price
+    /// 2. The proceeds repay LPs the bad debt the insurance fund could not cover
+    /// 3. The rest replenishes the insurance fund, which paid the remaining debt
+    ///
+    /// # Returns
+    /// Seized amount of the token still held by the market
+    pub fn sweep_seized_collateral(
+        env: Env,
+        token: Address,
+        amount: i128,
+    ) -> Result<i128, NoetherError> {
         require_admin(&env)?;
-        new_admin.require_auth();
 
-        let old = get_admin(&env);
-        set_admin(&env, &new_admin);
+        let seized = get_seized_collateral(&env, &token);
+        if amount <= 0 || amount > seized {
+            return Err(NoetherError::InvalidAmount);
+        }
+
+        let config = require_collateral(&env, &token)?;
+        let price = Self::get_oracle_price(&env, &config.oracle_symbol)?;
+        let proceeds = calculate_haircut_value(amount, price, 0);
+        if proceeds <= 0 {
+            return Err(NoetherError::InvalidAmount);
+        }
+
+        set_seized_collateral(&env, &token, seized - amount);
+
+        // Swap the tokens for USDC with the admin
+        let admin = get_admin(&env);
+        let market = env.current_contract_address();
+        let usdc_client = token::Client::new(&env, &get_usdc_token(&env));
+        usdc_client.transfer(&admin, &market, &proceeds);
+        token::Client::new(&env, &token).transfer(&market, &admin, &amount);
+
+        // LPs are repaid first, as additional trader loss
+        let unrecovered = get_unrecovered_bad_debt(&env);
+        let to_vault = proceeds.min(unrecovered);
+        if to_vault > 0 {
+            let vault_address = get_vault(&env);
+            Self::settle_with_vault(&env, &vault_address, -to_vault)?;
+            usdc_client.transfer(&market, &vault_address, &to_vault);
+            set_unrecovered_bad_debt(&env, unrecovered - to_vault);
+        }
+
+        let to_fund = proceeds - to_vault;
+        Self::deposit_insurance(&env, Symbol::new(&env, "seized_collateral"), to_fund);
+
+        extend_instance_ttl(&env);
 
         env.events().publish(
-            (Symbol::new(&env, "admin_updated"),),
-            (old, new_admin),
+            (Symbol::new(&env, "seized_collateral_swept"),),
+            (token, amount, price, to_vault, to_fund, seized - amount),
+        );
+
+        Ok(seized - amount)
+    }
+
+    /// Set the referral tiers. Codes point at a tier by index; a code whose
+    /// tier is missing earns no discount or rebate.
+    ///
+    /// # Arguments
+    /// * `tiers` - Trader discount and referrer rebate of each tier
+    pub fn set_referral_tiers(env: Env, tiers: Vec<ReferralTier>) -> Result<(), NoetherError> {
+        require_admin(&env)?;
+
+        for tier in tiers.iter() {
+            if !is_valid_tier(tier.discount_bps, tier.rebate_bps) {
+                return Err(NoetherError::InvalidParameter);
+            }
+        }
+
+        set_referral_tiers(&env, &tiers);
+
+        extend_instance_ttl(&env);
+
+        env.events().publish(
+            (Symbol::new(&env, "referral_tiers_set"),),
+            (tiers.len(),),
+        );
+
+        Ok(())
+    }
+
+    /// Move a referral code to another tier.
+    pub fn set_referral_code_tier(env: Env, code: Symbol, tier: u32) -> Result<(), NoetherError> {
+        require_admin(&env)?;
+
+        let mut referral =
+            get_referral_code(&env, &code).ok_or(NoetherError::ReferralCodeNotFound)?;
+
+        if tier >= get_referral_tiers(&env).len() {
+            return Err(NoetherError::InvalidParameter);
+        }
+
+        referral.tier = tier;
+        set_referral_code(&env, &code, &referral);
+
+        env.events().publish(
+            (Symbol::new(&env, "referral_tier_set"),),
+            (code, tier),
+        );
+
+        Ok(())
+    }
+
+    /// Update oracle adapter address.
+    pub fn set_oracle_adapter(env: Env, oracle: Address) -> Result<(), NoetherError> {
+        require_admin(&env)?;
+        let old = get_oracle_adapter(&env);
+        set_oracle_adapter(&env, &oracle);
+
+        env.events().publish(
+            (Symbol::new(&env, "oracle_updated"),),
+            (old, oracle),
+        );
+
+        Ok(())
+    }
+
+    /// Update vault address.
+    pub fn set_vault(env: Env, vault: Address) -> Result<(), NoetherError> {
+        require_admin(&env)?;
+        let old = get_vault(&env);
+        set_vault(&env, &vault);
+
+        env.events().publish(
+            (Symbol::new(&env, "vault_updated"),),
+            (old, vault),
+        );
+
+        Ok(())
+    }
+
+    /// Set the cap on open interest across all markets, as a fraction of vault AUM.
+    ///
+    /// # Arguments
+    /// * `max_global_oi_bps` - Cap in basis points of AUM (e.g., 8000 = 80%)
+    pub fn set_max_global_oi(env: Env, max_global_oi_bps: u32) -> Result<(), NoetherError> {
+        require_admin(&env)?;
+
+        if max_global_oi_bps == 0 || max_global_oi_bps > BASIS_POINTS {
+            return Err(NoetherError::InvalidParameter);
+        }
+
+        let old = get_max_global_oi_bps(&env);
+        set_max_global_oi_bps(&env, max_global_oi_bps);
+
+        env.events().publish(
+            (Symbol::new(&env, "global_oi_cap_updated"),),
+            (old, max_global_oi_bps),
+        );
+
+        Ok(())
+    }
+
+    /// Get the cap on open interest across all markets (bps of vault AUM).
+    pub fn get_max_global_oi(env: Env) -> u32 {
+        get_max_global_oi_bps(&env)
+    }
+
+    /// Top up the insurance fund with USDC from the admin.
+    ///
+    /// # Returns
+    /// New insurance fund balance
+    pub fn top_up_insurance_fund(env: Env, amount: i128) -> Result<i128, NoetherError> {
+        require_admin(&env)?;
+
+        if amount <= 0 {
+            return Err(NoetherError::InvalidAmount);
+        }
+
+        let admin = get_admin(&env);
+        let usdc_token = get_usdc_token(&env);
+        let token_client = token::Client::new(&env, &usdc_token);
+        token_client.transfer(&admin, &env.current_contract_address(), &amount);
+
+        Self::deposit_insurance(&env, Symbol::new(&env, "top_up"), amount);
+
+        extend_instance_ttl(&env);
+
+        Ok(get_insurance_fund(&env))
+    }
+
+    /// Pause the market (emergency).
+    pub fn pause(env: Env) -> Result<(), NoetherError> {
+        require_admin(&env)?;
+        set_paused(&env, true);
+
+        env.events().publish(
+            (Symbol::new(&env, "paused"),),
+            (),
+        );
+
+        Ok(())
+    }
+
+    /// Unpause the market.
+    pub fn unpause(env: Env) -> Result<(), NoetherError> {
+        require_admin(&env)?;
+        set_paused(&env, false);
+
+        env.events().publish(
+            (Symbol::new(&env, "unpaused"),),
+            (),
+        );
+
+        Ok(())
+    }
+
+    /// Transfer admin role.
+    pub fn set_admin(env: Env, new_admin: Address) -> Result<(), NoetherError> {
+        require_admin(&env)?;
+        new_admin.require_auth();
+
+        let old = get_admin(&env);
+        set_admin(&env, &new_admin);
+
+        env.events().publish(
+            (Symbol::new(&env, "admin_updated"),),
+            (old, new_admin),
         );
 
         Ok(())
@@ -805,402 +1721,2744 @@ impl MarketContract {
     }
 
     // ═══════════════════════════════════════════════════════════════════════
-    // Order Functions (Limit Orders, Stop-Loss, Take-Profit)
+    // Operator Functions
     // ═══════════════════════════════════════════════════════════════════════
 
-    /// Place a limit entry order.
-    /// Collateral is locked immediately when the order is placed.
-    /// Order executes when price reaches trigger_price (based on trigger_condition).
+    /// Approve an operator (e.g. a trading bot) to act on the trader's behalf.
+    /// Replaces any existing approval for the same operator.
     ///
     /// # Arguments
-    /// * `trader` - Address of the trader
-    /// * `asset` - Asset symbol (e.g., "BTC", "ETH", "XLM")
-    /// * `direction` - Long or Short
-    /// * `collateral` - USDC collateral to lock (7 decimals)
-    /// * `leverage` - Leverage multiplier (1-10)
-    /// * `trigger_price` - Price at which to execute (7 decimals)
-    /// * `trigger_above` - true = execute when price >= trigger, false = when price <= trigger
-    /// * `slippage_tolerance_bps` - Max allowed slippage in basis points (e.g., 100 = 1%)
+    /// * `trader` - Address of the trader granting the approval
+    /// * `operator` - Address allowed to act for the trader
+    /// * `permissions` - Scopes granted (open, close, manage orders, add collateral)
+    /// * `expires_at` - Timestamp after which the approval lapses (None = until revoked)
+    ///
+    /// Operators can never withdraw collateral, and payouts always go to the trader.
+    pub fn approve_operator(
+        env: Env,
+        trader: Address,
+        operator: Address,
+        permissions: OperatorPermissions,
+        expires_at: Option<u64>,
+    ) -> Result<(), NoetherError> {
+        require_initialized(&env)?;
+
+        trader.require_auth();
+
+        if operator == trader {
+            return Err(NoetherError::InvalidParameter);
+        }
+        if let Some(expires_at) = expires_at {
+            if expires_at <= env.ledger().timestamp() {
+                return Err(NoetherError::InvalidParameter);
+            }
+        }
+
+        let approval = OperatorApproval {
+            permissions: permissions.clone(),
+            expires_at,
+        };
+        set_operator_approval(&env, &trader, &operator, &approval);
+
+        extend_instance_ttl(&env);
+
+        env.events().publish(
+            (Symbol::new(&env, "operator_approved"),),
+            (trader, operator, permissions, expires_at),
+        );
+
+        Ok(())
+    }
+
+    /// Revoke an operator's approval. Works while the market is paused.
+    pub fn revoke_operator(
+        env: Env,
+        trader: Address,
+        operator: Address,
+    ) -> Result<(), NoetherError> {
+        require_initialized(&env)?;
+
+        trader.require_auth();
+
+        if get_operator_approval(&env, &trader, &operator).is_none() {
+            return Err(NoetherError::OperatorNotApproved);
+        }
+        remove_operator_approval(&env, &trader, &operator);
+
+        env.events().publish(
+            (Symbol::new(&env, "operator_revoked"),),
+            (trader, operator),
+        );
+
+        Ok(())
+    }
+
+    /// Get the approval a trader has granted an operator.
+    pub fn get_operator(env: Env, trader: Address, operator: Address) -> Option<OperatorApproval> {
+        get_operator_approval(&env, &trader, &operator)
+    }
+
+    // ═══════════════════════════════════════════════════════════════════════
+    // Margin Account Functions
+    // ═══════════════════════════════════════════════════════════════════════
+
+    /// Deposit USDC into the trader's margin account.
+    /// Positions and orders draw from this balance before the wallet, and
+    /// closes and refunds are credited back to it.
     ///
     /// # Returns
-    /// The created Order
-    pub fn place_limit_order(
+    /// New margin balance
+    pub fn deposit_margin(env: Env, trader: Address, amount: i128) -> Result<i128, NoetherError> {
+        require_initialized(&env)?;
+        require_not_paused(&env)?;
+
+        trader.require_auth();
+
+        if amount <= 0 {
+            return Err(NoetherError::InvalidAmount);
+        }
+
+        let usdc_token = get_usdc_token(&env);
+        let token_client = token::Client::new(&env, &usdc_token);
+        token_client.transfer(&trader, &env.current_contract_address(), &amount);
+
+        let balance = get_margin_balance(&env, &trader) + amount;
+        set_margin_balance(&env, &trader, balance);
+
+        extend_instance_ttl(&env);
+
+        env.events().publish(
+            (Symbol::new(&env, "margin_deposited"),),
+            (trader, amount, balance),
+        );
+
+        Ok(balance)
+    }
+
+    /// Withdraw free USDC from the trader's margin account to their wallet.
+    /// Only the trader can withdraw; operators never can.
+    ///
+    /// # Returns
+    /// New margin balance
+    pub fn withdraw_margin(env: Env, trader: Address, amount: i128) -> Result<i128, NoetherError> {
+        require_initialized(&env)?;
+        require_not_paused(&env)?;
+
+        trader.require_auth();
+
+        if amount <= 0 {
+            return Err(NoetherError::InvalidAmount);
+        }
+
+        let balance = get_margin_balance(&env, &trader);
+        if amount > balance {
+            return Err(NoetherError::InsufficientBalance);
+        }
+
+        // A cross-margin account backing positions must stay above initial margin
+        if get_margin_mode(&env, &trader) == MarginMode::Cross && has_open_positions(&env, &trader) {
+            let (health, _, _) = Self::value_account(&env, &trader)?;
+            if health.equity - amount < health.initial_margin {
+                return Err(NoetherError::InsufficientMargin);
+            }
+        }
+
+        set_margin_balance(&env, &trader, balance - amount);
+
+        let usdc_token = get_usdc_token(&env);
+        let token_client = token::Client::new(&env, &usdc_token);
+        token_client.transfer(&env.current_contract_address(), &trader, &amount);
+
+        extend_instance_ttl(&env);
+
+        env.events().publish(
+            (Symbol::new(&env, "margin_withdrawn"),),
+            (trader, amount, balance - amount),
+        );
+
+        Ok(balance - amount)
+    }
+
+    /// Deposit a listed non-USDC token into the trader's margin account.
+    /// Token collateral counts toward cross-margin equity at the oracle
+    /// price less the token's haircut; it is never drawn to open positions.
+    ///
+    /// # Returns
+    /// New token balance
+    pub fn deposit_margin_token(
         env: Env,
         trader: Address,
-        asset: Symbol,
-        direction: Direction,
-        collateral: i128,
-        leverage: u32,
-        trigger_price: i128,
-        trigger_above: bool,
-        slippage_tolerance_bps: u32,
-    ) -> Result<Order, NoetherError> {
+        token: Address,
+        amount: i128,
+    ) -> Result<i128, NoetherError> {
         require_initialized(&env)?;
         require_not_paused(&env)?;
 
-        trader.require_auth();
+        trader.require_auth();
+
+        if amount <= 0 {
+            return Err(NoetherError::InvalidAmount);
+        }
+
+        let config = require_collateral(&env, &token)?;
+
+        let deposits = get_collateral_deposits(&env, &token) + amount;
+        if deposits > config.deposit_cap {
+            return Err(NoetherError::DepositCapExceeded);
+        }
+
+        let token_client = token::Client::new(&env, &token);
+        token_client.transfer(&trader, &env.current_contract_address(), &amount);
+
+        let balance = get_collateral_balance(&env, &trader, &token) + amount;
+        set_collateral_balance(&env, &trader, &token, balance);
+        set_collateral_deposits(&env, &token, deposits);
+
+        extend_instance_ttl(&env);
+
+        env.events().publish(
+            (Symbol::new(&env, "margin_token_deposited"),),
+            (trader, token, amount, balance),
+        );
+
+        Ok(balance)
+    }
+
+    /// Withdraw a non-USDC token from the trader's margin account.
+    /// Only the trader can withdraw; operators never can.
+    ///
+    /// # Returns
+    /// New token balance
+    pub fn withdraw_margin_token(
+        env: Env,
+        trader: Address,
+        token: Address,
+        amount: i128,
+    ) -> Result<i128, NoetherError> {
+        require_initialized(&env)?;
+        require_not_paused(&env)?;
+
+        trader.require_auth();
+
+        if amount <= 0 {
+            return Err(NoetherError::InvalidAmount);
+        }
+
+        let config = require_collateral(&env, &token)?;
+
+        let balance = get_collateral_balance(&env, &trader, &token);
+        if amount > balance {
+            return Err(NoetherError::InsufficientBalance);
+        }
+
+        // A cross-margin account backing positions must stay above initial margin
+        if get_margin_mode(&env, &trader) == MarginMode::Cross && has_open_positions(&env, &trader) {
+            let (health, _, _) = Self::value_account(&env, &trader)?;
+            let price = Self::get_oracle_price(&env, &config.oracle_symbol)?;
+            let value = calculate_haircut_value(amount, price, config.haircut_bps);
+            if health.equity - value < health.initial_margin {
+                return Err(NoetherError::InsufficientMargin);
+            }
+        }
+
+        set_collateral_balance(&env, &trader, &token, balance - amount);
+        set_collateral_deposits(&env, &token, get_collateral_deposits(&env, &token) - amount);
+
+        let token_client = token::Client::new(&env, &token);
+        token_client.transfer(&env.current_contract_address(), &trader, &amount);
+
+        extend_instance_ttl(&env);
+
+        env.events().publish(
+            (Symbol::new(&env, "margin_token_withdrawn"),),
+            (trader, token, amount, balance - amount),
+        );
+
+        Ok(balance - amount)
+    }
+
+    /// Get a trader's balance of a non-USDC collateral token.
+    pub fn get_margin_token_balance(env: Env, trader: Address, token: Address) -> i128 {
+        get_collateral_balance(&env, &trader, &token)
+    }
+
+    /// Get a trader's margin account: free balance, haircut value of token
+    /// collateral, and USDC locked in positions and pending limit orders.
+    pub fn get_margin_account(env: Env, trader: Address) -> MarginAccount {
+        let order_collateral = get_trader_orders(&env, &trader)
+            .iter()
+            .filter(|order| order.order_type == OrderType::LimitEntry)
+            .map(|order| order.collateral)
+            .sum();
+
+        MarginAccount {
+            balance: get_margin_balance(&env, &trader),
+            token_value: Self::token_collateral_value(&env, &trader),
+            position_collateral: get_trader_total_collateral(&env, &trader),
+            order_collateral,
+        }
+    }
+
+    /// Switch a trader's account between isolated and cross margin.
+    /// Only allowed while the trader has no open positions.
+    ///
+    /// In cross mode the margin balance backs all of the trader's positions:
+    /// they are liquidated together by `liquidate_account` once account
+    /// equity falls to the summed maintenance margin, never one by one.
+    pub fn set_margin_mode(env: Env, trader: Address, mode: MarginMode) -> Result<(), NoetherError> {
+        require_initialized(&env)?;
+        require_not_paused(&env)?;
+
+        trader.require_auth();
+
+        if has_open_positions(&env, &trader) {
+            return Err(NoetherError::OpenPositionsExist);
+        }
+
+        set_margin_mode(&env, &trader, mode);
+
+        extend_instance_ttl(&env);
+
+        env.events().publish(
+            (Symbol::new(&env, "margin_mode_set"),),
+            (trader, mode),
+        );
+
+        Ok(())
+    }
+
+    /// Get a trader's margin mode (Isolated unless switched).
+    pub fn get_margin_mode(env: Env, trader: Address) -> MarginMode {
+        get_margin_mode(&env, &trader)
+    }
+
+    // ═══════════════════════════════════════════════════════════════════════
+    // Referral Functions
+    // ═══════════════════════════════════════════════════════════════════════
+
+    /// Register a referral code. Codes start in tier 0.
+    ///
+    /// # Arguments
+    /// * `owner` - Referrer who earns the code's rebates
+    /// * `code` - Unregistered code (e.g., "ALICE")
+    pub fn register_code(env: Env, owner: Address, code: Symbol) -> Result<(), NoetherError> {
+        require_initialized(&env)?;
+
+        owner.require_auth();
+
+        if get_referral_code(&env, &code).is_some() {
+            return Err(NoetherError::ReferralCodeTaken);
+        }
+
+        set_referral_code(&env, &code, &ReferralCode { owner: owner.clone(), tier: 0 });
+
+        extend_instance_ttl(&env);
+
+        env.events().publish(
+            (Symbol::new(&env, "referral_code_registered"),),
+            (code, owner),
+        );
+
+        Ok(())
+    }
+
+    /// Set the referral code applied to a trader's fees.
+    /// Replaces any code set before; fees already paid are not affected.
+    pub fn set_referrer(env: Env, trader: Address, code: Symbol) -> Result<(), NoetherError> {
+        require_initialized(&env)?;
+
+        trader.require_auth();
+
+        let referral =
+            get_referral_code(&env, &code).ok_or(NoetherError::ReferralCodeNotFound)?;
+        if referral.owner == trader {
+            return Err(NoetherError::SelfReferral);
+        }
+
+        // Move the trader from the previous referrer's count
+        if let Some(previous) = get_referrer(&env, &trader) {
+            if previous == code {
+                return Ok(());
+            }
+            if let Some(previous_referral) = get_referral_code(&env, &previous) {
+                let mut stats = get_referral_stats(&env, &previous_referral.owner);
+                stats.referred_traders = stats.referred_traders.saturating_sub(1);
+                set_referral_stats(&env, &previous_referral.owner, &stats);
+            }
+        }
+
+        let mut stats = get_referral_stats(&env, &referral.owner);
+        stats.referred_traders += 1;
+        set_referral_stats(&env, &referral.owner, &stats);
+
+        set_referrer(&env, &trader, &code);
+
+        extend_instance_ttl(&env);
+
+        env.events().publish(
+            (Symbol::new(&env, "referrer_set"),),
+            (trader, code, referral.owner),
+        );
+
+        Ok(())
+    }
+
+    /// Claim a referrer's accrued rebates in USDC.
+    ///
+    /// # Returns
+    /// Amount claimed
+    pub fn claim_referral_rewards(env: Env, owner: Address) -> Result<i128, NoetherError> {
+        require_initialized(&env)?;
+
+        owner.require_auth();
+
+        let mut stats = get_referral_stats(&env, &owner);
+        let amount = stats.claimable;
+        if amount <= 0 {
+            return Err(NoetherError::NoReferralRewards);
+        }
+
+        stats.claimable = 0;
+        set_referral_stats(&env, &owner, &stats);
+
+        let usdc_token = get_usdc_token(&env);
+        let token_client = token::Client::new(&env, &usdc_token);
+        token_client.transfer(&env.current_contract_address(), &owner, &amount);
+
+        extend_instance_ttl(&env);
+
+        env.events().publish(
+            (Symbol::new(&env, "referral_rewards_claimed"),),
+            (owner, amount),
+        );
+
+        Ok(amount)
+    }
+
+    /// Get a referrer's referred trader count and earned, claimable rebates.
+    pub fn get_referral_stats(env: Env, owner: Address) -> ReferralStats {
+        get_referral_stats(&env, &owner)
+    }
+
+    /// Get the owner and tier of a referral code.
+    pub fn get_referral_code(env: Env, code: Symbol) -> Option<ReferralCode> {
+        get_referral_code(&env, &code)
+    }
+
+    /// Get the referral code a trader has set.
+    pub fn get_referrer(env: Env, trader: Address) -> Option<Symbol> {
+        get_referrer(&env, &trader)
+    }
+
+    /// Get the referral tiers.
+    pub fn get_referral_tiers(env: Env) -> Vec<ReferralTier> {
+        get_referral_tiers(&env)
+    }
+
+    // ═══════════════════════════════════════════════════════════════════════
+    // Order Functions (Limit Orders, Stop-Loss, Take-Profit)
+    // ═══════════════════════════════════════════════════════════════════════
+
+    /// Place a limit entry order.
+    /// Collateral is locked immediately when the order is placed.
+    /// Order executes when price reaches trigger_price (based on trigger_condition).
+    ///
+    /// # Arguments
+    /// * `caller` - Trader or an operator approved to open for them
+    /// * `trader` - Address of the trader
+    /// * `asset` - Asset symbol (e.g., "BTC", "ETH", "XLM")
+    /// * `direction` - Long or Short
+    /// * `collateral` - USDC collateral to lock (7 decimals)
+    /// * `leverage` - Leverage multiplier (1-10)
+    /// * `trigger_price` - Price at which to execute (7 decimals)
+    /// * `trigger_above` - true = execute when price >= trigger, false = when price <= trigger
+    /// * `options` - Slippage tolerance, plus optional expiry and bracket stop-loss/take-profit prices
+    ///
+    /// # Returns
+    /// The created Order
+    pub fn place_limit_order(
+        env: Env,
+        caller: Address,
+        trader: Address,
+        asset: Symbol,
+        direction: Direction,
+        collateral: i128,
+        leverage: u32,
+        trigger_price: i128,
+        trigger_above: bool,
+        options: LimitOrderOptions,
+    ) -> Result<Order, NoetherError> {
+        require_initialized(&env)?;
+        require_not_paused(&env)?;
+
+        let LimitOrderOptions {
+            slippage_tolerance_bps,
+            expires_at,
+            stop_loss_price,
+            take_profit_price,
+        } = options;
+
+        let via_operator = Self::require_trader_auth(
+            &env,
+            &caller,
+            &trader,
+            OperatorScope::Open,
+            NoetherError::OperatorNotApproved,
+        )?;
+
+        let config = require_market(&env, &asset)?;
+
+        // Validate parameters
+        if collateral < config.min_collateral {
+            return Err(NoetherError::InsufficientCollateral);
+        }
+        if leverage < 1 || leverage > config.max_leverage {
+            return Err(NoetherError::InvalidLeverage);
+        }
+        if trigger_price <= 0 {
+            return Err(NoetherError::InvalidTriggerPrice);
+        }
+        if slippage_tolerance_bps == 0 || slippage_tolerance_bps > 10000 {
+            return Err(NoetherError::InvalidSlippageTolerance);
+        }
+        Self::validate_expiry(&env, expires_at)?;
+
+        // Bracket legs must sit on the right side of the intended entry;
+        // they are checked again against the actual fill
+        Self::validate_bracket(direction, trigger_price, stop_loss_price, take_profit_price)?;
+
+        // Calculate position size to check against limits
+        let size = calculate_position_size(collateral, leverage);
+        if size > config.max_position_size {
+            return Err(NoetherError::PositionTooLarge);
+        }
+
+        // Lock collateral from the margin account, then the wallet
+        Self::debit_margin(&env, &trader, collateral, via_operator);
+
+        // Generate order ID
+        let order_id = next_order_id(&env);
+
+        // Create order
+        let trigger_condition = if trigger_above {
+            TriggerCondition::Above
+        } else {
+            TriggerCondition::Below
+        };
+
+        let order = Order {
+            id: order_id,
+            trader: trader.clone(),
+            asset: asset.clone(),
+            order_type: OrderType::LimitEntry,
+            direction,
+            collateral,
+            leverage,
+            trigger_price,
+            trigger_condition,
+            slippage_tolerance_bps,
+            position_id: 0,
+            has_position: false,
+            created_at: env.ledger().timestamp(),
+            expires_at,
+            trail_bps: 0,
+            trail_amount: 0,
+            water_mark: 0,
+            stop_loss_price,
+            take_profit_price,
+            status: OrderStatus::Pending,
+        };
+
+        // Store order
+        save_order(&env, &order);
+
+        extend_instance_ttl(&env);
+
+        env.events().publish(
+            (Symbol::new(&env, "order_placed"),),
+            (order_id, trader, asset, OrderType::LimitEntry, trigger_price, direction),
+        );
+
+        Ok(order)
+    }
+
+    /// Set a stop-loss order on an existing position.
+    /// Automatically closes the position when price reaches trigger to limit losses.
+    ///
+    /// # Arguments
+    /// * `caller` - Trader (must own position) or an operator approved to manage orders
+    /// * `position_id` - ID of the position to protect
+    /// * `trigger_price` - Price at which to close (7 decimals)
+    /// * `slippage_tolerance_bps` - Max allowed slippage in basis points
+    /// * `expires_at` - Timestamp after which the order expires (None = good till cancelled)
+    pub fn set_stop_loss(
+        env: Env,
+        caller: Address,
+        position_id: u64,
+        trigger_price: i128,
+        slippage_tolerance_bps: u32,
+        expires_at: Option<u64>,
+    ) -> Result<Order, NoetherError> {
+        require_initialized(&env)?;
+        require_not_paused(&env)?;
+
+        // Validate slippage and expiry
+        if slippage_tolerance_bps == 0 || slippage_tolerance_bps > 10000 {
+            return Err(NoetherError::InvalidSlippageTolerance);
+        }
+        Self::validate_expiry(&env, expires_at)?;
+
+        // Get position
+        let position = get_position(&env, position_id)
+            .ok_or(NoetherError::PositionNotFound)?;
+
+        // Verify ownership (or an operator allowed to manage orders)
+        Self::require_trader_auth(
+            &env,
+            &caller,
+            &position.trader,
+            OperatorScope::ManageOrders,
+            NoetherError::NotPositionOwner,
+        )?;
+
+        // Check if SL already exists
+        if get_position_stop_loss(&env, position_id).is_some() {
+            return Err(NoetherError::OrderAlreadyExists);
+        }
+
+        let order = Self::attach_close_order(
+            &env,
+            &position,
+            OrderType::StopLoss,
+            trigger_price,
+            slippage_tolerance_bps,
+            expires_at,
+        )?;
+
+        extend_instance_ttl(&env);
+
+        Ok(order)
+    }
+
+    /// Set a take-profit order on an existing position.
+    /// Automatically closes the position when price reaches trigger to lock in profits.
+    ///
+    /// # Arguments
+    /// * `caller` - Trader (must own position) or an operator approved to manage orders
+    /// * `position_id` - ID of the position
+    /// * `trigger_price` - Price at which to close (7 decimals)
+    /// * `slippage_tolerance_bps` - Max allowed slippage in basis points
+    /// * `expires_at` - Timestamp after which the order expires (None = good till cancelled)
+    pub fn set_take_profit(
+        env: Env,
+        caller: Address,
+        position_id: u64,
+        trigger_price: i128,
+        slippage_tolerance_bps: u32,
+        expires_at: Option<u64>,
+    ) -> Result<Order, NoetherError> {
+        require_initialized(&env)?;
+        require_not_paused(&env)?;
+
+        // Validate slippage and expiry
+        if slippage_tolerance_bps == 0 || slippage_tolerance_bps > 10000 {
+            return Err(NoetherError::InvalidSlippageTolerance);
+        }
+        Self::validate_expiry(&env, expires_at)?;
+
+        // Get position
+        let position = get_position(&env, position_id)
+            .ok_or(NoetherError::PositionNotFound)?;
+
+        // Verify ownership (or an operator allowed to manage orders)
+        Self::require_trader_auth(
+            &env,
+            &caller,
+            &position.trader,
+            OperatorScope::ManageOrders,
+            NoetherError::NotPositionOwner,
+        )?;
+
+        // Check if TP already exists
+        if get_position_take_profit(&env, position_id).is_some() {
+            return Err(NoetherError::OrderAlreadyExists);
+        }
+
+        let order = Self::attach_close_order(
+            &env,
+            &position,
+            OrderType::TakeProfit,
+            trigger_price,
+            slippage_tolerance_bps,
+            expires_at,
+        )?;
+
+        extend_instance_ttl(&env);
+
+        Ok(order)
+    }
+
+    /// Set a trailing stop on an existing position.
+    /// The stop follows the price as it moves in the position's favour and
+    /// closes the position when the price retraces by the trail distance.
+    /// Uses the position's stop-loss slot, so a position holds either a
+    /// stop-loss or a trailing stop.
+    ///
+    /// # Arguments
+    /// * `caller` - Trader (must own position) or an operator approved to manage orders
+    /// * `position_id` - ID of the position to protect
+    /// * `trail_bps` - Trail as a share of the water mark (0 = use `trail_amount`)
+    /// * `trail_amount` - Trail as a fixed price distance (7 decimals, 0 = use `trail_bps`)
+    /// * `slippage_tolerance_bps` - Max allowed slippage in basis points
+    /// * `expires_at` - Timestamp after which the order expires (None = good till cancelled)
+    pub fn set_trailing_stop(
+        env: Env,
+        caller: Address,
+        position_id: u64,
+        trail_bps: u32,
+        trail_amount: i128,
+        slippage_tolerance_bps: u32,
+        expires_at: Option<u64>,
+    ) -> Result<Order, NoetherError> {
+        require_initialized(&env)?;
+        require_not_paused(&env)?;
+
+        // Validate slippage and expiry
+        if slippage_tolerance_bps == 0 || slippage_tolerance_bps > 10000 {
+            return Err(NoetherError::InvalidSlippageTolerance);
+        }
+        Self::validate_expiry(&env, expires_at)?;
+
+        // Exactly one trail distance must be set
+        let bps_set = trail_bps > 0;
+        let amount_set = trail_amount > 0;
+        if bps_set == amount_set || trail_bps >= BASIS_POINTS || trail_amount < 0 {
+            return Err(NoetherError::InvalidParameter);
+        }
+
+        // Get position
+        let position = get_position(&env, position_id)
+            .ok_or(NoetherError::PositionNotFound)?;
+
+        // Verify ownership (or an operator allowed to manage orders)
+        Self::require_trader_auth(
+            &env,
+            &caller,
+            &position.trader,
+            OperatorScope::ManageOrders,
+            NoetherError::NotPositionOwner,
+        )?;
+
+        // Shares the stop-loss slot
+        if get_position_stop_loss(&env, position_id).is_some() {
+            return Err(NoetherError::OrderAlreadyExists);
+        }
+
+        // Start the water mark at the current price
+        let water_mark = Self::get_oracle_price(&env, &position.asset)?;
+        let trigger_price =
+            calculate_trailing_trigger(water_mark, position.direction, trail_bps, trail_amount);
+
+        // Long position: close when price <= trigger (price falling)
+        // Short position: close when price >= trigger (price rising)
+        let trigger_condition = match position.direction {
+            Direction::Long => TriggerCondition::Below,
+            Direction::Short => TriggerCondition::Above,
+        };
+
+        // Generate order ID
+        let order_id = next_order_id(&env);
+
+        // Create order
+        let order = Order {
+            id: order_id,
+            trader: position.trader.clone(),
+            asset: position.asset.clone(),
+            order_type: OrderType::TrailingStop,
+            direction: position.direction,
+            collateral: 0, // No collateral locked for trailing stops
+            leverage: position.leverage,
+            trigger_price,
+            trigger_condition,
+            slippage_tolerance_bps,
+            position_id,
+            has_position: true,
+            created_at: env.ledger().timestamp(),
+            expires_at,
+            trail_bps,
+            trail_amount,
+            water_mark,
+            stop_loss_price: None,
+            take_profit_price: None,
+            status: OrderStatus::Pending,
+        };
+
+        // Store order and link to position
+        save_order(&env, &order);
+        set_position_stop_loss(&env, position_id, order_id);
+
+        extend_instance_ttl(&env);
+
+        env.events().publish(
+            (Symbol::new(&env, "trailing_stop_set"),),
+            (order_id, position_id, water_mark, trigger_price),
+        );
+
+        Ok(order)
+    }
+
+    /// Ratchet a trailing stop's water mark to the current oracle price.
+    /// Callable by anyone (keeper). The mark only moves in the position's
+    /// favour, so calling it on a retracement leaves the stop unchanged.
+    ///
+    /// # Arguments
+    /// * `order_id` - ID of the trailing stop order
+    ///
+    /// # Returns
+    /// The order's trigger price after the update
+    pub fn update_trailing_stop(env: Env, order_id: u64) -> Result<i128, NoetherError> {
+        require_initialized(&env)?;
+
+        // Get order
+        let mut order = get_order(&env, order_id)
+            .ok_or(NoetherError::OrderNotFound)?;
+
+        if order.order_type != OrderType::TrailingStop {
+            return Err(NoetherError::InvalidParameter);
+        }
+        if order.status != OrderStatus::Pending || Self::is_order_expired(&env, &order) {
+            return Err(NoetherError::OrderNotPending);
+        }
+
+        let current_price = Self::get_oracle_price(&env, &order.asset)?;
+        let water_mark = update_water_mark(order.water_mark, current_price, order.direction);
+
+        if water_mark != order.water_mark {
+            order.water_mark = water_mark;
+            order.trigger_price = calculate_trailing_trigger(
+                water_mark,
+                order.direction,
+                order.trail_bps,
+                order.trail_amount,
+            );
+            save_order(&env, &order);
+
+            env.events().publish(
+                (Symbol::new(&env, "trailing_stop_updated"),),
+                (order_id, order.position_id, water_mark, order.trigger_price),
+            );
+        }
+
+        extend_instance_ttl(&env);
+
+        Ok(order.trigger_price)
+    }
+
+    /// Modify a pending order in place.
+    /// Re-validates the trigger like placing the order would, and for limit
+    /// orders transfers only the difference between old and new collateral.
+    ///
+    /// # Arguments
+    /// * `caller` - Trader (must own order) or an operator approved to manage orders
+    ///   (raising a limit order's collateral also needs the open permission)
+    /// * `order_id` - ID of the order to modify
+    /// * `new_trigger_price` - New trigger price (7 decimals)
+    /// * `new_slippage_bps` - New max allowed slippage in basis points
+    /// * `new_collateral` - New locked collateral for limit orders (must be 0 for SL/TP)
+    ///
+    /// Trailing stops track the price instead of a fixed trigger and cannot be
+    /// modified; cancel and set a new one.
+    ///
+    /// # Returns
+    /// The updated Order
+    pub fn modify_order(
+        env: Env,
+        caller: Address,
+        order_id: u64,
+        new_trigger_price: i128,
+        new_slippage_bps: u32,
+        new_collateral: i128,
+    ) -> Result<Order, NoetherError> {
+        require_initialized(&env)?;
+        require_not_paused(&env)?;
+
+        // Get order
+        let mut order = get_order(&env, order_id)
+            .ok_or(NoetherError::OrderNotFound)?;
+
+        // Verify ownership (or an operator allowed to manage orders)
+        let via_operator = Self::require_trader_auth(
+            &env,
+            &caller,
+            &order.trader,
+            OperatorScope::ManageOrders,
+            NoetherError::NotOrderOwner,
+        )?;
+        let trader = order.trader.clone();
+
+        // Check if still pending (expired orders can only be expired)
+        if order.status != OrderStatus::Pending || Self::is_order_expired(&env, &order) {
+            return Err(NoetherError::OrderNotPending);
+        }
+
+        // Validate slippage
+        if new_slippage_bps == 0 || new_slippage_bps > 10000 {
+            return Err(NoetherError::InvalidSlippageTolerance);
+        }
+
+        match order.order_type {
+            OrderType::TrailingStop => return Err(NoetherError::InvalidParameter),
+            OrderType::LimitEntry => {
+                let config = require_market(&env, &order.asset)?;
+
+                if new_trigger_price <= 0 {
+                    return Err(NoetherError::InvalidTriggerPrice);
+                }
+                Self::validate_bracket(
+                    order.direction,
+                    new_trigger_price,
+                    order.stop_loss_price,
+                    order.take_profit_price,
+                )?;
+                if new_collateral < config.min_collateral {
+                    return Err(NoetherError::InsufficientCollateral);
+                }
+                if calculate_position_size(new_collateral, order.leverage) > config.max_position_size {
+                    return Err(NoetherError::PositionTooLarge);
+                }
+
+                // Raising collateral pulls funds like placing an order does
+                let difference = new_collateral - order.collateral;
+                if difference > 0 && via_operator {
+                    let now = env.ledger().timestamp();
+                    let permitted = get_operator_approval(&env, &trader, &caller)
+                        .map(|approval| is_operator_permitted(&approval, OperatorScope::Open, now))
+                        .unwrap_or(false);
+                    if !permitted {
+                        return Err(NoetherError::OperatorNotPermitted);
+                    }
+                }
+
+                // Move only the collateral difference
+                if difference > 0 {
+                    Self::debit_margin(&env, &trader, difference, via_operator);
+                } else if difference < 0 {
+                    Self::credit_margin(&env, &trader, -difference);
+                }
+
+                order.collateral = new_collateral;
+            }
+            _ => {
+                if new_collateral != 0 {
+                    return Err(NoetherError::InvalidAmount);
+                }
+
+                let position = get_position(&env, order.position_id)
+                    .ok_or(NoetherError::PositionNotFound)?;
+                Self::validate_close_trigger(
+                    position.direction,
+                    position.entry_price,
+                    order.order_type,
+                    new_trigger_price,
+                )?;
+            }
+        }
+
+        order.trigger_price = new_trigger_price;
+        order.slippage_tolerance_bps = new_slippage_bps;
+        save_order(&env, &order);
+
+        extend_instance_ttl(&env);
+
+        env.events().publish(
+            (Symbol::new(&env, "order_modified"),),
+            (order_id, trader, new_trigger_price, new_slippage_bps, order.collateral),
+        );
+
+        Ok(order)
+    }
+
+    /// Cancel a pending order.
+    /// For limit orders, refunds the locked collateral to the margin account.
+    ///
+    /// # Arguments
+    /// * `caller` - Trader (must own order) or an operator approved to manage orders
+    /// * `order_id` - ID of the order to cancel
+    pub fn cancel_order(
+        env: Env,
+        caller: Address,
+        order_id: u64,
+    ) -> Result<(), NoetherError> {
+        require_initialized(&env)?;
+
+        // Get order
+        let order = get_order(&env, order_id)
+            .ok_or(NoetherError::OrderNotFound)?;
+
+        // Verify ownership (or an operator allowed to manage orders)
+        Self::require_trader_auth(
+            &env,
+            &caller,
+            &order.trader,
+            OperatorScope::ManageOrders,
+            NoetherError::NotOrderOwner,
+        )?;
+        let trader = order.trader.clone();
+
+        // Check if still pending
+        if order.status != OrderStatus::Pending {
+            return Err(NoetherError::OrderNotPending);
+        }
+
+        // Refund collateral, remove SL/TP links and update order status
+        Self::close_out_order(&env, &order, OrderStatus::Cancelled);
+
+        extend_instance_ttl(&env);
+
+        env.events().publish(
+            (Symbol::new(&env, "order_cancelled"),),
+            (order_id, trader, Symbol::new(&env, "user_cancelled")),
+        );
+
+        Ok(())
+    }
+
+    /// Execute a triggered order (called by keeper).
+    /// Checks if price condition is met and executes the order.
+    /// Keeper receives a fee for successful execution.
+    ///
+    /// # Arguments
+    /// * `keeper` - Address of the keeper executing the order
+    /// * `order_id` - ID of the order to execute
+    ///
+    /// # Returns
+    /// Keeper reward amount
+    pub fn execute_order(
+        env: Env,
+        keeper: Address,
+        order_id: u64,
+    ) -> Result<i128, NoetherError> {
+        require_initialized(&env)?;
+
+        keeper.require_auth();
+
+        // Get order
+        let order = get_order(&env, order_id)
+            .ok_or(NoetherError::OrderNotFound)?;
+
+        // Check if still pending
+        if order.status != OrderStatus::Pending {
+            return Err(NoetherError::OrderNotPending);
+        }
+
+        // Expired orders are never filled
+        // Returns Ok(0) like a slippage cancellation so the expiry commits
+        if Self::is_order_expired(&env, &order) {
+            Self::expire_order(&env, &order);
+            extend_instance_ttl(&env);
+            return Ok(0);
+        }
+
+        // Neither are close orders whose position is gone
+        if Self::is_order_orphaned(&env, &order) {
+            Self::cancel_orphaned_order(&env, &order);
+            extend_instance_ttl(&env);
+            return Ok(0);
+        }
+
+        // Get current price
+        let current_price = Self::get_oracle_price(&env, &order.asset)?;
+
+        // Check if trigger condition is met
+        if !Self::is_order_triggered(&order, current_price) {
+            return Err(NoetherError::OrderNotTriggered);
+        }
+
+        let reward = Self::execute_order_at_price(&env, &keeper, &order, current_price)?;
+
+        extend_instance_ttl(&env);
+
+        Ok(reward)
+    }
+
+    /// Execute several triggered orders in one call (keeper).
+    /// Each asset's price is fetched once. Missing, non-pending or untriggered
+    /// orders, and orders that cannot fill (e.g. an open interest cap or
+    /// vault liquidity), are skipped instead of failing the batch. Expired
+    /// orders are expired and close orders whose position is gone are
+    /// cancelled without executing. The skip is decided before anything is
+    /// written, so an execution that still fails fails the whole batch
+    /// rather than leaving partial state.
+    ///
+    /// # Arguments
+    /// * `keeper` - Address of the keeper executing the orders
+    /// * `order_ids` - IDs of orders to execute
+    ///
+    /// # Returns
+    /// One result per ID, in order, with the keeper reward earned
+    pub fn execute_orders_batch(
+        env: Env,
+        keeper: Address,
+        order_ids: Vec<u64>,
+    ) -> Result<Vec<BatchResult>, NoetherError> {
+        require_initialized(&env)?;
+
+        keeper.require_auth();
+
+        let mut prices = Map::new(&env);
+        let mut results = Vec::new(&env);
+
+        for order_id in order_ids.iter() {
+            let mut reward = None;
+
+            if let Some(order) = get_order(&env, order_id) {
+                if order.status == OrderStatus::Pending && Self::is_order_expired(&env, &order) {
+                    Self::expire_order(&env, &order);
+                } else if order.status == OrderStatus::Pending && Self::is_order_orphaned(&env, &order) {
+                    Self::cancel_orphaned_order(&env, &order);
+                } else if order.status == OrderStatus::Pending {
+                    if let Some(current_price) = Self::get_cached_price(&env, &mut prices, &order.asset) {
+                        if Self::is_order_triggered(&order, current_price)
+                            && Self::check_order_fill(&env, &order, current_price).is_ok()
+                        {
+                            reward = Some(Self::execute_order_at_price(
+                                &env,
+                                &keeper,
+                                &order,
+                                current_price,
+                            )?);
+                        }
+                    }
+                }
+            }
+
+            results.push_back(BatchResult {
+                id: order_id,
+                executed: reward.is_some(),
+                reward: reward.unwrap_or(0),
+            });
+        }
+
+        extend_instance_ttl(&env);
+
+        Ok(results)
+    }
+
+    /// Expire pending orders that are past their expires_at timestamp.
+    /// Callable by anyone. Refunds escrowed limit order collateral and clears
+    /// SL/TP links. Orders that are not pending or not yet expired are skipped.
+    ///
+    /// # Arguments
+    /// * `order_ids` - IDs of orders to expire
+    ///
+    /// # Returns
+    /// IDs of the orders that were expired
+    pub fn expire_orders(env: Env, order_ids: Vec<u64>) -> Result<Vec<u64>, NoetherError> {
+        require_initialized(&env)?;
+
+        let mut expired = Vec::new(&env);
+
+        for order_id in order_ids.iter() {
+            if let Some(order) = get_order(&env, order_id) {
+                if order.status == OrderStatus::Pending && Self::is_order_expired(&env, &order) {
+                    Self::expire_order(&env, &order);
+                    expired.push_back(order_id);
+                }
+            }
+        }
+
+        extend_instance_ttl(&env);
+
+        Ok(expired)
+    }
+
+    /// Check if an order should be executed at current price.
+    pub fn should_execute_order(env: Env, order_id: u64) -> Result<bool, NoetherError> {
+        let order = get_order(&env, order_id)
+            .ok_or(NoetherError::OrderNotFound)?;
+
+        if order.status != OrderStatus::Pending || Self::is_order_expired(&env, &order) {
+            return Ok(false);
+        }
+
+        let current_price = Self::get_oracle_price(&env, &order.asset)?;
+
+        Ok(Self::is_order_triggered(&order, current_price))
+    }
+
+    /// Get all orders for a trader.
+    pub fn get_orders(env: Env, trader: Address) -> Vec<Order> {
+        get_trader_orders(&env, &trader)
+    }
+
+    /// Get a specific order by ID.
+    pub fn get_order(env: Env, order_id: u64) -> Option<Order> {
+        get_order(&env, order_id)
+    }
+
+    /// Get all pending order IDs (for keeper).
+    pub fn get_all_order_ids(env: Env) -> Vec<u64> {
+        get_all_order_ids(&env)
+    }
+
+    /// Get stop-loss (or trailing stop) order ID attached to a position.
+    pub fn get_position_sl(env: Env, position_id: u64) -> Option<u64> {
+        get_position_stop_loss(&env, position_id)
+    }
+
+    /// Get take-profit order ID attached to a position.
+    pub fn get_position_tp(env: Env, position_id: u64) -> Option<u64> {
+        get_position_take_profit(&env, position_id)
+    }
+
+    // ═══════════════════════════════════════════════════════════════════════
+    // Internal Functions
+    // ═══════════════════════════════════════════════════════════════════════
+
+    /// Authorize an action on a trader's behalf. The caller must be the
+    /// trader or an operator whose approval covers `scope`.
+    ///
+    /// # Arguments
+    /// * `caller` - Address that signed the call
+    /// * `trader` - Owner of the account being acted on
+    /// * `scope` - Action being attempted
+    /// * `not_owner` - Error for callers with no approval at all
+    ///
+    /// # Returns
+    /// Whether the caller is an operator
+    fn require_trader_auth(
+        env: &Env,
+        caller: &Address,
+        trader: &Address,
+        scope: OperatorScope,
+        not_owner: NoetherError,
+    ) -> Result<bool, NoetherError> {
+        caller.require_auth();
+
+        if caller == trader {
+            return Ok(false);
+        }
+
+        match get_operator_approval(env, trader, caller) {
+            Some(approval) if is_operator_permitted(&approval, scope, env.ledger().timestamp()) => {
+                Ok(true)
+            }
+            Some(_) => Err(NoetherError::OperatorNotPermitted),
+            None => Err(not_owner),
+        }
+    }
+
+    /// Draw USDC for a trader's position or order: first from the margin
+    /// account, then any shortfall from the trader's wallet. Operators cannot
+    /// sign the trader's transfer, so their calls spend the trader's
+    /// allowance to the market instead.
+    fn debit_margin(env: &Env, trader: &Address, amount: i128, via_operator: bool) {
+        let balance = get_margin_balance(env, trader);
+        let (from_balance, from_wallet) = calculate_margin_draw(balance, amount);
+
+        if from_balance > 0 {
+            set_margin_balance(env, trader, balance - from_balance);
+        }
+
+        if from_wallet > 0 {
+            let usdc_token = get_usdc_token(env);
+            let token_client = token::Client::new(env, &usdc_token);
+            let market = env.current_contract_address();
+
+            if via_operator {
+                token_client.transfer_from(&market, trader, &market, &from_wallet);
+            } else {
+                token_client.transfer(trader, &market, &from_wallet);
+            }
+        }
+    }
+
+    /// Credit USDC owed to a trader (payouts, refunds) to their margin account.
+    fn credit_margin(env: &Env, trader: &Address, amount: i128) {
+        if amount > 0 {
+            set_margin_balance(env, trader, get_margin_balance(env, trader) + amount);
+        }
+    }
+
+    /// Fetch price from oracle adapter.
+    fn get_oracle_price(env: &Env, asset: &Symbol) -> Result<i128, NoetherError> {
+        let oracle_address = get_oracle_adapter(env);
+
+        // Call oracle adapter using invoke_contract
+        let args: Vec<soroban_sdk::Val> = (asset.clone(),).into_val(env);
+        let (price, timestamp): (i128, u64) = env.invoke_contract(
+            &oracle_address,
+            &Symbol::new(env, "lastprice"),
+            args,
+        );
+
+        // Check staleness (per-asset threshold for listed markets)
+        let config = get_market_config(env, asset).unwrap_or_else(|| get_config(env));
+        let current_time = env.ledger().timestamp();
+
+        if current_time > timestamp && current_time - timestamp > config.max_price_staleness {
+            return Err(NoetherError::PriceStale);
+        }
+
+        if price <= 0 {
+            return Err(NoetherError::InvalidPrice);
+        }
+
+        Ok(price)
+    }
+
+    /// Get an asset's oracle price, fetching it at most once per batch.
+    /// Failed lookups (stale or invalid price) are cached as None so the
+    /// asset's IDs are skipped.
+    fn get_cached_price(
+        env: &Env,
+        prices: &mut Map<Symbol, Option<i128>>,
+        asset: &Symbol,
+    ) -> Option<i128> {
+        if let Some(price) = prices.get(asset.clone()) {
+            return price;
+        }
+
+        let price = Self::get_oracle_price(env, asset).ok();
+        prices.set(asset.clone(), price);
+        price
+    }
+
+    /// Validate collateral token parameters. USDC itself cannot be listed
+    /// and a token must keep some margin value.
+    fn validate_collateral_config(env: &Env, config: &CollateralConfig) -> Result<(), NoetherError> {
+        if config.token == get_usdc_token(env) {
+            return Err(NoetherError::InvalidParameter);
+        }
+        if config.haircut_bps >= BASIS_POINTS || config.deposit_cap <= 0 {
+            return Err(NoetherError::InvalidParameter);
+        }
+        Ok(())
+    }
+
+    /// Validate market configuration parameters.
+    fn validate_config(config: &MarketConfig) -> Result<(), NoetherError> {
+        if config.max_leverage < 1 || config.max_leverage > 100 {
+            return Err(NoetherError::InvalidParameter);
+        }
+        if config.initial_margin_bps < config.maintenance_margin_bps
+            || config.initial_margin_bps > BASIS_POINTS
+        {
+            return Err(NoetherError::InvalidParameter);
+        }
+        if config.optimal_utilization_bps == 0 || config.optimal_utilization_bps > BASIS_POINTS {
+            return Err(NoetherError::InvalidParameter);
+        }
+        if config.max_long_oi <= 0 || config.max_short_oi <= 0 {
+            return Err(NoetherError::InvalidParameter);
+        }
+        if config.liquidation_buffer_bps > BASIS_POINTS
+            || config.insurance_fee_share_bps > BASIS_POINTS
+        {
+            return Err(NoetherError::InvalidParameter);
+        }
+        if config.adl_threshold_bps == 0 || config.adl_threshold_bps > BASIS_POINTS {
+            return Err(NoetherError::InvalidParameter);
+        }
+        if config.price_impact_factor < 0
+            || config.price_impact_exponent < 1
+            || config.price_impact_exponent > 3
+        {
+            return Err(NoetherError::InvalidParameter);
+        }
+        Ok(())
+    }
+
+    /// Get open interest capacity left on each side of an asset and across all markets.
+    /// The global cap is a fraction of vault AUM.
+    ///
+    /// # Returns
+    /// (remaining_long, remaining_short, remaining_global)
+    fn remaining_open_interest(
+        env: &Env,
+        asset: &Symbol,
+        config: &MarketConfig,
+    ) -> (i128, i128, i128) {
+        let aum: i128 = env.invoke_contract(
+            &get_vault(env),
+            &Symbol::new(env, "get_aum"),
+            Vec::new(env),
+        );
+        let global_cap = aum * (get_max_global_oi_bps(env) as i128) / (BASIS_POINTS as i128);
+
+        (
+            (config.max_long_oi - get_total_long_size(env, asset)).max(0),
+            (config.max_short_oi - get_total_short_size(env, asset)).max(0),
+            (global_cap - get_total_open_interest(env)).max(0),
+        )
+    }
+
+    /// Check that adding size to one side of an asset stays within open interest caps.
+    fn check_open_interest(
+        env: &Env,
+        asset: &Symbol,
+        config: &MarketConfig,
+        direction: Direction,
+        added_size: i128,
+    ) -> Result<(), NoetherError> {
+        let (remaining_long, remaining_short, remaining_global) =
+            Self::remaining_open_interest(env, asset, config);
+
+        let remaining_side = match direction {
+            Direction::Long => remaining_long,
+            Direction::Short => remaining_short,
+        };
+
+        if added_size > remaining_side || added_size > remaining_global {
+            return Err(NoetherError::OpenInterestCapExceeded);
+        }
+
+        Ok(())
+    }
+
+    /// Get the execution price of a trade after skew-based price impact.
+    /// Must run before open interest changes.
+    ///
+    /// # Arguments
+    /// * `size_delta` - Change in the position's size (positive = open, negative = close)
+    ///
+    /// # Returns
+    /// (execution_price, price_impact) - impact in USD, positive = favorable
+    fn get_execution_price(
+        env: &Env,
+        asset: &Symbol,
+        config: &MarketConfig,
+        price: i128,
+        direction: Direction,
+        size_delta: i128,
+    ) -> Result<(i128, i128), NoetherError> {
+        let skew_before = get_total_long_size(env, asset) - get_total_short_size(env, asset);
+        let skew_delta = match direction {
+            Direction::Long => size_delta,
+            Direction::Short => -size_delta,
+        };
+
+        let impact = calculate_price_impact(
+            skew_before,
+            skew_before + skew_delta,
+            config.price_impact_factor,
+            config.price_impact_exponent,
+        )?;
+
+        // Growing the long side (or shrinking the short side) buys the asset
+        let execution_price =
+            calculate_execution_price(price, size_delta.abs(), impact, skew_delta > 0)?;
+
+        Ok((execution_price, impact))
+    }
+
+    /// Reserve Vault liquidity for a potential payout.
+    /// Fails if the Vault's unreserved liquidity cannot cover it; the failed
+    /// call leaves no reservation behind, so batches can skip the ID.
+    fn reserve_vault_liquidity(env: &Env, vault: &Address, amount: i128) -> Result<(), NoetherError> {
+        // Call vault's reserve_for_position function
+        // This records the reservation without moving funds
+        let args: Vec<soroban_sdk::Val> = (amount,).into_val(env);
+        let result = env.try_invoke_contract::<(), soroban_sdk::Error>(
+            vault,
+            &Symbol::new(env, "reserve_for_position"),
+            args,
+        );
+
+        match result {
+            Ok(Ok(())) => Ok(()),
+            _ => Err(NoetherError::InsufficientLiquidity),
+        }
+    }
+
+    /// Release Vault liquidity reserved for a closed (or reduced) position.
+    /// Callers pass the reservation recorded on the position (or its closed share).
+    fn release_vault_liquidity(env: &Env, amount: i128) -> Result<(), NoetherError> {
+        if amount <= 0 {
+            return Ok(());
+        }
+
+        let args: Vec<soroban_sdk::Val> = (amount,).into_val(env);
+        let _: () = env.invoke_contract(
+            &get_vault(env),
+            &Symbol::new(env, "release_reservation"),
+            args,
+        );
+
+        Ok(())
+    }
+
+    /// Settle PnL with vault contract.
+    fn settle_with_vault(env: &Env, vault: &Address, pnl: i128) -> Result<(), NoetherError> {
+        // Call vault's settle_pnl function
+        // - If pnl > 0: Vault transfers profit to Market
+        // - If pnl < 0: Vault updates accounting (Market transfers loss separately)
+        let args: Vec<soroban_sdk::Val> = (pnl,).into_val(env);
+        let _: () = env.invoke_contract(
+            vault,
+            &Symbol::new(env, "settle_pnl"),
+            args,
+        );
+
+        Ok(())
+    }
+
+    /// Settle realized funding with the vault and record it in the funding ledger.
+    /// - If funding > 0: trader paid, Market transfers it to Vault
+    /// - If funding < 0: trader is owed, Vault transfers it to Market
+    fn settle_funding_with_vault(
+        env: &Env,
+        vault: &Address,
+        asset: &Symbol,
+        funding: i128,
+    ) -> Result<(), NoetherError> {
+        if funding == 0 {
+            return Ok(());
+        }
+
+        let args: Vec<soroban_sdk::Val> = (funding,).into_val(env);
+        let _: () = env.invoke_contract(
+            vault,
+            &Symbol::new(env, "settle_funding"),
+            args,
+        );
+
+        if funding > 0 {
+            let usdc_token = get_usdc_token(env);
+            let token_client = token::Client::new(env, &usdc_token);
+            token_client.transfer(&env.current_contract_address(), vault, &funding);
+        }
+
+        record_realized_funding(env, asset, funding);
+
+        Ok(())
+    }
+
+    /// Trading fee on `size` for a trader, after any referral discount.
+    fn trading_fee(env: &Env, trader: &Address, size: i128, config: &MarketConfig) -> i128 {
+        let fee = calculate_trading_fee(size, config.trading_fee_bps);
+
+        match Self::referral_tier(env, trader) {
+            Some((_, tier)) => apply_referral_discount(fee, tier.discount_bps),
+            None => fee,
+        }
+    }
+
+    /// Referrer and tier applying to a trader's fees, if they set a code
+    /// whose tier is configured.
+    fn referral_tier(env: &Env, trader: &Address) -> Option<(Address, ReferralTier)> {
+        let code = get_referrer(env, trader)?;
+        let referral = get_referral_code(env, &code)?;
+        let tier = get_referral_tiers(env).get(referral.tier)?;
+        Some((referral.owner, tier))
+    }
+
+    /// Send a trading fee to the vault, keeping the referrer's rebate and the
+    /// insurance fund's share in the market.
+    fn collect_trading_fee(
+        env: &Env,
+        vault: &Address,
+        config: &MarketConfig,
+        trader: &Address,
+        fee: i128,
+    ) {
+        let mut fee = fee;
+
+        // Accrue the referrer's rebate, claimable later
+        if let Some((owner, tier)) = Self::referral_tier(env, trader) {
+            let rebate = calculate_referral_rebate(fee, tier.rebate_bps);
+            if rebate > 0 {
+                let mut stats = get_referral_stats(env, &owner);
+                stats.total_rewards += rebate;
+                stats.claimable += rebate;
+                set_referral_stats(env, &owner, &stats);
+                fee -= rebate;
+
+                env.events().publish(
+                    (Symbol::new(env, "referral_reward"),),
+                    (owner, trader.clone(), rebate, stats.claimable),
+                );
+            }
+        }
+
+        let insurance = calculate_insurance_share(fee, config.insurance_fee_share_bps);
+        let to_vault = fee - insurance;
+
+        if to_vault > 0 {
+            let usdc_token = get_usdc_token(env);
+            let token_client = token::Client::new(env, &usdc_token);
+            token_client.transfer(&env.current_contract_address(), vault, &to_vault);
+        }
+
+        Self::deposit_insurance(env, Symbol::new(env, "trading_fee"), insurance);
+    }
+
+    /// Pay a liquidation fee to the keeper, keeping the insurance fund's share in the market.
+    ///
+    /// # Returns
+    /// Amount paid to the keeper
+    fn pay_liquidation_fee(env: &Env, keeper: &Address, config: &MarketConfig, fee: i128) -> i128 {
+        let insurance = calculate_insurance_share(fee, config.insurance_fee_share_bps);
+        let to_keeper = fee - insurance;
+
+        if to_keeper > 0 {
+            let usdc_token = get_usdc_token(env);
+            let token_client = token::Client::new(env, &usdc_token);
+            token_client.transfer(&env.current_contract_address(), keeper, &to_keeper);
+        }
+
+        Self::deposit_insurance(env, Symbol::new(env, "liquidation_fee"), insurance);
+
+        to_keeper
+    }
+
+    /// Credit USDC already held by the market to the insurance fund.
+    fn deposit_insurance(env: &Env, source: Symbol, amount: i128) {
+        if amount <= 0 {
+            return;
+        }
+
+        let balance = get_insurance_fund(env) + amount;
+        set_insurance_fund(env, balance);
+
+        env.events().publish(
+            (Symbol::new(env, "insurance_deposit"),),
+            (source, amount, balance),
+        );
+    }
+
+    /// Pay the vault a liquidated position's bad debt from the insurance fund.
+    /// Whatever the fund cannot cover is absorbed by LPs.
+    ///
+    /// # Returns
+    /// Amount covered by the fund
+    fn cover_bad_debt(
+        env: &Env,
+        vault: &Address,
+        position_id: u64,
+        asset: &Symbol,
+        bad_debt: i128,
+    ) -> Result<i128, NoetherError> {
+        if bad_debt <= 0 {
+            return Ok(0);
+        }
+
+        let fund = get_insurance_fund(env);
+        let covered = calculate_bad_debt_cover(bad_debt, fund);
+
+        // Paid to the vault as additional trader loss
+        if covered > 0 {
+            Self::settle_with_vault(env, vault, -covered)?;
+            let usdc_token = get_usdc_token(env);
+            let token_client = token::Client::new(env, &usdc_token);
+            token_client.transfer(&env.current_contract_address(), vault, &covered);
+            set_insurance_fund(env, fund - covered);
+        }
+
+        env.events().publish(
+            (Symbol::new(env, "bad_debt_covered"),),
+            (position_id, asset.clone(), bad_debt, covered, fund - covered),
+        );
+
+        Ok(covered)
+    }
+
+    /// Settle a closed position (or closed slice of one) and pay the trader.
+    ///
+    /// # Flow
+    /// 1. Settle PnL with vault (vault pays profit to Market on wins)
+    /// 2. Transfer the loss to Vault if trader lost
+    /// 3. Settle funding with vault (paid to or received from the pool)
+    /// 4. Credit trader's margin account: collateral + pnl - funding - fees
+    ///
+    /// Losses, fees and funding are capped at what the position itself
+    /// holds, so the payout never draws on other traders' collateral.
+    /// Cross-margin callers top the collateral up first (`draw_cross_cover`).
+    ///
+    /// # Returns
+    /// (amount paid to the trader, fees withheld for the caller to pay out)
+    fn settle_close(
+        env: &Env,
+        trader: &Address,
+        asset: &Symbol,
+        collateral: i128,
+        pnl: i128,
+        funding: i128,
+        fees: i128,
+    ) -> Result<(i128, i128), NoetherError> {
+        let pnl = pnl.max(-collateral);
+        let fees = fees.min(collateral + pnl);
+        let funding = funding.min((collateral + pnl - fees).max(0));
+        let to_trader = collateral + pnl - funding - fees;
+
+        // - If pnl > 0: Vault transfers profit to Market
+        // - If pnl < 0: Vault just updates accounting
+        let vault_address = get_vault(env);
+        Self::settle_with_vault(env, &vault_address, pnl)?;
+
+        let usdc_token = get_usdc_token(env);
+        let token_client = token::Client::new(env, &usdc_token);
+
+        // If trader lost, transfer the loss amount to Vault
+        // (Vault's settle_pnl already updated accounting, now transfer actual tokens)
+        if pnl < 0 {
+            let loss = -pnl;
+            token_client.transfer(&env.current_contract_address(), &vault_address, &loss);
+        }
+
+        // Funding paid goes to the pool, funding received comes from it
+        Self::settle_funding_with_vault(env, &vault_address, asset, funding)?;
+
+        // Credit the trader's margin account (if positive)
+        Self::credit_margin(env, trader, to_trader);
+
+        Ok((to_trader, fees))
+    }
+
+    /// Sum unrealized PnL (net of pending fees) of an asset's positions and
+    /// collect ADL scores of profitable ones.
+    ///
+    /// # Returns
+    /// (total_pnl, scores)
+    fn get_adl_scores(
+        env: &Env,
+        asset: &Symbol,
+        current_price: i128,
+    ) -> Result<(i128, Vec<i128>), NoetherError> {
+        let all_positions = get_all_position_ids(env);
+        let mut total_pnl = 0i128;
+        let mut scores = Vec::new(env);
+
+        for i in 0..all_positions.len() {
+            if let Some(position) = get_position(env, all_positions.get(i).unwrap()) {
+                if position.asset != *asset {
+                    continue;
+                }
+                let pnl = Self::adl_pnl(env, &position, current_price)?;
+                total_pnl += pnl;
+
+                let score = calculate_adl_score(pnl, position.collateral);
+                if score > 0 {
+                    scores.push_back(score);
+                }
+            }
+        }
+
+        Ok((total_pnl, scores))
+    }
+
+    /// Unrealized PnL of a position net of funding and borrow fees owed up to now.
+    fn adl_pnl(env: &Env, position: &Position, current_price: i128) -> Result<i128, NoetherError> {
+        let position = Self::with_pending_fees(env, position)?;
+        Ok(calculate_position_value(&position, current_price)? - position.collateral)
+    }
+
+    /// Rank of a score among ADL scores (1 + number of strictly higher scores).
+    fn adl_rank(scores: &Vec<i128>, score: i128) -> u32 {
+        let mut rank = 1u32;
+        for other in scores.iter() {
+            if other > score {
+                rank += 1;
+            }
+        }
+        rank
+    }
+
+    /// Close a whole position at the current price and pay it out.
+    ///
+    /// # Flow
+    /// 1. Apply pending funding and borrow fees
+    /// 2. Settle with vault at the price-impacted exit price, covering a
+    ///    cross-margin shortfall from the rest of the account
+    /// 3. Release the vault reservation and open interest
+    /// 4. Cancel attached orders (with `reason`) and delete the position
+    ///
+    /// # Returns
+    /// (pnl, exit_price, price_impact)
+    fn close_at_market(
+        env: &Env,
+        position: &mut Position,
+        config: &MarketConfig,
+        reason: &str,
+    ) -> Result<(i128, i128, i128), NoetherError> {
+        // Apply pending funding
+        Self::apply_pending_fees(env, position)?;
+
+        // Get current price and apply price impact
+        let oracle_price = Self::get_oracle_price(env, &position.asset)?;
+        let (exit_price, price_impact) = Self::get_execution_price(
+            env,
+            &position.asset,
+            config,
+            oracle_price,
+            position.direction,
+            -position.size,
+        )?;
+
+        // Calculate PnL
+        let pnl = calculate_pnl(position, exit_price)?;
+
+        // A cross-margin loss beyond the collateral is drawn from the margin balance
+        let net_pnl = pnl - position.accumulated_borrow_fee;
+        let value = position.collateral + net_pnl - position.accumulated_funding;
+        let (_, bad_debt) = Self::draw_cross_cover(env, position, value, 0);
+
+        // Settle with vault and pay out collateral +/- PnL (borrow fees go to the vault)
+        Self::settle_close(
+            env,
+            &position.trader,
+            &position.asset,
+            position.collateral,
+            net_pnl,
+            position.accumulated_funding,
+            0,
+        )?;
+
+        // Then from the insurance fund, backed by seized token collateral
+        Self::cover_cross_bad_debt(env, position, bad_debt)?;
+
+        // Release the vault reservation backing the position
+        Self::release_vault_liquidity(env, position.reserved)?;
+
+        // Update market stats
+        adjust_open_interest(env, &position.asset, position.direction, -position.size);
+
+        // Cancel orders attached to the position
+        Self::cancel_position_orders(env, position.id, None, reason);
+
+        // Delete position
+        delete_position(env, position.id, &position.trader);
+
+        Ok((pnl, exit_price, price_impact))
+    }
+
+    /// Close a fraction of a position at the current price and pay out the slice.
+    ///
+    /// # Flow
+    /// 1. Apply pending funding and borrow fees
+    /// 2. Split collateral, size and fees by `close_bps`
+    /// 3. Settle the closed slice with vault and release its reservation
+    /// 4. Shrink and save the position (leverage and liquidation price are unchanged)
+    ///
+    /// # Returns
+    /// (pnl, close_size, close_funding, exit_price, price_impact) of the closed slice
+    fn reduce_position(
+        env: &Env,
+        position: &mut Position,
+        config: &MarketConfig,
+        close_bps: u32,
+    ) -> Result<(i128, i128, i128, i128, i128), NoetherError> {
+        // Apply pending funding
+        Self::apply_pending_fees(env, position)?;
+
+        // Split the position
+        let (close_collateral, close_size, remaining_collateral, remaining_size) =
+            calculate_partial_close(position, close_bps);
+
+        // Get current price and apply price impact on the closed slice
+        let oracle_price = Self::get_oracle_price(env, &position.asset)?;
+        let (exit_price, price_impact) = Self::get_execution_price(
+            env,
+            &position.asset,
+            config,
+            oracle_price,
+            position.direction,
+            -close_size,
+        )?;
+        let close_funding = position.accumulated_funding * (close_bps as i128)
+            / (BASIS_POINTS as i128);
+        let close_borrow_fee = position.accumulated_borrow_fee * (close_bps as i128)
+            / (BASIS_POINTS as i128);
+
+        if remaining_collateral < config.min_collateral {
+            return Err(NoetherError::InsufficientCollateral);
+        }
+
+        // PnL on the closed slice
+        let mut closed = position.clone();
+        closed.size = close_size;
+        let pnl = calculate_pnl(&closed, exit_price)?;
+
+        // A slice that cannot cover its own loss must be liquidated instead
+        if close_collateral + pnl - close_funding - close_borrow_fee < 0 {
+            return Err(NoetherError::InsufficientMargin);
+        }
+
+        // Settle with vault and pay out the closed slice (borrow fees go to the vault)
+        Self::settle_close(
+            env,
+            &position.trader,
+            &position.asset,
+            close_collateral,
+            pnl - close_borrow_fee,
+            close_funding,
+            0,
+        )?;
+
+        // Release the vault reservation backing the closed slice
+        let close_reserved = position.reserved * (close_bps as i128) / (BASIS_POINTS as i128);
+        Self::release_vault_liquidity(env, close_reserved)?;
+
+        // Shrink the position
+        position.size = remaining_size;
+        position.collateral = remaining_collateral;
+        position.reserved -= close_reserved;
+        position.accumulated_funding -= close_funding;
+        position.accumulated_borrow_fee -= close_borrow_fee;
+        save_position(env, position);
+
+        // Update market stats
+        adjust_open_interest(env, &position.asset, position.direction, -close_size);
+
+        Ok((pnl, close_size, close_funding, exit_price, price_impact))
+    }
+
+    /// Liquidate a position that has already been checked as liquidatable.
+    /// Shared by `liquidate` and `liquidate_batch`.
+    ///
+    /// # Arguments
+    /// * `position` - The position with funding and borrow fees applied
+    /// * `current_price` - Oracle price the position was checked at
+    ///
+    /// # Returns
+    /// Keeper reward amount
+    fn liquidate_at_price(
+        env: &Env,
+        keeper: &Address,
+        mut position: Position,
+        config: &MarketConfig,
+        current_price: i128,
+    ) -> Result<i128, NoetherError> {
+        let position_id = position.id;
+
+        // Calculate PnL
+        let pnl = calculate_pnl(&position, current_price)?;
+
+        // Calculate remaining collateral after PnL, funding and borrow fees
+        let remaining = position.collateral + pnl
+            - position.accumulated_funding
+            - position.accumulated_borrow_fee;
+
+        // Calculate keeper reward (only from remaining equity, if positive)
+        let keeper_reward = if remaining > 0 {
+            calculate_keeper_reward(remaining, config.liquidation_fee_bps)
+        } else {
+            0
+        };
+
+        // Ensure keeper_reward doesn't exceed position collateral
+        let actual_keeper_reward = if keeper_reward > position.collateral {
+            position.collateral / 10 // Cap at 10% of collateral as safety
+        } else {
+            keeper_reward
+        };
+
+        // Close only part of the position when that restores its margin
+        let target_margin_bps = config.maintenance_margin_bps + config.liquidation_buffer_bps;
+        let close_bps = calculate_partial_liquidation_bps(
+            &position,
+            current_price,
+            target_margin_bps,
+            actual_keeper_reward,
+        );
+        if close_bps < BASIS_POINTS {
+            if let Some(reward) = Self::partially_liquidate(
+                env,
+                keeper,
+                &mut position,
+                config,
+                current_price,
+                close_bps,
+                actual_keeper_reward,
+            )? {
+                return Ok(reward);
+            }
+        }
+
+        // Calculate what goes to Vault (everything except keeper reward)
+        let vault_receives = if position.collateral > actual_keeper_reward {
+            position.collateral - actual_keeper_reward
+        } else {
+            0
+        };
+
+        // Get addresses and token client
+        let vault_address = get_vault(env);
+        let usdc_token = get_usdc_token(env);
+        let token_client = token::Client::new(env, &usdc_token);
+
+        // Funding owed by the position is settled out of what the vault receives;
+        // funding owed to a liquidated position is forfeited
+        let funding_paid = position.accumulated_funding.clamp(0, vault_receives);
+        let loss_paid = vault_receives - funding_paid;
+
+        // Settle with vault - pass the amount Vault is receiving (as negative pnl)
+        // This ensures Vault's total_usdc accounting matches actual token receipt
+        if loss_paid > 0 {
+            Self::settle_with_vault(env, &vault_address, -loss_paid)?;
+            token_client.transfer(&env.current_contract_address(), &vault_address, &loss_paid);
+        }
+        Self::settle_funding_with_vault(env, &vault_address, &position.asset, funding_paid)?;
+
+        // Pay keeper reward (insurance fund keeps its share)
+        let keeper_paid = Self::pay_liquidation_fee(env, keeper, config, actual_keeper_reward);
+
+        // Cover losses beyond the collateral from the insurance fund
+        let (_, _, bad_debt) =
+            calculate_liquidation_distribution(&position, current_price, config.liquidation_fee_bps);
+        Self::cover_bad_debt(env, &vault_address, position_id, &position.asset, bad_debt)?;
+
+        // Release the vault reservation backing the position
+        Self::release_vault_liquidity(env, position.reserved)?;
+
+        // Update market stats
+        adjust_open_interest(env, &position.asset, position.direction, -position.size);
+
+        // Cancel orders attached to the position
+        Self::cancel_position_orders(env, position_id, None, "position_liquidated");
+
+        // Delete position
+        delete_position(env, position_id, &position.trader);
+
+        // Emit comprehensive event with full trade data for frontend history
+        env.events().publish(
+            (Symbol::new(env, "position_liquidated"),),
+            (
+                position_id,
+                position.trader,
+                position.asset,
+                position.direction,
+                position.size,
+                position.entry_price,
+                current_price,
+                pnl,
+                keeper.clone(),
+                keeper_paid,
+            ),
+        );
+
+        Ok(keeper_paid)
+    }
+
+    /// Value a trader's account at live prices with pending fees applied.
+    ///
+    /// # Returns
+    /// (account health, positions, their oracle prices)
+    fn value_account(
+        env: &Env,
+        trader: &Address,
+    ) -> Result<(AccountHealth, Vec<Position>, Vec<i128>), NoetherError> {
+        let mut positions = Vec::new(env);
+        let mut prices = Vec::new(env);
+        let mut equity = get_margin_balance(env, trader) + Self::token_collateral_value(env, trader);
+        let mut maintenance_margin = 0;
+        let mut initial_margin = 0;
+
+        for position in get_trader_positions(env, trader).iter() {
+            let config = require_market(env, &position.asset)?;
+            let position = Self::with_pending_fees(env, &position)?;
+            let price = Self::get_oracle_price(env, &position.asset)?;
+
+            equity += calculate_position_value(&position, price)?;
+            maintenance_margin +=
+                calculate_liquidation_threshold(position.size, config.maintenance_margin_bps);
+            initial_margin +=
+                position.size * (config.initial_margin_bps as i128) / (BASIS_POINTS as i128);
+
+            positions.push_back(position);
+            prices.push_back(price);
+        }
+
+        let health = AccountHealth {
+            equity,
+            maintenance_margin,
+            initial_margin,
+            liquidatable: !positions.is_empty()
+                && is_account_liquidatable(equity, maintenance_margin),
+        };
+
+        Ok((health, positions, prices))
+    }
+
+    /// Haircut value of a trader's token collateral at oracle prices.
+    /// Tokens whose oracle price is stale count for nothing.
+    fn token_collateral_value(env: &Env, trader: &Address) -> i128 {
+        let mut value = 0;
+
+        for token in get_collateral_assets(env).iter() {
+            let balance = get_collateral_balance(env, trader, &token);
+            if balance <= 0 {
+                continue;
+            }
+            let Some(config) = get_collateral_config(env, &token) else {
+                continue;
+            };
+            if let Ok(price) = Self::get_oracle_price(env, &config.oracle_symbol) {
+                value += calculate_haircut_value(balance, price, config.haircut_bps);
+            }
+        }
+
+        value
+    }
+
+    /// Seize a trader's token collateral for a cross-margin loss, at the
+    /// full oracle price. Seized tokens stay with the market in a separate
+    /// ledger until swept for conversion; they are never booked as USDC.
+    ///
+    /// # Returns
+    /// USDC value of the tokens seized
+    fn seize_margin_tokens(env: &Env, trader: &Address, debt: i128) -> i128 {
+        let mut remaining = debt;
+
+        for token in get_collateral_assets(env).iter() {
+            if remaining <= 0 {
+                break;
+            }
+            let balance = get_collateral_balance(env, trader, &token);
+            if balance <= 0 {
+                continue;
+            }
+            let Some(config) = get_collateral_config(env, &token) else {
+                continue;
+            };
+            let Ok(price) = Self::get_oracle_price(env, &config.oracle_symbol) else {
+                continue;
+            };
+
+            let (amount, value) = calculate_seize_amount(remaining, price, balance);
+            if amount <= 0 {
+                continue;
+            }
+
+            set_collateral_balance(env, trader, &token, balance - amount);
+            set_collateral_deposits(env, &token, get_collateral_deposits(env, &token) - amount);
+            set_seized_collateral(env, &token, get_seized_collateral(env, &token) + amount);
+
+            remaining -= value;
+
+            env.events().publish(
+                (Symbol::new(env, "collateral_seized"),),
+                (trader.clone(), token, amount, price, value),
+            );
+        }
+
+        debt - remaining
+    }
+
+    /// Top up a cross-margin position's collateral from the margin balance
+    /// so that closing it can pay its own loss, funding and `fees`.
+    /// Isolated positions are left untouched.
+    ///
+    /// # Arguments
+    /// * `value` - Position value after PnL, funding and borrow fees
+    /// * `fees` - Fees the close withholds from the payout
+    ///
+    /// # Returns
+    /// (cover drawn from the balance, loss the balance could not cover)
+    fn draw_cross_cover(
+        env: &Env,
+        position: &mut Position,
+        value: i128,
+        fees: i128,
+    ) -> (i128, i128) {
+        if get_margin_mode(env, &position.trader) != MarginMode::Cross {
+            return (0, 0);
+        }
+
+        let balance = get_margin_balance(env, &position.trader);
+        let (cover, bad_debt) = calculate_cross_liquidation_cover(value, fees, balance);
+        if cover > 0 {
+            set_margin_balance(env, &position.trader, balance - cover);
+            position.collateral += cover;
+        }
+
+        (cover, bad_debt)
+    }
+
+    /// Pay the vault a cross-margin loss the margin balance could not cover.
+    /// The insurance fund pays in USDC, and token collateral worth the loss
+    /// is seized; converting it repays LPs for any part the fund could not
+    /// cover, then the fund (see `sweep_seized_collateral`).
+    fn cover_cross_bad_debt(
+        env: &Env,
+        position: &Position,
+        bad_debt: i128,
+    ) -> Result<(), NoetherError> {
+        if bad_debt <= 0 {
+            return Ok(());
+        }
+
+        let seized = Self::seize_margin_tokens(env, &position.trader, bad_debt);
+
+        let vault_address = get_vault(env);
+        let covered =
+            Self::cover_bad_debt(env, &vault_address, position.id, &position.asset, bad_debt)?;
+
+        // LPs absorb what the fund could not cover; the seized tokens repay them first
+        let recoverable = (bad_debt - covered).min(seized);
+        if recoverable > 0 {
+            set_unrecovered_bad_debt(env, get_unrecovered_bad_debt(env) + recoverable);
+        }
+
+        Ok(())
+    }
+
+    /// Close one position of a cross-margin account being liquidated.
+    /// The loss and keeper reward are drawn from the margin balance before
+    /// the insurance fund; any value left is credited back to the balance.
+    ///
+    /// # Returns
+    /// Keeper reward paid
+    fn liquidate_cross_position(
+        env: &Env,
+        keeper: &Address,
+        mut position: Position,
+        config: &MarketConfig,
+        current_price: i128,
+    ) -> Result<i128, NoetherError> {
+        let trader = position.trader.clone();
+        let pnl = calculate_pnl(&position, current_price)?;
+        let value = calculate_position_value(&position, current_price)?;
 
-        let config = get_config(&env);
+        // Keeper reward on the margin committed to the position
+        let keeper_reward = calculate_keeper_reward(position.collateral, config.liquidation_fee_bps);
 
-        // Validate parameters
-        if collateral < config.min_collateral {
-            return Err(NoetherError::InsufficientCollateral);
-        }
-        if leverage < 1 || leverage > config.max_leverage {
-            return Err(NoetherError::InvalidLeverage);
-        }
-        if trigger_price <= 0 {
-            return Err(NoetherError::InvalidTriggerPrice);
-        }
-        if slippage_tolerance_bps == 0 || slippage_tolerance_bps > 10000 {
-            return Err(NoetherError::InvalidSlippageTolerance);
-        }
+        // Cover the shortfall from the margin balance
+        let (cover, bad_debt) = Self::draw_cross_cover(env, &mut position, value, keeper_reward);
+        let keeper_reward = keeper_reward.min((value + cover).max(0));
 
-        // Calculate position size to check against limits
-        let size = calculate_position_size(collateral, leverage);
-        if size > config.max_position_size {
-            return Err(NoetherError::PositionTooLarge);
-        }
+        // Settle with vault, withholding the keeper reward; the rest goes back to the balance
+        let (_, keeper_reward) = Self::settle_close(
+            env,
+            &trader,
+            &position.asset,
+            position.collateral,
+            pnl - position.accumulated_borrow_fee,
+            position.accumulated_funding,
+            keeper_reward,
+        )?;
 
-        // Transfer collateral from trader to market contract (lock it)
-        let usdc_token = get_usdc_token(&env);
-        let token_client = token::Client::new(&env, &usdc_token);
-        token_client.transfer(&trader, &env.current_contract_address(), &collateral);
+        // Pay keeper reward (insurance fund keeps its share)
+        let keeper_paid = Self::pay_liquidation_fee(env, keeper, config, keeper_reward);
 
-        // Generate order ID
-        let order_id = next_order_id(&env);
+        // Cover the loss the USDC balance could not from the insurance fund,
+        // seizing token collateral to replenish it
+        Self::cover_cross_bad_debt(env, &position, bad_debt)?;
 
-        // Create order
-        let trigger_condition = if trigger_above {
-            TriggerCondition::Above
-        } else {
-            TriggerCondition::Below
-        };
+        // Release the vault reservation backing the position
+        Self::release_vault_liquidity(env, position.reserved)?;
 
-        let order = Order {
-            id: order_id,
-            trader: trader.clone(),
-            asset: asset.clone(),
-            order_type: OrderType::LimitEntry,
-            direction,
-            collateral,
-            leverage,
-            trigger_price,
-            trigger_condition,
-            slippage_tolerance_bps,
-            position_id: 0,
-            has_position: false,
-            created_at: env.ledger().timestamp(),
-            status: OrderStatus::Pending,
-        };
+        // Update market stats
+        adjust_open_interest(env, &position.asset, position.direction, -position.size);
 
-        // Store order
-        save_order(&env, &order);
+        // Cancel orders attached to the position
+        Self::cancel_position_orders(env, position.id, None, "position_liquidated");
 
-        extend_instance_ttl(&env);
+        // Delete position
+        delete_position(env, position.id, &trader);
 
         env.events().publish(
-            (Symbol::new(&env, "order_placed"),),
-            (order_id, trader, asset, OrderType::LimitEntry, trigger_price, direction),
+            (Symbol::new(env, "position_liquidated"),),
+            (
+                position.id,
+                trader,
+                position.asset,
+                position.direction,
+                position.size,
+                position.entry_price,
+                current_price,
+                pnl,
+                keeper.clone(),
+                keeper_paid,
+            ),
         );
 
-        Ok(order)
+        Ok(keeper_paid)
     }
 
-    /// Set a stop-loss order on an existing position.
-    /// Automatically closes the position when price reaches trigger to limit losses.
+    /// Liquidate a slice of a position and keep the rest open.
+    /// The slice realizes its PnL, funding and borrow fees against the
+    /// position's collateral, and the keeper is paid `close_bps` of the full reward.
     ///
-    /// # Arguments
-    /// * `trader` - Address of the trader (must own position)
-    /// * `position_id` - ID of the position to protect
-    /// * `trigger_price` - Price at which to close (7 decimals)
-    /// * `slippage_tolerance_bps` - Max allowed slippage in basis points
-    pub fn set_stop_loss(
-        env: Env,
-        trader: Address,
-        position_id: u64,
-        trigger_price: i128,
-        slippage_tolerance_bps: u32,
-    ) -> Result<Order, NoetherError> {
-        require_initialized(&env)?;
-        require_not_paused(&env)?;
+    /// # Returns
+    /// Keeper reward, or None when the remaining collateral would fall below
+    /// the market minimum (the position must be liquidated fully)
+    fn partially_liquidate(
+        env: &Env,
+        keeper: &Address,
+        position: &mut Position,
+        config: &MarketConfig,
+        current_price: i128,
+        close_bps: u32,
+        full_keeper_reward: i128,
+    ) -> Result<Option<i128>, NoetherError> {
+        let fraction = close_bps as i128;
+        let bps = BASIS_POINTS as i128;
+
+        let close_size = position.size * fraction / bps;
+        let close_funding = position.accumulated_funding * fraction / bps;
+        let close_borrow_fee = position.accumulated_borrow_fee * fraction / bps;
+        let keeper_reward = full_keeper_reward * fraction / bps;
+
+        // PnL on the liquidated slice
+        let mut closed = position.clone();
+        closed.size = close_size;
+        let pnl = calculate_pnl(&closed, current_price)?;
+
+        let remaining_collateral =
+            position.collateral + pnl - close_funding - close_borrow_fee - keeper_reward;
+        if remaining_collateral < config.min_collateral {
+            return Ok(None);
+        }
 
-        trader.require_auth();
+        let vault_address = get_vault(env);
+        let usdc_token = get_usdc_token(env);
+        let token_client = token::Client::new(env, &usdc_token);
 
-        // Validate slippage
-        if slippage_tolerance_bps == 0 || slippage_tolerance_bps > 10000 {
-            return Err(NoetherError::InvalidSlippageTolerance);
+        // Settle the slice with the vault (borrow fees go to the vault)
+        let vault_pnl = pnl - close_borrow_fee;
+        Self::settle_with_vault(env, &vault_address, vault_pnl)?;
+        if vault_pnl < 0 {
+            token_client.transfer(&env.current_contract_address(), &vault_address, &(-vault_pnl));
         }
+        Self::settle_funding_with_vault(env, &vault_address, &position.asset, close_funding)?;
+
+        // Pay keeper reward (insurance fund keeps its share)
+        let keeper_paid = Self::pay_liquidation_fee(env, keeper, config, keeper_reward);
+
+        // Release the vault reservation backing the liquidated slice
+        let close_reserved = position.reserved * fraction / bps;
+        Self::release_vault_liquidity(env, close_reserved)?;
+
+        // Shrink the position; its leverage falls back under maintenance
+        position.size -= close_size;
+        position.reserved -= close_reserved;
+        position.collateral = remaining_collateral;
+        position.accumulated_funding -= close_funding;
+        position.accumulated_borrow_fee -= close_borrow_fee;
+        position.leverage = calculate_effective_leverage(position.size, position.collateral);
+        position.liquidation_price =
+            calculate_equity_liquidation_price(position, config.maintenance_margin_bps);
+        save_position(env, position);
 
-        // Get position
-        let position = get_position(&env, position_id)
-            .ok_or(NoetherError::PositionNotFound)?;
+        // Update market stats
+        adjust_open_interest(env, &position.asset, position.direction, -close_size);
 
-        // Verify ownership
-        if position.trader != trader {
-            return Err(NoetherError::NotPositionOwner);
-        }
+        env.events().publish(
+            (Symbol::new(env, "position_partially_liquidated"),),
+            (
+                position.id,
+                position.trader.clone(),
+                position.asset.clone(),
+                close_size,
+                current_price,
+                pnl,
+                keeper_paid,
+                position.size,
+                keeper.clone(),
+            ),
+        );
 
-        // Check if SL already exists
-        if get_position_stop_loss(&env, position_id).is_some() {
-            return Err(NoetherError::OrderAlreadyExists);
-        }
+        Ok(Some(keeper_paid))
+    }
 
-        // Validate trigger price based on direction
-        // For Long: stop-loss must be BELOW entry price (triggers when price falls)
-        // For Short: stop-loss must be ABOVE entry price (triggers when price rises)
-        match position.direction {
-            Direction::Long => {
-                if trigger_price >= position.entry_price {
-                    return Err(NoetherError::InvalidTriggerPrice);
-                }
-            }
-            Direction::Short => {
-                if trigger_price <= position.entry_price {
-                    return Err(NoetherError::InvalidTriggerPrice);
-                }
-            }
-        }
+    /// Compute the cumulative funding indices of an asset as of now (read-only).
+    ///
+    /// # Returns
+    /// (long_index, short_index) scaled by FUNDING_INDEX_PRECISION
+    fn current_funding_indices(env: &Env, asset: &Symbol) -> Result<(i128, i128), NoetherError> {
+        let config = require_market(env, asset)?;
+        let (long_index, short_index) = get_cumulative_funding(env, asset);
+
+        let elapsed = env.ledger().timestamp().saturating_sub(get_last_funding_time(env, asset));
+        let delta = calculate_funding_index_delta(
+            get_total_long_size(env, asset),
+            get_total_short_size(env, asset),
+            config.base_funding_rate_bps,
+            elapsed,
+        );
 
-        // Determine trigger condition
-        // Long position: close when price <= trigger (price falling)
-        // Short position: close when price >= trigger (price rising)
-        let trigger_condition = match position.direction {
-            Direction::Long => TriggerCondition::Below,
-            Direction::Short => TriggerCondition::Above,
-        };
+        // Longs pay what shorts receive per unit of size, and vice versa
+        Ok((long_index + delta, short_index - delta))
+    }
 
-        // Generate order ID
-        let order_id = next_order_id(&env);
+    /// Accrue the cumulative funding indices of an asset up to now.
+    /// Must run before open interest changes so past time accrues at the old imbalance.
+    fn accrue_funding(env: &Env, asset: &Symbol) -> Result<(i128, i128), NoetherError> {
+        let (long_index, short_index) = Self::current_funding_indices(env, asset)?;
 
-        // Create order
-        let order = Order {
-            id: order_id,
-            trader: trader.clone(),
-            asset: position.asset.clone(),
-            order_type: OrderType::StopLoss,
-            direction: position.direction.clone(),
-            collateral: 0, // No collateral locked for SL
-            leverage: position.leverage,
-            trigger_price,
-            trigger_condition,
-            slippage_tolerance_bps,
-            position_id,
-            has_position: true,
-            created_at: env.ledger().timestamp(),
-            status: OrderStatus::Pending,
+        set_cumulative_funding(env, asset, long_index, short_index);
+        set_last_funding_time(env, asset, env.ledger().timestamp());
+
+        Ok((long_index, short_index))
+    }
+
+    /// Move funding owed since the position's index snapshot into accumulated_funding.
+    fn settle_position_funding(env: &Env, position: &mut Position, indices: (i128, i128)) {
+        let current_index = match position.direction {
+            Direction::Long => indices.0,
+            Direction::Short => indices.1,
         };
 
-        // Store order and link to position
-        save_order(&env, &order);
-        set_position_stop_loss(&env, position_id, order_id);
+        position.accumulated_funding +=
+            calculate_funding_owed(position.size, position.funding_index, current_index);
+        position.funding_index = current_index;
+        position.last_funding_time = env.ledger().timestamp();
+    }
 
-        extend_instance_ttl(&env);
+    /// Get vault utilization and the asset's borrow rate at that utilization.
+    ///
+    /// # Returns
+    /// (utilization_bps, borrow_rate_bps per year)
+    fn current_borrow_rate(env: &Env, config: &MarketConfig) -> (i128, i128) {
+        let utilization_bps: i128 = env.invoke_contract(
+            &get_vault(env),
+            &Symbol::new(env, "get_utilization"),
+            Vec::new(env),
+        );
 
-        env.events().publish(
-            (Symbol::new(&env, "stop_loss_set"),),
-            (order_id, position_id, trigger_price),
+        let rate = calculate_borrow_rate_bps(
+            utilization_bps,
+            config.borrow_base_rate_bps,
+            config.borrow_slope_bps,
+            config.borrow_kink_slope_bps,
+            config.optimal_utilization_bps,
         );
 
-        Ok(order)
+        (utilization_bps, rate)
     }
 
-    /// Set a take-profit order on an existing position.
-    /// Automatically closes the position when price reaches trigger to lock in profits.
-    ///
-    /// # Arguments
-    /// * `trader` - Address of the trader (must own position)
-    /// * `position_id` - ID of the position
-    /// * `trigger_price` - Price at which to close (7 decimals)
-    /// * `slippage_tolerance_bps` - Max allowed slippage in basis points
-    pub fn set_take_profit(
-        env: Env,
-        trader: Address,
-        position_id: u64,
-        trigger_price: i128,
-        slippage_tolerance_bps: u32,
-    ) -> Result<Order, NoetherError> {
-        require_initialized(&env)?;
-        require_not_paused(&env)?;
-
-        trader.require_auth();
+    /// Compute the cumulative borrow index of an asset as of now (read-only).
+    fn current_borrow_index(env: &Env, asset: &Symbol) -> Result<i128, NoetherError> {
+        let config = require_market(env, asset)?;
+        let index = get_cumulative_borrow(env, asset);
 
-        // Validate slippage
-        if slippage_tolerance_bps == 0 || slippage_tolerance_bps > 10000 {
-            return Err(NoetherError::InvalidSlippageTolerance);
+        let elapsed = env.ledger().timestamp().saturating_sub(get_last_borrow_time(env, asset));
+        if elapsed == 0 {
+            return Ok(index);
         }
 
-        // Get position
-        let position = get_position(&env, position_id)
-            .ok_or(NoetherError::PositionNotFound)?;
+        let (_, rate) = Self::current_borrow_rate(env, &config);
+        Ok(index + calculate_borrow_index_delta(rate, elapsed))
+    }
 
-        // Verify ownership
-        if position.trader != trader {
-            return Err(NoetherError::NotPositionOwner);
-        }
+    /// Accrue the cumulative borrow index of an asset up to now.
+    /// Must run before open interest changes so past time accrues at the old utilization.
+    fn accrue_borrow(env: &Env, asset: &Symbol) -> Result<i128, NoetherError> {
+        let index = Self::current_borrow_index(env, asset)?;
+        set_cumulative_borrow(env, asset, index, env.ledger().timestamp());
+        Ok(index)
+    }
 
-        // Check if TP already exists
-        if get_position_take_profit(&env, position_id).is_some() {
-            return Err(NoetherError::OrderAlreadyExists);
-        }
+    /// Move borrow fees owed since the position's index snapshot into accumulated_borrow_fee.
+    fn settle_position_borrow(position: &mut Position, borrow_index: i128) {
+        position.accumulated_borrow_fee +=
+            calculate_borrow_fee(position.size, position.borrow_index, borrow_index);
+        position.borrow_index = borrow_index;
+    }
 
-        // Validate trigger price based on direction
-        // For Long: take-profit must be ABOVE entry price (profit when price rises)
-        // For Short: take-profit must be BELOW entry price (profit when price falls)
-        match position.direction {
-            Direction::Long => {
-                if trigger_price <= position.entry_price {
-                    return Err(NoetherError::InvalidTriggerPrice);
-                }
+    /// Apply pending funding and borrow fees to a position.
+    fn apply_pending_fees(env: &Env, position: &mut Position) -> Result<(), NoetherError> {
+        let indices = Self::accrue_funding(env, &position.asset)?;
+        Self::settle_position_funding(env, position, indices);
+
+        let borrow_index = Self::accrue_borrow(env, &position.asset)?;
+        Self::settle_position_borrow(position, borrow_index);
+        Ok(())
+    }
+
+    /// Copy of a position with funding and borrow fees owed up to now included (for views).
+    fn with_pending_fees(env: &Env, position: &Position) -> Result<Position, NoetherError> {
+        let indices = Self::current_funding_indices(env, &position.asset)?;
+        let borrow_index = Self::current_borrow_index(env, &position.asset)?;
+
+        let mut position = position.clone();
+        Self::settle_position_funding(env, &mut position, indices);
+        Self::settle_position_borrow(&mut position, borrow_index);
+        Ok(position)
+    }
+
+    /// Copy of a position with pending fees applied and its liquidation price
+    /// derived from the resulting equity (for views).
+    fn with_live_liquidation_price(env: &Env, position: &Position) -> Result<Position, NoetherError> {
+        let config = require_market(env, &position.asset)?;
+        let mut position = Self::with_pending_fees(env, position)?;
+        position.liquidation_price =
+            calculate_equity_liquidation_price(&position, config.maintenance_margin_bps);
+        Ok(position)
+    }
+
+    // ═══════════════════════════════════════════════════════════════════════
+    // Internal Order Functions
+    // ═══════════════════════════════════════════════════════════════════════
+
+    /// Validate a stop-loss or take-profit trigger against a position's entry.
+    ///
+    /// - Long: stop-loss BELOW entry (price falls), take-profit ABOVE entry (price rises)
+    /// - Short: stop-loss ABOVE entry (price rises), take-profit BELOW entry (price falls)
+    fn validate_close_trigger(
+        direction: Direction,
+        entry_price: i128,
+        order_type: OrderType,
+        trigger_price: i128,
+    ) -> Result<(), NoetherError> {
+        let valid = match (order_type, direction) {
+            (OrderType::StopLoss | OrderType::TrailingStop, Direction::Long) => {
+                trigger_price < entry_price
             }
-            Direction::Short => {
-                if trigger_price >= position.entry_price {
+            (OrderType::StopLoss | OrderType::TrailingStop, Direction::Short) => {
+                trigger_price > entry_price
+            }
+            (OrderType::TakeProfit, Direction::Long) => trigger_price > entry_price,
+            (OrderType::TakeProfit, Direction::Short) => trigger_price < entry_price,
+            (OrderType::LimitEntry, _) => trigger_price > 0,
+        };
+
+        if !valid {
+            return Err(NoetherError::InvalidTriggerPrice);
+        }
+        Ok(())
+    }
+
+    /// Validate a limit entry's bracket legs against its entry price.
+    fn validate_bracket(
+        direction: Direction,
+        entry_price: i128,
+        stop_loss_price: Option<i128>,
+        take_profit_price: Option<i128>,
+    ) -> Result<(), NoetherError> {
+        for (order_type, leg) in [
+            (OrderType::StopLoss, stop_loss_price),
+            (OrderType::TakeProfit, take_profit_price),
+        ] {
+            if let Some(leg) = leg {
+                if leg <= 0 {
                     return Err(NoetherError::InvalidTriggerPrice);
                 }
+                Self::validate_close_trigger(direction, entry_price, order_type, leg)?;
             }
         }
+        Ok(())
+    }
 
-        // Determine trigger condition
-        // Long position: close when price >= trigger (price rising to target)
-        // Short position: close when price <= trigger (price falling to target)
-        let trigger_condition = match position.direction {
-            Direction::Long => TriggerCondition::Above,
-            Direction::Short => TriggerCondition::Below,
+    /// Validate a stop-loss or take-profit trigger against a position's entry
+    /// price, then create the order and link it to the position.
+    /// Shared by `set_stop_loss` and `set_take_profit`.
+    fn attach_close_order(
+        env: &Env,
+        position: &Position,
+        order_type: OrderType,
+        trigger_price: i128,
+        slippage_tolerance_bps: u32,
+        expires_at: Option<u64>,
+    ) -> Result<Order, NoetherError> {
+        Self::validate_close_trigger(
+            position.direction,
+            position.entry_price,
+            order_type,
+            trigger_price,
+        )?;
+
+        Ok(Self::link_close_order(
+            env,
+            position,
+            order_type,
+            trigger_price,
+            slippage_tolerance_bps,
+            expires_at,
+        ))
+    }
+
+    /// Create a stop-loss or take-profit order whose trigger has been
+    /// validated and link it to a position.
+    ///
+    /// # Flow
+    /// 1. Derive the trigger condition from the position direction
+    /// 2. Store the order, link it to the position and emit the set event
+    fn link_close_order(
+        env: &Env,
+        position: &Position,
+        order_type: OrderType,
+        trigger_price: i128,
+        slippage_tolerance_bps: u32,
+        expires_at: Option<u64>,
+    ) -> Order {
+        // Long stop-loss and short take-profit trigger on a falling price,
+        // long take-profit and short stop-loss on a rising one
+        let trigger_condition = match (order_type, position.direction) {
+            (OrderType::TakeProfit, Direction::Long) => TriggerCondition::Above,
+            (OrderType::TakeProfit, Direction::Short) => TriggerCondition::Below,
+            (_, Direction::Long) => TriggerCondition::Below,
+            (_, Direction::Short) => TriggerCondition::Above,
         };
 
-        // Generate order ID
-        let order_id = next_order_id(&env);
+        let order_id = next_order_id(env);
 
-        // Create order
         let order = Order {
             id: order_id,
-            trader: trader.clone(),
+            trader: position.trader.clone(),
             asset: position.asset.clone(),
-            order_type: OrderType::TakeProfit,
-            direction: position.direction.clone(),
-            collateral: 0, // No collateral locked for TP
+            order_type,
+            direction: position.direction,
+            collateral: 0, // No collateral locked for SL/TP
             leverage: position.leverage,
             trigger_price,
             trigger_condition,
             slippage_tolerance_bps,
-            position_id,
+            position_id: position.id,
             has_position: true,
             created_at: env.ledger().timestamp(),
+            expires_at,
+            trail_bps: 0,
+            trail_amount: 0,
+            water_mark: 0,
+            stop_loss_price: None,
+            take_profit_price: None,
             status: OrderStatus::Pending,
         };
 
         // Store order and link to position
-        save_order(&env, &order);
-        set_position_take_profit(&env, position_id, order_id);
-
-        extend_instance_ttl(&env);
+        save_order(env, &order);
+        let event = if order_type == OrderType::TakeProfit {
+            set_position_take_profit(env, position.id, order_id);
+            "take_profit_set"
+        } else {
+            set_position_stop_loss(env, position.id, order_id);
+            "stop_loss_set"
+        };
 
         env.events().publish(
-            (Symbol::new(&env, "take_profit_set"),),
-            (order_id, position_id, trigger_price),
+            (Symbol::new(env, event),),
+            (order_id, position.id, trigger_price),
         );
 
-        Ok(order)
+        order
     }
 
-    /// Cancel a pending order.
-    /// For limit orders, refunds the locked collateral.
-    ///
-    /// # Arguments
-    /// * `trader` - Address of the trader (must own order)
-    /// * `order_id` - ID of the order to cancel
-    pub fn cancel_order(
-        env: Env,
-        trader: Address,
-        order_id: u64,
-    ) -> Result<(), NoetherError> {
-        require_initialized(&env)?;
-
-        trader.require_auth();
-
-        // Get order
-        let order = get_order(&env, order_id)
-            .ok_or(NoetherError::OrderNotFound)?;
-
-        // Verify ownership
-        if order.trader != trader {
-            return Err(NoetherError::NotOrderOwner);
+    /// Validate an order expiry timestamp (must be in the future if set).
+    fn validate_expiry(env: &Env, expires_at: Option<u64>) -> Result<(), NoetherError> {
+        if let Some(expires_at) = expires_at {
+            if expires_at <= env.ledger().timestamp() {
+                return Err(NoetherError::InvalidParameter);
+            }
         }
+        Ok(())
+    }
 
-        // Check if still pending
-        if order.status != OrderStatus::Pending {
-            return Err(NoetherError::OrderNotPending);
+    /// Check whether an order is past its expiry timestamp.
+    fn is_order_expired(env: &Env, order: &Order) -> bool {
+        match order.expires_at {
+            Some(expires_at) => env.ledger().timestamp() >= expires_at,
+            None => false,
         }
+    }
 
-        // Refund collateral for limit orders
-        if order.order_type == OrderType::LimitEntry && order.collateral > 0 {
-            let usdc_token = get_usdc_token(&env);
-            let token_client = token::Client::new(&env, &usdc_token);
-            token_client.transfer(&env.current_contract_address(), &trader, &order.collateral);
+    /// Take an order off the book: refund escrowed limit order collateral
+    /// to the margin account, remove its SL/TP link and set its final status.
+    fn close_out_order(env: &Env, order: &Order, status: OrderStatus) {
+        if order.order_type == OrderType::LimitEntry {
+            Self::credit_margin(env, &order.trader, order.collateral);
         }
 
-        // Remove SL/TP links if attached to position
         if order.has_position {
             match order.order_type {
-                OrderType::StopLoss => remove_position_stop_loss(&env, order.position_id),
-                OrderType::TakeProfit => remove_position_take_profit(&env, order.position_id),
+                OrderType::StopLoss | OrderType::TrailingStop => {
+                    remove_position_stop_loss(env, order.position_id)
+                }
+                OrderType::TakeProfit => remove_position_take_profit(env, order.position_id),
                 _ => {}
             }
         }
 
-        // Update order status
-        update_order_status(&env, order_id, OrderStatus::Cancelled);
+        update_order_status(env, order.id, status);
+    }
 
-        extend_instance_ttl(&env);
+    /// Mark an order Expired, refunding and unlinking it.
+    fn expire_order(env: &Env, order: &Order) {
+        Self::close_out_order(env, order, OrderStatus::Expired);
 
         env.events().publish(
-            (Symbol::new(&env, "order_cancelled"),),
-            (order_id, trader, Symbol::new(&env, "user_cancelled")),
+            (Symbol::new(env, "order_expired"),),
+            (order.id, order.trader.clone(), order.expires_at.unwrap_or(0)),
         );
+    }
 
-        Ok(())
+    /// Check whether a stop-loss, take-profit or trailing stop has lost its position.
+    fn is_order_orphaned(env: &Env, order: &Order) -> bool {
+        order.order_type != OrderType::LimitEntry && get_position(env, order.position_id).is_none()
     }
 
-    /// Execute a triggered order (called by keeper).
-    /// Checks if price condition is met and executes the order.
-    /// Keeper receives a fee for successful execution.
+    /// Cancel a close order whose position is gone, unlinking it.
+    fn cancel_orphaned_order(env: &Env, order: &Order) {
+        Self::close_out_order(env, order, OrderStatus::Cancelled);
+
+        env.events().publish(
+            (Symbol::new(env, "order_cancelled"),),
+            (order.id, order.trader.clone(), Symbol::new(env, "position_not_found")),
+        );
+    }
+
+    /// Cancel every pending order attached to a position that is going away,
+    /// so SL and TP behave as a one-cancels-other pair.
     ///
     /// # Arguments
-    /// * `keeper` - Address of the keeper executing the order
-    /// * `order_id` - ID of the order to execute
-    ///
-    /// # Returns
-    /// Keeper reward amount
-    pub fn execute_order(
-        env: Env,
-        keeper: Address,
-        order_id: u64,
-    ) -> Result<i128, NoetherError> {
-        require_initialized(&env)?;
-
-        keeper.require_auth();
+    /// * `position_id` - Position being closed
+    /// * `executing_order_id` - Order closing the position (left to its caller)
+    /// * `reason` - Reason emitted with each `order_cancelled` event
+    fn cancel_position_orders(
+        env: &Env,
+        position_id: u64,
+        executing_order_id: Option<u64>,
+        reason: &str,
+    ) {
+        let attached = [
+            get_position_stop_loss(env, position_id),
+            get_position_take_profit(env, position_id),
+        ];
+
+        for order_id in attached.into_iter().flatten() {
+            if Some(order_id) == executing_order_id {
+                continue;
+            }
+            let order = match get_order(env, order_id) {
+                Some(order) if order.status == OrderStatus::Pending => order,
+                _ => continue,
+            };
 
-        // Get order
-        let order = get_order(&env, order_id)
-            .ok_or(NoetherError::OrderNotFound)?;
+            Self::close_out_order(env, &order, OrderStatus::Cancelled);
 
-        // Check if still pending
-        if order.status != OrderStatus::Pending {
-            return Err(NoetherError::OrderNotPending);
+            env.events().publish(
+                (Symbol::new(env, "order_cancelled"),),
+                (order_id, order.trader, Symbol::new(env, reason)),
+            );
         }
 
-        // Get current price
-        let current_price = Self::get_oracle_price(&env, &order.asset)?;
+        // Drop any links left behind (including the executing order's)
+        remove_position_stop_loss(env, position_id);
+        remove_position_take_profit(env, position_id);
+    }
 
-        // Check if trigger condition is met
-        let triggered = match order.trigger_condition {
+    /// Check whether an order's trigger condition is met at a price.
+    fn is_order_triggered(order: &Order, current_price: i128) -> bool {
+        match order.trigger_condition {
             TriggerCondition::Above => current_price >= order.trigger_price,
             TriggerCondition::Below => current_price <= order.trigger_price,
-        };
-
-        if !triggered {
-            return Err(NoetherError::OrderNotTriggered);
         }
+    }
+
+    /// Execute a pending order whose trigger has been checked.
+    /// Shared by `execute_order` and `execute_orders_batch`.
+    /// Orders filled outside their slippage tolerance are cancelled (reward 0).
+    ///
+    /// # Returns
+    /// Keeper reward amount
+    fn execute_order_at_price(
+        env: &Env,
+        keeper: &Address,
+        order: &Order,
+        current_price: i128,
+    ) -> Result<i128, NoetherError> {
+        let order_id = order.id;
 
         // Check slippage
         let price_diff = if current_price > order.trigger_price {
@@ -1216,57 +4474,52 @@ impl MarketContract {
             // and the order is properly removed from the pending list. Returning Err()
             // would rollback all state changes, leaving the order stuck in pending.
 
-            if order.order_type == OrderType::LimitEntry && order.collateral > 0 {
-                // Refund collateral
-                let usdc_token = get_usdc_token(&env);
-                let token_client = token::Client::new(&env, &usdc_token);
-                token_client.transfer(&env.current_contract_address(), &order.trader, &order.collateral);
-            }
+            Self::close_out_order(env, order, OrderStatus::CancelledSlippage);
 
-            // Remove SL/TP links
-            if order.has_position {
-                match order.order_type {
-                    OrderType::StopLoss => remove_position_stop_loss(&env, order.position_id),
-                    OrderType::TakeProfit => remove_position_take_profit(&env, order.position_id),
-                    _ => {}
-                }
-            }
+            env.events().publish(
+                (Symbol::new(env, "order_cancelled"),),
+                (order_id, order.trader.clone(), Symbol::new(env, "slippage_exceeded")),
+            );
 
-            update_order_status(&env, order_id, OrderStatus::CancelledSlippage);
+            // Return Ok(0) - no keeper reward for cancelled orders, but transaction commits
+            return Ok(0);
+        }
 
-            extend_instance_ttl(&env);
+        // Bracket legs the fill price would put on the wrong side cancel the
+        // order the same way, instead of failing every execution attempt
+        if order.order_type == OrderType::LimitEntry
+            && !Self::bracket_fits_fill(env, order, current_price)?
+        {
+            Self::close_out_order(env, order, OrderStatus::Cancelled);
 
             env.events().publish(
-                (Symbol::new(&env, "order_cancelled"),),
-                (order_id, order.trader.clone(), Symbol::new(&env, "slippage_exceeded")),
+                (Symbol::new(env, "order_cancelled"),),
+                (order_id, order.trader.clone(), Symbol::new(env, "invalid_bracket")),
             );
 
-            // Return Ok(0) - no keeper reward for cancelled orders, but transaction commits
             return Ok(0);
         }
 
         // Calculate keeper fee
-        let keeper_fee = Self::calculate_keeper_order_fee(&env, &order);
+        let keeper_fee = Self::calculate_keeper_order_fee(env, order);
 
         // Execute based on order type
         let result = match order.order_type {
             OrderType::LimitEntry => {
-                Self::execute_limit_entry(&env, &order, current_price, keeper_fee, &keeper)
+                Self::execute_limit_entry(env, order, current_price, keeper_fee, keeper)
             }
-            OrderType::StopLoss | OrderType::TakeProfit => {
-                Self::execute_close_order(&env, &order, current_price, keeper_fee, &keeper)
+            OrderType::StopLoss | OrderType::TakeProfit | OrderType::TrailingStop => {
+                Self::execute_close_order(env, order, current_price, keeper_fee, keeper)
             }
         };
 
         match result {
             Ok(reward) => {
-                update_order_status(&env, order_id, OrderStatus::Executed);
-
-                extend_instance_ttl(&env);
+                update_order_status(env, order_id, OrderStatus::Executed);
 
                 env.events().publish(
-                    (Symbol::new(&env, "order_executed"),),
-                    (order_id, order.trader, order.order_type, current_price, reward),
+                    (Symbol::new(env, "order_executed"),),
+                    (order_id, order.trader.clone(), order.order_type, current_price, reward),
                 );
 
                 Ok(reward)
@@ -1275,146 +4528,88 @@ impl MarketContract {
         }
     }
 
-    /// Check if an order should be executed at current price.
-    pub fn should_execute_order(env: Env, order_id: u64) -> Result<bool, NoetherError> {
-        let order = get_order(&env, order_id)
-            .ok_or(NoetherError::OrderNotFound)?;
-
-        if order.status != OrderStatus::Pending {
-            return Ok(false);
+    /// Check a limit entry's bracket legs against the price it would fill at
+    /// after price impact.
+    fn bracket_fits_fill(env: &Env, order: &Order, current_price: i128) -> Result<bool, NoetherError> {
+        if order.stop_loss_price.is_none() && order.take_profit_price.is_none() {
+            return Ok(true);
         }
 
-        let current_price = Self::get_oracle_price(&env, &order.asset)?;
-
-        let triggered = match order.trigger_condition {
-            TriggerCondition::Above => current_price >= order.trigger_price,
-            TriggerCondition::Below => current_price <= order.trigger_price,
-        };
-
-        Ok(triggered)
-    }
-
-    /// Get all orders for a trader.
-    pub fn get_orders(env: Env, trader: Address) -> Vec<Order> {
-        get_trader_orders(&env, &trader)
-    }
-
-    /// Get a specific order by ID.
-    pub fn get_order(env: Env, order_id: u64) -> Option<Order> {
-        get_order(&env, order_id)
-    }
-
-    /// Get all pending order IDs (for keeper).
-    pub fn get_all_order_ids(env: Env) -> Vec<u64> {
-        get_all_order_ids(&env)
-    }
-
-    /// Get stop-loss order ID attached to a position.
-    pub fn get_position_sl(env: Env, position_id: u64) -> Option<u64> {
-        get_position_stop_loss(&env, position_id)
-    }
+        let config = require_market(env, &order.asset)?;
+        let size = calculate_position_size(order.collateral, order.leverage);
+        let (entry_price, _) = Self::get_execution_price(
+            env,
+            &order.asset,
+            &config,
+            current_price,
+            order.direction,
+            size,
+        )?;
 
-    /// Get take-profit order ID attached to a position.
-    pub fn get_position_tp(env: Env, position_id: u64) -> Option<u64> {
-        get_position_take_profit(&env, position_id)
+        Ok(Self::validate_bracket(
+            order.direction,
+            entry_price,
+            order.stop_loss_price,
+            order.take_profit_price,
+        )
+        .is_ok())
     }
 
-    // ═══════════════════════════════════════════════════════════════════════
-    // Internal Functions
-    // ═══════════════════════════════════════════════════════════════════════
-
-    /// Fetch price from oracle adapter.
-    fn get_oracle_price(env: &Env, asset: &Symbol) -> Result<i128, NoetherError> {
-        let oracle_address = get_oracle_adapter(env);
-
-        // Call oracle adapter using invoke_contract
-        let args: Vec<soroban_sdk::Val> = (asset.clone(),).into_val(env);
-        let (price, timestamp): (i128, u64) = env.invoke_contract(
-            &oracle_address,
-            &Symbol::new(env, "lastprice"),
-            args,
-        );
-
-        // Check staleness
-        let config = get_config(env);
-        let current_time = env.ledger().timestamp();
-
-        if current_time > timestamp && current_time - timestamp > config.max_price_staleness {
-            return Err(NoetherError::PriceStale);
-        }
-
-        if price <= 0 {
-            return Err(NoetherError::InvalidPrice);
+    /// Check, without writing anything, that a triggered order can fill:
+    /// a limit entry's fees, open interest caps and vault liquidity, or a
+    /// close order's fees owed and exit price.
+    /// Used by `execute_orders_batch` to decide which orders to skip.
+    fn check_order_fill(env: &Env, order: &Order, current_price: i128) -> Result<(), NoetherError> {
+        if order.order_type != OrderType::LimitEntry {
+            let position = get_position(env, order.position_id)
+                .ok_or(NoetherError::PositionNotFound)?;
+            let config = require_market(env, &position.asset)?;
+            let position = Self::with_pending_fees(env, &position)?;
+            let (exit_price, _) = Self::get_execution_price(
+                env,
+                &position.asset,
+                &config,
+                current_price,
+                position.direction,
+                -position.size,
+            )?;
+            calculate_pnl(&position, exit_price)?;
+            return Ok(());
         }
 
-        Ok(price)
-    }
-
-    /// Check if Vault has enough liquidity for a potential payout.
-    fn check_vault_liquidity(env: &Env, vault: &Address, amount: i128) -> Result<(), NoetherError> {
-        // Call vault's reserve_for_position function
-        // This checks liquidity without moving funds
-        let args: Vec<soroban_sdk::Val> = (amount,).into_val(env);
-        let _: () = env.invoke_contract(
-            vault,
-            &Symbol::new(env, "reserve_for_position"),
-            args,
-        );
-
-        Ok(())
-    }
-
-    /// Settle PnL with vault contract.
-    fn settle_with_vault(env: &Env, vault: &Address, pnl: i128) -> Result<(), NoetherError> {
-        // Call vault's settle_pnl function
-        // - If pnl > 0: Vault transfers profit to Market
-        // - If pnl < 0: Vault updates accounting (Market transfers loss separately)
-        let args: Vec<soroban_sdk::Val> = (pnl,).into_val(env);
-        let _: () = env.invoke_contract(
-            vault,
-            &Symbol::new(env, "settle_pnl"),
-            args,
-        );
-
-        Ok(())
-    }
-
-    /// Apply pending funding to a position.
-    fn apply_funding_to_position(env: &Env, position: &mut Position) -> Result<(), NoetherError> {
-        let current_time = env.ledger().timestamp();
-        let hours_elapsed = (current_time - position.last_funding_time) / 3600;
+        let config = require_market(env, &order.asset)?;
+        let size = calculate_position_size(order.collateral, order.leverage);
+        Self::check_open_interest(env, &order.asset, &config, order.direction, size)?;
+        Self::get_execution_price(env, &order.asset, &config, current_price, order.direction, size)?;
 
-        if hours_elapsed == 0 {
-            return Ok(());
+        let fees = Self::trading_fee(env, &order.trader, size, &config)
+            + Self::calculate_keeper_order_fee(env, order);
+        if order.collateral - fees <= 0 {
+            return Err(NoetherError::InsufficientCollateral);
         }
 
-        let funding_rate = get_current_funding_rate(env);
-        let funding_payment = calculate_funding_payment(
-            position.size,
-            funding_rate,
-            position.direction.clone(),
-            hours_elapsed,
-        );
-
-        position.accumulated_funding += funding_payment;
-        position.last_funding_time = current_time;
+        let vault = get_vault(env);
+        let total_usdc: i128 =
+            env.invoke_contract(&vault, &Symbol::new(env, "get_total_usdc"), Vec::new(env));
+        let reserved_usdc: i128 =
+            env.invoke_contract(&vault, &Symbol::new(env, "get_reserved_usdc"), Vec::new(env));
+        if size > total_usdc - reserved_usdc {
+            return Err(NoetherError::InsufficientLiquidity);
+        }
 
         Ok(())
     }
 
-    // ═══════════════════════════════════════════════════════════════════════
-    // Internal Order Functions
-    // ═══════════════════════════════════════════════════════════════════════
-
     /// Calculate keeper fee for order execution.
-    /// Fee = base_fee (0.50 USDC) + variable_fee (0.05% of position size)
+    /// Fee = base_fee (0.50 USDC) + variable_fee (0.05% of position size),
+    /// less the trader's referral discount
     fn calculate_keeper_order_fee(env: &Env, order: &Order) -> i128 {
         let fee_config = KeeperFeeConfig::default();
 
         let position_size = match order.order_type {
             OrderType::LimitEntry => calculate_position_size(order.collateral, order.leverage),
-            OrderType::StopLoss | OrderType::TakeProfit => {
-                // For SL/TP, get size from the position
+            OrderType::StopLoss | OrderType::TakeProfit | OrderType::TrailingStop => {
+                // For SL/TP/trailing stops, get size from the position
                 if let Some(pos) = get_position(env, order.position_id) {
                     pos.size
                 } else {
@@ -1424,10 +4619,17 @@ impl MarketContract {
         };
 
         let variable_fee = (position_size * fee_config.variable_fee_bps as i128) / 10_000;
-        fee_config.base_fee + variable_fee
+        let fee = fee_config.base_fee + variable_fee;
+
+        match Self::referral_tier(env, &order.trader) {
+            Some((_, tier)) => apply_referral_discount(fee, tier.discount_bps),
+            None => fee,
+        }
     }
 
     /// Execute a limit entry order - opens a new position.
+    /// Bracket stop-loss/take-profit legs are attached in the same call.
+    /// Every check runs before the first write, so a failed fill leaves no state behind.
     fn execute_limit_entry(
         env: &Env,
         order: &Order,
@@ -1435,25 +4637,26 @@ impl MarketContract {
         keeper_fee: i128,
         keeper: &Address,
     ) -> Result<i128, NoetherError> {
-        let config = get_config(env);
+        let config = require_market(env, &order.asset)?;
 
         // Calculate position size
         let size = calculate_position_size(order.collateral, order.leverage);
 
-        // Check Vault has enough liquidity
-        let vault_address = get_vault(env);
-        Self::check_vault_liquidity(env, &vault_address, size)?;
+        // Check the asset and global open interest caps
+        Self::check_open_interest(env, &order.asset, &config, order.direction, size)?;
 
-        // Calculate liquidation price using current price as entry
-        let liquidation_price = calculate_liquidation_price(
+        // Apply price impact to the fill
+        let (entry_price, price_impact) = Self::get_execution_price(
+            env,
+            &order.asset,
+            &config,
             current_price,
-            order.leverage,
-            order.direction.clone(),
-            config.maintenance_margin_bps,
-        );
+            order.direction,
+            size,
+        )?;
 
         // Calculate trading fee
-        let trading_fee = calculate_trading_fee(size, config.trading_fee_bps);
+        let trading_fee = Self::trading_fee(env, &order.trader, size, &config);
 
         // Total fees = trading fee + keeper fee
         let total_fees = trading_fee + keeper_fee;
@@ -1463,44 +4666,85 @@ impl MarketContract {
             return Err(NoetherError::InsufficientCollateral);
         }
 
+        // Bracket legs must sit on the right side of the fill
+        Self::validate_bracket(
+            order.direction,
+            entry_price,
+            order.stop_loss_price,
+            order.take_profit_price,
+        )?;
+
+        // Reserve Vault liquidity for potential payout (after the checks
+        // above, so a failed fill leaves no state behind)
+        let vault_address = get_vault(env);
+        Self::reserve_vault_liquidity(env, &vault_address, size)?;
+
+        // Accrue funding and borrow fees before open interest changes and snapshot the indices
+        let (long_index, short_index) = Self::accrue_funding(env, &order.asset)?;
+        let funding_index = match order.direction {
+            Direction::Long => long_index,
+            Direction::Short => short_index,
+        };
+        let borrow_index = Self::accrue_borrow(env, &order.asset)?;
+
         // Generate position ID
         let position_id = next_position_id(env);
 
         // Create position
-        let position = Position {
+        let mut position = Position {
             id: position_id,
             trader: order.trader.clone(),
             asset: order.asset.clone(),
             collateral: net_collateral,
             size,
-            entry_price: current_price,
+            entry_price,
             direction: order.direction.clone(),
             leverage: order.leverage,
-            liquidation_price,
+            liquidation_price: 0,
             timestamp: env.ledger().timestamp(),
             last_funding_time: env.ledger().timestamp(),
             accumulated_funding: 0,
+            funding_index,
+            accumulated_borrow_fee: 0,
+            borrow_index,
+            reserved: size,
         };
+        position.liquidation_price =
+            calculate_equity_liquidation_price(&position, config.maintenance_margin_bps);
 
         // Store position
         save_position(env, &position);
 
-        // Update market stats
-        match order.direction {
-            Direction::Long => {
-                let total = get_total_long_size(env);
-                set_total_long_size(env, total + size);
-            }
-            Direction::Short => {
-                let total = get_total_short_size(env);
-                set_total_short_size(env, total + size);
-            }
+        // Attach bracket SL/TP legs (validated above)
+        if let Some(stop_loss_price) = order.stop_loss_price {
+            Self::link_close_order(
+                env,
+                &position,
+                OrderType::StopLoss,
+                stop_loss_price,
+                order.slippage_tolerance_bps,
+                None,
+            );
+        }
+        if let Some(take_profit_price) = order.take_profit_price {
+            Self::link_close_order(
+                env,
+                &position,
+                OrderType::TakeProfit,
+                take_profit_price,
+                order.slippage_tolerance_bps,
+                None,
+            );
         }
 
-        // Transfer trading fee to vault
+        // Update market stats
+        adjust_open_interest(env, &order.asset, order.direction, size);
+
+        // Transfer trading fee to vault (insurance fund keeps its share)
+        Self::collect_trading_fee(env, &vault_address, &config, &order.trader, trading_fee);
+
         let usdc_token = get_usdc_token(env);
         let token_client = token::Client::new(env, &usdc_token);
-        token_client.transfer(&env.current_contract_address(), &vault_address, &trading_fee);
 
         // Pay keeper fee
         if keeper_fee > 0 {
@@ -1510,7 +4754,16 @@ impl MarketContract {
         // Emit position opened event
         env.events().publish(
             (Symbol::new(env, "position_opened"),),
-            (position.id, order.trader.clone(), order.asset.clone(), size, order.direction.clone(), order.leverage, current_price),
+            (
+                position.id,
+                order.trader.clone(),
+                order.asset.clone(),
+                size,
+                order.direction.clone(),
+                order.leverage,
+                entry_price,
+                price_impact,
+            ),
         );
 
         Ok(keeper_fee)
@@ -1528,63 +4781,59 @@ impl MarketContract {
         let mut position = get_position(env, order.position_id)
             .ok_or(NoetherError::PositionNotFound)?;
 
-        // Apply pending funding
-        Self::apply_funding_to_position(env, &mut position)?;
-
-        // Calculate PnL
-        let pnl = calculate_pnl(&position, current_price)?;
-
-        // Calculate amount to return to trader
-        let to_trader = position.collateral + pnl - position.accumulated_funding - keeper_fee;
-
-        // Settle with vault
-        let vault_address = get_vault(env);
-        Self::settle_with_vault(env, &vault_address, pnl)?;
+        let config = require_market(env, &position.asset)?;
 
-        // Get token client for transfers
-        let usdc_token = get_usdc_token(env);
-        let token_client = token::Client::new(env, &usdc_token);
+        // Apply pending funding
+        Self::apply_pending_fees(env, &mut position)?;
 
-        // If trader lost, transfer the loss amount to Vault
-        if pnl < 0 {
-            let loss = -pnl;
-            token_client.transfer(&env.current_contract_address(), &vault_address, &loss);
-        }
+        // Apply price impact to the fill
+        let (exit_price, price_impact) = Self::get_execution_price(
+            env,
+            &position.asset,
+            &config,
+            current_price,
+            position.direction,
+            -position.size,
+        )?;
 
-        // Transfer remaining funding to vault
-        if position.accumulated_funding > 0 {
-            token_client.transfer(
-                &env.current_contract_address(),
-                &vault_address,
-                &position.accumulated_funding,
-            );
-        }
+        // Calculate PnL
+        let pnl = calculate_pnl(&position, exit_price)?;
+
+        // A cross-margin loss or fee beyond the collateral is drawn from the margin balance
+        let net_pnl = pnl - position.accumulated_borrow_fee;
+        let value = position.collateral + net_pnl - position.accumulated_funding;
+        let (_, bad_debt) = Self::draw_cross_cover(env, &mut position, value, keeper_fee);
+
+        // Settle with vault and pay out collateral +/- PnL minus the keeper fee
+        // (borrow fees go to the vault); the fee is capped at the position's equity
+        let (_, keeper_fee) = Self::settle_close(
+            env,
+            &position.trader,
+            &position.asset,
+            position.collateral,
+            net_pnl,
+            position.accumulated_funding,
+            keeper_fee,
+        )?;
+
+        // Then from the insurance fund, backed by seized token collateral
+        Self::cover_cross_bad_debt(env, &position, bad_debt)?;
 
         // Pay keeper fee
         if keeper_fee > 0 {
+            let usdc_token = get_usdc_token(env);
+            let token_client = token::Client::new(env, &usdc_token);
             token_client.transfer(&env.current_contract_address(), keeper, &keeper_fee);
         }
 
-        // Transfer to trader (if positive)
-        if to_trader > 0 {
-            token_client.transfer(&env.current_contract_address(), &position.trader, &to_trader);
-        }
+        // Release the vault reservation backing the position
+        Self::release_vault_liquidity(env, position.reserved)?;
 
         // Update market stats
-        match position.direction {
-            Direction::Long => {
-                let total = get_total_long_size(env);
-                set_total_long_size(env, total - position.size);
-            }
-            Direction::Short => {
-                let total = get_total_short_size(env);
-                set_total_short_size(env, total - position.size);
-            }
-        }
+        adjust_open_interest(env, &position.asset, position.direction, -position.size);
 
-        // Remove SL/TP links
-        remove_position_stop_loss(env, position.id);
-        remove_position_take_profit(env, position.id);
+        // Cancel the sibling SL/TP order (OCO)
+        Self::cancel_position_orders(env, position.id, Some(order.id), "oco");
 
         // Delete position
         delete_position(env, position.id, &position.trader);
@@ -1599,9 +4848,10 @@ impl MarketContract {
                 position.direction,
                 position.size,
                 position.entry_price,
-                current_price,
+                exit_price,
                 pnl,
                 position.accumulated_funding,
+                price_impact,
             ),
         );
 
@@ -1614,6 +4864,4 @@ impl MarketContract {
 // ═══════════════════════════════════════════════════════════════════════════
 
 #[cfg(test)]
-mod tests {
-    // Integration tests in separate file due to complexity
-}
+mod test;
@@ -21,7 +21,7 @@ pub fn calculate_current_margin(position: &Position, current_price: i128) -> i12
         }
     };
 
-    position.collateral + pnl - position.accumulated_funding
+    position.collateral + pnl - position.accumulated_funding - position.accumulated_borrow_fee
 }
 
 /// Calculate margin ratio for liquidation check.
@@ -34,13 +34,86 @@ pub fn calculate_margin_ratio_bps(position: &Position, current_price: i128) -> i
     current_margin * (BASIS_POINTS as i128) / position.size
 }
 
-/// Check if a position should be liquidated.
-/// Simpler check: just compare price to liquidation price.
-pub fn check_liquidation(position: &Position, current_price: i128) -> bool {
+/// Check if a position should be liquidated from its live equity.
+/// Liquidates when collateral + PnL - funding - borrow fees is at or
+/// below the maintenance margin; the stored liquidation price is not used.
+pub fn is_liquidatable(position: &Position, current_price: i128, maintenance_margin_bps: u32) -> bool {
+    calculate_current_margin(position, current_price)
+        <= calculate_liquidation_threshold(position.size, maintenance_margin_bps)
+}
+
+/// Calculate the price at which a position's equity reaches maintenance margin.
+///
+/// Solves `calculate_current_margin(position, price) = threshold`, so funding
+/// and borrow fees already accrued move the price:
+/// ```
+/// long:  price = entry × (1 - (equity_at_entry - threshold) / size)
+/// short: price = entry × (1 + (equity_at_entry - threshold) / size)
+/// ```
+///
+/// # Arguments
+/// * `position` - The position (with funding and borrow fees applied)
+/// * `maintenance_margin_bps` - Maintenance margin in basis points
+///
+/// # Returns
+/// Liquidation price (7 decimals), never negative
+pub fn calculate_equity_liquidation_price(position: &Position, maintenance_margin_bps: u32) -> i128 {
+    if position.size <= 0 {
+        return 0;
+    }
+
+    let equity_at_entry =
+        position.collateral - position.accumulated_funding - position.accumulated_borrow_fee;
+    let threshold = calculate_liquidation_threshold(position.size, maintenance_margin_bps);
+    let price_move = position.entry_price * (equity_at_entry - threshold) / position.size;
+
     match position.direction {
-        Direction::Long => current_price <= position.liquidation_price,
-        Direction::Short => current_price >= position.liquidation_price,
+        Direction::Long => (position.entry_price - price_move).max(0),
+        Direction::Short => (position.entry_price + price_move).max(0),
+    }
+}
+
+/// Calculate the fraction of a position to liquidate to restore its margin.
+///
+/// The liquidated slice realizes its PnL against the collateral that stays in
+/// the position, so only the pro-rated keeper reward reduces equity:
+/// ```
+/// (equity - reward × f) / (size × (1 - f)) = target
+/// f = (target - margin_ratio) / (target - reward / size)
+/// ```
+///
+/// # Arguments
+/// * `position` - The position (with funding and borrow fees applied)
+/// * `current_price` - Current market price (7 decimals)
+/// * `target_margin_bps` - Margin ratio to restore (maintenance + buffer)
+/// * `keeper_reward` - Keeper reward for liquidating the whole position
+///
+/// # Returns
+/// Fraction to liquidate in basis points (BASIS_POINTS = liquidate fully)
+pub fn calculate_partial_liquidation_bps(
+    position: &Position,
+    current_price: i128,
+    target_margin_bps: u32,
+    keeper_reward: i128,
+) -> u32 {
+    if position.size <= 0 {
+        return BASIS_POINTS;
     }
+
+    let bps = BASIS_POINTS as i128;
+    let target = target_margin_bps as i128;
+    let margin_ratio = calculate_margin_ratio_bps(position, current_price);
+    let reward_ratio = keeper_reward * bps / position.size;
+
+    // Equity cannot even cover the reward on the closed size
+    if margin_ratio <= reward_ratio || target <= reward_ratio {
+        return BASIS_POINTS;
+    }
+
+    let fraction = ((target - margin_ratio) * bps + (target - reward_ratio) - 1)
+        / (target - reward_ratio);
+
+    fraction.clamp(1, bps) as u32
 }
 
 /// Calculate liquidation proceeds distribution.
@@ -59,7 +132,7 @@ pub fn calculate_liquidation_distribution(
         }
     };
 
-    let remaining = position.collateral + pnl - position.accumulated_funding;
+    let remaining = position.collateral + pnl - position.accumulated_funding - position.accumulated_borrow_fee;
 
     if remaining <= 0 {
         // Bad debt scenario - position lost more than collateral
@@ -99,7 +172,8 @@ pub fn calculate_distance_to_liquidation_pct(position: &Position, current_price:
 #[cfg(test)]
 mod tests {
     use super::*;
-    use soroban_sdk::{Env, Address, Symbol};
+    use noether_common::PRECISION;
+    use soroban_sdk::{testutils::Address as _, Env, Address, Symbol};
 
     fn create_long_position(env: &Env) -> Position {
         Position {
@@ -115,6 +189,10 @@ mod tests {
             timestamp: 1000000,
             last_funding_time: 1000000,
             accumulated_funding: 0,
+            funding_index: 0,
+            accumulated_borrow_fee: 0,
+            borrow_index: 0,
+            reserved: 0,
         }
     }
 
@@ -124,13 +202,71 @@ mod tests {
         let position = create_long_position(&env);
 
         // Price above liquidation - should not liquidate
-        assert!(!check_liquidation(&position, PRECISION * 95 / 100));
+        assert!(!is_liquidatable(&position, PRECISION * 95 / 100, 100));
 
         // Price at liquidation - should liquidate
-        assert!(check_liquidation(&position, PRECISION * 91 / 100));
+        assert!(is_liquidatable(&position, PRECISION * 91 / 100, 100));
 
         // Price below liquidation - should liquidate
-        assert!(check_liquidation(&position, PRECISION * 85 / 100));
+        assert!(is_liquidatable(&position, PRECISION * 85 / 100, 100));
+    }
+
+    #[test]
+    fn test_equity_liquidation_price() {
+        let env = Env::default();
+        let mut position = create_long_position(&env);
+
+        // $100 equity, $10 maintenance: $90 of room on $1000 = 9% move
+        assert_eq!(calculate_equity_liquidation_price(&position, 100), PRECISION * 91 / 100);
+
+        // $20 of funding and fees owed moves it 2% closer
+        position.accumulated_funding = 15 * PRECISION;
+        position.accumulated_borrow_fee = 5 * PRECISION;
+        let liq_price = calculate_equity_liquidation_price(&position, 100);
+        assert_eq!(liq_price, PRECISION * 93 / 100);
+        assert!(is_liquidatable(&position, liq_price, 100));
+        assert!(!is_liquidatable(&position, liq_price + PRECISION / 100, 100));
+
+        // Short positions are liquidated above entry
+        position.direction = Direction::Short;
+        assert_eq!(calculate_equity_liquidation_price(&position, 100), PRECISION * 107 / 100);
+    }
+
+    #[test]
+    fn test_liquidatable_with_funding() {
+        let env = Env::default();
+        let mut position = create_long_position(&env);
+
+        // $0.95 is above the $0.91 liquidation price
+        assert!(!is_liquidatable(&position, PRECISION * 95 / 100, 100));
+
+        // $45 funding owed eats the remaining $50 margin below the $10 maintenance
+        position.accumulated_funding = 45 * PRECISION;
+        assert!(is_liquidatable(&position, PRECISION * 95 / 100, 100));
+    }
+
+    #[test]
+    fn test_partial_liquidation_bps() {
+        let env = Env::default();
+        let position = create_long_position(&env);
+
+        // At $0.92: margin $20 on $1000 size = 200 bps. Restoring 300 bps
+        // with a $2 reward (20 bps) needs (300 - 200) / (300 - 20) = 35.72%
+        let bps = calculate_partial_liquidation_bps(&position, PRECISION * 92 / 100, 300, 2 * PRECISION);
+        assert_eq!(bps, 3572);
+
+        // The closed slice realizes its $80 loss and pays its share of the
+        // reward, leaving the rest of the position back above the target
+        let fraction = bps as i128;
+        let bp = BASIS_POINTS as i128;
+        let mut remaining = position.clone();
+        remaining.size -= position.size * fraction / bp;
+        remaining.collateral -= (80 * PRECISION + 2 * PRECISION) * fraction / bp;
+        assert!(calculate_margin_ratio_bps(&remaining, PRECISION * 92 / 100) >= 300);
+
+        // Underwater positions are liquidated fully
+        let bps = calculate_partial_liquidation_bps(&position, PRECISION * 89 / 100, 300, 0);
+        assert_eq!(bps, BASIS_POINTS);
     }
 
     #[test]
@@ -0,0 +1,192 @@
+//! # Margin Account Logic
+//!
+//! Each trader has a USDC balance held by the market. Opening and increasing
+//! positions, adding collateral and placing limit orders draw from it first;
+//! closes, refunds and collateral withdrawals are credited back to it.
+//!
+//! ## Drawing Margin
+//!
+//! Any shortfall the balance does not cover is pulled from the trader's
+//! wallet in the same call:
+//! ```
+//! from_balance = min(balance, amount)
+//! from_wallet  = amount - from_balance
+//! ```
+//!
+//! ## Cross Margin
+//!
+//! In cross mode the balance backs all of a trader's positions, which are
+//! liquidated together once account equity reaches their summed maintenance
+//! margin:
+//! ```
+//! equity = balance + token_value + Σ(collateral + pnl - funding - borrow fees)
+//! ```
+//! Each position closed by an account liquidation draws its loss and keeper
+//! reward from the balance first; only what the balance cannot cover is bad
+//! debt for the insurance fund.
+//!
+//! ## Token Collateral
+//!
+//! Listed non-USDC tokens (7 decimals, like SAC-wrapped XLM) can be posted
+//! as cross margin. They count toward equity at the oracle price less the
+//! token's haircut:
+//! ```
+//! value = amount * price / PRECISION * (10000 - haircut_bps) / 10000
+//! ```
+//! Bad debt the USDC balance cannot cover is paid by seizing tokens at the
+//! full oracle price, rounding the seized amount up.
+
+use noether_common::{BASIS_POINTS, PRECISION};
+
+/// Split an amount between the margin balance and the trader's wallet.
+///
+/// # Arguments
+/// * `balance` - Margin account balance (7 decimals)
+/// * `amount` - Amount needed (7 decimals)
+///
+/// # Returns
+/// (from_balance, from_wallet)
+pub fn calculate_margin_draw(balance: i128, amount: i128) -> (i128, i128) {
+    if amount <= 0 {
+        return (0, 0);
+    }
+
+    let from_balance = balance.clamp(0, amount);
+    (from_balance, amount - from_balance)
+}
+
+/// Check if a cross-margin account can be liquidated.
+pub fn is_account_liquidatable(equity: i128, maintenance_margin: i128) -> bool {
+    equity <= maintenance_margin
+}
+
+/// Calculate how much of a liquidated cross position's shortfall the margin
+/// balance covers.
+///
+/// # Arguments
+/// * `value` - Position value after PnL, funding and borrow fees (7 decimals)
+/// * `keeper_reward` - Keeper reward owed for the close (7 decimals)
+/// * `balance` - Trader's margin balance (7 decimals)
+///
+/// # Returns
+/// (cover from the balance, bad debt left for the insurance fund)
+pub fn calculate_cross_liquidation_cover(
+    value: i128,
+    keeper_reward: i128,
+    balance: i128,
+) -> (i128, i128) {
+    let shortfall = (keeper_reward - value).max(0);
+    let cover = shortfall.min(balance.max(0));
+    let bad_debt = (-(value + cover)).max(0);
+    (cover, bad_debt)
+}
+
+/// Calculate the margin value of a token balance at the haircut price.
+///
+/// # Arguments
+/// * `amount` - Token amount (7 decimals)
+/// * `price` - Oracle price of the token in USDC (7 decimals)
+/// * `haircut_bps` - Haircut in basis points
+///
+/// # Returns
+/// USDC value counted toward equity (7 decimals)
+pub fn calculate_haircut_value(amount: i128, price: i128, haircut_bps: u32) -> i128 {
+    if amount <= 0 || price <= 0 {
+        return 0;
+    }
+
+    let bps = BASIS_POINTS as i128;
+    amount * price / PRECISION * (bps - haircut_bps as i128) / bps
+}
+
+/// Calculate how many tokens to seize to pay a debt at the oracle price.
+///
+/// # Arguments
+/// * `debt` - USDC owed (7 decimals)
+/// * `price` - Oracle price of the token in USDC (7 decimals)
+/// * `balance` - Trader's token balance (7 decimals)
+///
+/// # Returns
+/// (tokens seized, USDC value credited for them)
+pub fn calculate_seize_amount(debt: i128, price: i128, balance: i128) -> (i128, i128) {
+    if debt <= 0 || price <= 0 || balance <= 0 {
+        return (0, 0);
+    }
+
+    let needed = (debt * PRECISION + price - 1) / price;
+    if needed >= balance {
+        (balance, balance * price / PRECISION)
+    } else {
+        (needed, debt)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_margin_draw() {
+        // Balance covers everything
+        assert_eq!(calculate_margin_draw(500 * PRECISION, 100 * PRECISION), (100 * PRECISION, 0));
+        // Shortfall comes from the wallet
+        assert_eq!(
+            calculate_margin_draw(30 * PRECISION, 100 * PRECISION),
+            (30 * PRECISION, 70 * PRECISION)
+        );
+        // Empty account
+        assert_eq!(calculate_margin_draw(0, 100 * PRECISION), (0, 100 * PRECISION));
+        assert_eq!(calculate_margin_draw(500 * PRECISION, 0), (0, 0));
+    }
+
+    #[test]
+    fn test_account_liquidatable() {
+        assert!(!is_account_liquidatable(60 * PRECISION, 50 * PRECISION));
+        assert!(is_account_liquidatable(50 * PRECISION, 50 * PRECISION));
+        assert!(is_account_liquidatable(-10 * PRECISION, 50 * PRECISION));
+    }
+
+    #[test]
+    fn test_cross_liquidation_cover() {
+        // Position still covers its keeper reward: nothing drawn
+        assert_eq!(
+            calculate_cross_liquidation_cover(20 * PRECISION, 5 * PRECISION, 100 * PRECISION),
+            (0, 0)
+        );
+        // $30 loss past collateral plus $5 reward, balance covers it
+        assert_eq!(
+            calculate_cross_liquidation_cover(-30 * PRECISION, 5 * PRECISION, 100 * PRECISION),
+            (35 * PRECISION, 0)
+        );
+        // Balance runs out: the rest of the loss is bad debt
+        assert_eq!(
+            calculate_cross_liquidation_cover(-30 * PRECISION, 5 * PRECISION, 10 * PRECISION),
+            (10 * PRECISION, 20 * PRECISION)
+        );
+    }
+
+    #[test]
+    fn test_haircut_value() {
+        // 1,000 XLM at $0.10 with a 20% haircut
+        assert_eq!(calculate_haircut_value(1000 * PRECISION, PRECISION / 10, 2000), 80 * PRECISION);
+        assert_eq!(calculate_haircut_value(1000 * PRECISION, PRECISION / 10, 0), 100 * PRECISION);
+        assert_eq!(calculate_haircut_value(0, PRECISION / 10, 2000), 0);
+    }
+
+    #[test]
+    fn test_seize_amount() {
+        // $30 of debt at $0.10 takes 300 tokens
+        assert_eq!(
+            calculate_seize_amount(30 * PRECISION, PRECISION / 10, 1000 * PRECISION),
+            (300 * PRECISION, 30 * PRECISION)
+        );
+        // Rounds the seized amount up
+        assert_eq!(calculate_seize_amount(1, 3 * PRECISION, 1000 * PRECISION), (1, 1));
+        // Balance runs out: everything is seized at oracle value
+        assert_eq!(
+            calculate_seize_amount(30 * PRECISION, PRECISION / 10, 100 * PRECISION),
+            (100 * PRECISION, 10 * PRECISION)
+        );
+        assert_eq!(calculate_seize_amount(0, PRECISION / 10, 100 * PRECISION), (0, 0));
+    }
+}
@@ -0,0 +1,80 @@
+//! # Operator Permissions
+//!
+//! Traders can approve operators (e.g. trading bots) to act on their behalf
+//! without holding the trader's key. Each approval grants a set of scopes and
+//! may expire:
+//! - Open: open and increase positions, place limit orders
+//! - Close: close positions, fully or partially
+//! - ManageOrders: set, modify and cancel SL/TP/trailing orders
+//! - AddCollateral: add collateral to positions
+//!
+//! There is no withdraw scope. Removing collateral is reserved to the trader,
+//! and every payout still goes to the trader's address.
+
+use noether_common::OperatorApproval;
+
+/// Action an operator is attempting on a trader's behalf
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OperatorScope {
+    Open,
+    Close,
+    ManageOrders,
+    AddCollateral,
+}
+
+/// Check whether an approval covers a scope at a point in time.
+///
+/// # Arguments
+/// * `approval` - Approval granted by the trader
+/// * `scope` - Action being attempted
+/// * `now` - Current ledger timestamp
+pub fn is_operator_permitted(approval: &OperatorApproval, scope: OperatorScope, now: u64) -> bool {
+    if let Some(expires_at) = approval.expires_at {
+        if now >= expires_at {
+            return false;
+        }
+    }
+
+    let permissions = &approval.permissions;
+    match scope {
+        OperatorScope::Open => permissions.open,
+        OperatorScope::Close => permissions.close,
+        OperatorScope::ManageOrders => permissions.manage_orders,
+        OperatorScope::AddCollateral => permissions.add_collateral,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use noether_common::OperatorPermissions;
+
+    fn approval(expires_at: Option<u64>) -> OperatorApproval {
+        OperatorApproval {
+            permissions: OperatorPermissions {
+                open: true,
+                close: true,
+                manage_orders: false,
+                add_collateral: false,
+            },
+            expires_at,
+        }
+    }
+
+    #[test]
+    fn test_operator_scopes() {
+        let approval = approval(None);
+        assert!(is_operator_permitted(&approval, OperatorScope::Open, 1000));
+        assert!(is_operator_permitted(&approval, OperatorScope::Close, 1000));
+        assert!(!is_operator_permitted(&approval, OperatorScope::ManageOrders, 1000));
+        assert!(!is_operator_permitted(&approval, OperatorScope::AddCollateral, 1000));
+    }
+
+    #[test]
+    fn test_operator_expiry() {
+        let approval = approval(Some(1000));
+        assert!(is_operator_permitted(&approval, OperatorScope::Open, 999));
+        // Lapses at the expiry timestamp
+        assert!(!is_operator_permitted(&approval, OperatorScope::Open, 1000));
+    }
+}
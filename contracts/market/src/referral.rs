@@ -0,0 +1,80 @@
+//! # Referral Logic
+//!
+//! Calculations for referral codes. A trader who sets a referrer's code pays
+//! discounted trading fees, and the referrer earns a rebate on what the
+//! trader pays.
+//!
+//! ## Tiers
+//!
+//! Each code belongs to a tier set by the admin, which fixes both shares:
+//! ```
+//! fee_charged = fee - fee * discount_bps / 10000
+//! rebate      = fee_charged * rebate_bps / 10000
+//! ```
+//! The rebate is carved out of the fee before the insurance fund and vault
+//! take theirs, and accrues to the referrer until claimed.
+
+use noether_common::BASIS_POINTS;
+
+/// Apply a referral tier's trader discount to a fee.
+///
+/// # Arguments
+/// * `fee` - Undiscounted fee (7 decimals)
+/// * `discount_bps` - Trader discount in basis points
+///
+/// # Returns
+/// Fee the trader is charged (7 decimals)
+pub fn apply_referral_discount(fee: i128, discount_bps: u32) -> i128 {
+    if fee <= 0 {
+        return fee;
+    }
+
+    fee - fee * (discount_bps as i128) / (BASIS_POINTS as i128)
+}
+
+/// Calculate the referrer's rebate on a fee a referred trader paid.
+///
+/// # Arguments
+/// * `fee` - Fee charged to the trader (7 decimals)
+/// * `rebate_bps` - Referrer rebate in basis points
+pub fn calculate_referral_rebate(fee: i128, rebate_bps: u32) -> i128 {
+    if fee <= 0 {
+        return 0;
+    }
+
+    fee * (rebate_bps as i128) / (BASIS_POINTS as i128)
+}
+
+/// Check that a tier's discount and rebate are valid shares of a fee.
+pub fn is_valid_tier(discount_bps: u32, rebate_bps: u32) -> bool {
+    discount_bps <= BASIS_POINTS && rebate_bps <= BASIS_POINTS
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use noether_common::PRECISION;
+
+    #[test]
+    fn test_referral_discount() {
+        // 10% off a $10 fee
+        assert_eq!(apply_referral_discount(10 * PRECISION, 1000), 9 * PRECISION);
+        assert_eq!(apply_referral_discount(10 * PRECISION, 0), 10 * PRECISION);
+        assert_eq!(apply_referral_discount(10 * PRECISION, 10_000), 0);
+    }
+
+    #[test]
+    fn test_referral_rebate() {
+        // 20% of a $9 fee
+        assert_eq!(calculate_referral_rebate(9 * PRECISION, 2000), 18 * PRECISION / 10);
+        assert_eq!(calculate_referral_rebate(0, 2000), 0);
+    }
+
+    #[test]
+    fn test_valid_tier() {
+        assert!(is_valid_tier(1000, 2000));
+        assert!(is_valid_tier(10_000, 0));
+        assert!(!is_valid_tier(10_001, 0));
+        assert!(!is_valid_tier(0, 10_001));
+    }
+}
@@ -2,8 +2,11 @@
 //!
 //! Storage keys and helpers for the Market contract.
 
-use soroban_sdk::{contracttype, Address, Env, Vec};
-use noether_common::{NoetherError, Position, MarketConfig, Order, OrderStatus};
+use soroban_sdk::{contracttype, Address, Env, Symbol, Vec};
+use noether_common::{
+    NoetherError, Position, MarketConfig, Order, OrderStatus, Direction, OperatorApproval,
+    MarginMode, CollateralConfig, ReferralTier, ReferralCode, ReferralStats,
+};
 
 // ═══════════════════════════════════════════════════════════════════════════
 // Storage Keys
@@ -20,18 +23,64 @@ pub enum DataKey {
     Vault,
     /// USDC token contract address
     UsdcToken,
-    /// Market configuration
+    /// Protocol-wide default configuration
     Config,
+    /// Market configuration for a listed asset
+    Market(Symbol),
+    /// Symbols of all listed markets
+    Markets,
+    /// Cap on open interest across all markets, as a fraction of vault AUM (bps)
+    MaxGlobalOiBps,
     /// Position counter (for ID generation)
     PositionCounter,
-    /// Total long position size
-    TotalLongSize,
-    /// Total short position size
-    TotalShortSize,
-    /// Last funding time
-    LastFundingTime,
-    /// Current funding rate
-    CurrentFundingRate,
+    /// Total long position size for an asset
+    TotalLongSize(Symbol),
+    /// Total short position size for an asset
+    TotalShortSize(Symbol),
+    /// Number of open positions in an asset
+    PositionCount(Symbol),
+    /// Last funding time for an asset
+    LastFundingTime(Symbol),
+    /// Current funding rate for an asset
+    CurrentFundingRate(Symbol),
+    /// Cumulative funding index for longs of an asset
+    CumulativeFundingLong(Symbol),
+    /// Cumulative funding index for shorts of an asset
+    CumulativeFundingShort(Symbol),
+    /// Cumulative borrow index of an asset
+    CumulativeBorrow(Symbol),
+    /// Last borrow index update time for an asset
+    LastBorrowTime(Symbol),
+    /// Realized funding paid by traders of an asset
+    FundingPaid(Symbol),
+    /// Realized funding received by traders of an asset
+    FundingReceived(Symbol),
+    /// Insurance fund balance (USDC held by the market)
+    InsuranceFund,
+    /// Free margin account balance of a trader (USDC held by the market)
+    MarginBalance(Address),
+    /// Margin mode of a trader (isolated unless set)
+    MarginMode(Address),
+    /// Configuration of a non-USDC collateral token
+    Collateral(Address),
+    /// Addresses of all listed collateral tokens
+    CollateralTokens,
+    /// Total amount of a collateral token deposited by all traders
+    CollateralDeposits(Address),
+    /// Trader's balance of a collateral token (trader, token)
+    CollateralBalance(Address, Address),
+    /// Seized collateral tokens held by the market, awaiting conversion to USDC
+    SeizedCollateral(Address),
+    /// Bad debt LPs absorbed that seized collateral is owed back to them
+    UnrecoveredBadDebt,
+    /// Discount and rebate tiers for referral codes
+    ReferralTiers,
+    /// Referral code registration
+    ReferralCode(Symbol),
+    /// Referral code a trader has set
+    Referrer(Address),
+    /// Referral statistics and claimable rewards of a referrer
+    ReferralStats(Address),
     /// Whether initialized
     Initialized,
     /// Whether paused
@@ -54,6 +103,8 @@ pub enum DataKey {
     PositionStopLoss(u64),
     /// Take-profit order ID attached to a position
     PositionTakeProfit(u64),
+    /// Operator approval granted by a trader (trader, operator)
+    Operator(Address, Address),
 }
 
 // ═══════════════════════════════════════════════════════════════════════════
@@ -116,6 +167,62 @@ pub fn set_config(env: &Env, config: &MarketConfig) {
     env.storage().instance().set(&DataKey::Config, config);
 }
 
+pub fn get_max_global_oi_bps(env: &Env) -> u32 {
+    env.storage().instance().get(&DataKey::MaxGlobalOiBps).unwrap_or(10_000) // 100% of AUM default
+}
+
+pub fn set_max_global_oi_bps(env: &Env, bps: u32) {
+    env.storage().instance().set(&DataKey::MaxGlobalOiBps, &bps);
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+// Instance Storage - Market Registry
+// ═══════════════════════════════════════════════════════════════════════════
+
+pub fn get_market_config(env: &Env, asset: &Symbol) -> Option<MarketConfig> {
+    env.storage().instance().get(&DataKey::Market(asset.clone()))
+}
+
+pub fn set_market_config(env: &Env, asset: &Symbol, config: &MarketConfig) {
+    env.storage().instance().set(&DataKey::Market(asset.clone()), config);
+}
+
+pub fn get_market_assets(env: &Env) -> Vec<Symbol> {
+    env.storage().instance().get(&DataKey::Markets).unwrap_or(Vec::new(env))
+}
+
+pub fn add_market_asset(env: &Env, asset: &Symbol) {
+    let mut assets = get_market_assets(env);
+    assets.push_back(asset.clone());
+    env.storage().instance().set(&DataKey::Markets, &assets);
+}
+
+pub fn get_collateral_config(env: &Env, token: &Address) -> Option<CollateralConfig> {
+    env.storage().instance().get(&DataKey::Collateral(token.clone()))
+}
+
+pub fn set_collateral_config(env: &Env, token: &Address, config: &CollateralConfig) {
+    env.storage().instance().set(&DataKey::Collateral(token.clone()), config);
+}
+
+pub fn get_collateral_assets(env: &Env) -> Vec<Address> {
+    env.storage().instance().get(&DataKey::CollateralTokens).unwrap_or(Vec::new(env))
+}
+
+pub fn add_collateral_asset(env: &Env, token: &Address) {
+    let mut tokens = get_collateral_assets(env);
+    tokens.push_back(token.clone());
+    env.storage().instance().set(&DataKey::CollateralTokens, &tokens);
+}
+
+pub fn get_referral_tiers(env: &Env) -> Vec<ReferralTier> {
+    env.storage().instance().get(&DataKey::ReferralTiers).unwrap_or(Vec::new(env))
+}
+
+pub fn set_referral_tiers(env: &Env, tiers: &Vec<ReferralTier>) {
+    env.storage().instance().set(&DataKey::ReferralTiers, tiers);
+}
+
 // ═══════════════════════════════════════════════════════════════════════════
 // Persistent Storage - Market State
 // ═══════════════════════════════════════════════════════════════════════════
@@ -136,40 +243,230 @@ pub fn next_position_id(env: &Env) -> u64 {
     next_id
 }
 
-pub fn get_total_long_size(env: &Env) -> i128 {
-    env.storage().persistent().get(&DataKey::TotalLongSize).unwrap_or(0)
+pub fn get_total_long_size(env: &Env, asset: &Symbol) -> i128 {
+    env.storage().persistent().get(&DataKey::TotalLongSize(asset.clone())).unwrap_or(0)
+}
+
+pub fn set_total_long_size(env: &Env, asset: &Symbol, size: i128) {
+    let key = DataKey::TotalLongSize(asset.clone());
+    env.storage().persistent().set(&key, &size);
+    extend_persistent_ttl(env, &key);
+}
+
+pub fn get_total_short_size(env: &Env, asset: &Symbol) -> i128 {
+    env.storage().persistent().get(&DataKey::TotalShortSize(asset.clone())).unwrap_or(0)
+}
+
+pub fn set_total_short_size(env: &Env, asset: &Symbol, size: i128) {
+    let key = DataKey::TotalShortSize(asset.clone());
+    env.storage().persistent().set(&key, &size);
+    extend_persistent_ttl(env, &key);
+}
+
+/// Add `delta` (negative to reduce) to the open interest of one side of a market.
+pub fn adjust_open_interest(env: &Env, asset: &Symbol, direction: Direction, delta: i128) {
+    match direction {
+        Direction::Long => {
+            let total = get_total_long_size(env, asset);
+            set_total_long_size(env, asset, total + delta);
+        }
+        Direction::Short => {
+            let total = get_total_short_size(env, asset);
+            set_total_short_size(env, asset, total + delta);
+        }
+    }
+}
+
+pub fn get_last_funding_time(env: &Env, asset: &Symbol) -> u64 {
+    env.storage().persistent().get(&DataKey::LastFundingTime(asset.clone())).unwrap_or(0)
+}
+
+pub fn set_last_funding_time(env: &Env, asset: &Symbol, time: u64) {
+    let key = DataKey::LastFundingTime(asset.clone());
+    env.storage().persistent().set(&key, &time);
+    extend_persistent_ttl(env, &key);
+}
+
+pub fn set_current_funding_rate(env: &Env, asset: &Symbol, rate: i128) {
+    let key = DataKey::CurrentFundingRate(asset.clone());
+    env.storage().persistent().set(&key, &rate);
+    extend_persistent_ttl(env, &key);
+}
+
+pub fn get_cumulative_funding(env: &Env, asset: &Symbol) -> (i128, i128) {
+    let long_index = env.storage()
+        .persistent()
+        .get(&DataKey::CumulativeFundingLong(asset.clone()))
+        .unwrap_or(0);
+    let short_index = env.storage()
+        .persistent()
+        .get(&DataKey::CumulativeFundingShort(asset.clone()))
+        .unwrap_or(0);
+    (long_index, short_index)
+}
+
+pub fn set_cumulative_funding(env: &Env, asset: &Symbol, long_index: i128, short_index: i128) {
+    let long_key = DataKey::CumulativeFundingLong(asset.clone());
+    env.storage().persistent().set(&long_key, &long_index);
+    extend_persistent_ttl(env, &long_key);
+
+    let short_key = DataKey::CumulativeFundingShort(asset.clone());
+    env.storage().persistent().set(&short_key, &short_index);
+    extend_persistent_ttl(env, &short_key);
+}
+
+pub fn get_cumulative_borrow(env: &Env, asset: &Symbol) -> i128 {
+    env.storage().persistent().get(&DataKey::CumulativeBorrow(asset.clone())).unwrap_or(0)
+}
+
+pub fn get_last_borrow_time(env: &Env, asset: &Symbol) -> u64 {
+    env.storage().persistent().get(&DataKey::LastBorrowTime(asset.clone())).unwrap_or(0)
+}
+
+/// Store the cumulative borrow index of an asset as of `time`.
+pub fn set_cumulative_borrow(env: &Env, asset: &Symbol, index: i128, time: u64) {
+    let index_key = DataKey::CumulativeBorrow(asset.clone());
+    env.storage().persistent().set(&index_key, &index);
+    extend_persistent_ttl(env, &index_key);
+
+    let time_key = DataKey::LastBorrowTime(asset.clone());
+    env.storage().persistent().set(&time_key, &time);
+    extend_persistent_ttl(env, &time_key);
+}
+
+/// Get realized funding for an asset as (total_paid, total_received).
+pub fn get_realized_funding(env: &Env, asset: &Symbol) -> (i128, i128) {
+    let paid = env.storage().persistent().get(&DataKey::FundingPaid(asset.clone())).unwrap_or(0);
+    let received = env.storage().persistent().get(&DataKey::FundingReceived(asset.clone())).unwrap_or(0);
+    (paid, received)
 }
 
-pub fn set_total_long_size(env: &Env, size: i128) {
-    env.storage().persistent().set(&DataKey::TotalLongSize, &size);
-    extend_persistent_ttl(env, &DataKey::TotalLongSize);
+/// Record funding realized by a position (positive = paid, negative = received).
+pub fn record_realized_funding(env: &Env, asset: &Symbol, amount: i128) {
+    let key = if amount > 0 {
+        DataKey::FundingPaid(asset.clone())
+    } else {
+        DataKey::FundingReceived(asset.clone())
+    };
+    let total: i128 = env.storage().persistent().get(&key).unwrap_or(0);
+    env.storage().persistent().set(&key, &(total + amount.abs()));
+    extend_persistent_ttl(env, &key);
 }
 
-pub fn get_total_short_size(env: &Env) -> i128 {
-    env.storage().persistent().get(&DataKey::TotalShortSize).unwrap_or(0)
+pub fn get_insurance_fund(env: &Env) -> i128 {
+    env.storage().persistent().get(&DataKey::InsuranceFund).unwrap_or(0)
 }
 
-pub fn set_total_short_size(env: &Env, size: i128) {
-    env.storage().persistent().set(&DataKey::TotalShortSize, &size);
-    extend_persistent_ttl(env, &DataKey::TotalShortSize);
+pub fn set_insurance_fund(env: &Env, balance: i128) {
+    env.storage().persistent().set(&DataKey::InsuranceFund, &balance);
+    extend_persistent_ttl(env, &DataKey::InsuranceFund);
 }
 
-pub fn get_last_funding_time(env: &Env) -> u64 {
-    env.storage().persistent().get(&DataKey::LastFundingTime).unwrap_or(0)
+pub fn get_margin_balance(env: &Env, trader: &Address) -> i128 {
+    env.storage()
+        .persistent()
+        .get(&DataKey::MarginBalance(trader.clone()))
+        .unwrap_or(0)
 }
 
-pub fn set_last_funding_time(env: &Env, time: u64) {
-    env.storage().persistent().set(&DataKey::LastFundingTime, &time);
-    extend_persistent_ttl(env, &DataKey::LastFundingTime);
+pub fn set_margin_balance(env: &Env, trader: &Address, balance: i128) {
+    let key = DataKey::MarginBalance(trader.clone());
+    env.storage().persistent().set(&key, &balance);
+    extend_persistent_ttl(env, &key);
 }
 
-pub fn get_current_funding_rate(env: &Env) -> i128 {
-    env.storage().persistent().get(&DataKey::CurrentFundingRate).unwrap_or(0)
+pub fn get_margin_mode(env: &Env, trader: &Address) -> MarginMode {
+    env.storage()
+        .persistent()
+        .get(&DataKey::MarginMode(trader.clone()))
+        .unwrap_or(MarginMode::Isolated)
 }
 
-pub fn set_current_funding_rate(env: &Env, rate: i128) {
-    env.storage().persistent().set(&DataKey::CurrentFundingRate, &rate);
-    extend_persistent_ttl(env, &DataKey::CurrentFundingRate);
+pub fn set_margin_mode(env: &Env, trader: &Address, mode: MarginMode) {
+    let key = DataKey::MarginMode(trader.clone());
+    env.storage().persistent().set(&key, &mode);
+    extend_persistent_ttl(env, &key);
+}
+
+pub fn get_collateral_deposits(env: &Env, token: &Address) -> i128 {
+    env.storage()
+        .persistent()
+        .get(&DataKey::CollateralDeposits(token.clone()))
+        .unwrap_or(0)
+}
+
+pub fn set_collateral_deposits(env: &Env, token: &Address, amount: i128) {
+    let key = DataKey::CollateralDeposits(token.clone());
+    env.storage().persistent().set(&key, &amount);
+    extend_persistent_ttl(env, &key);
+}
+
+pub fn get_collateral_balance(env: &Env, trader: &Address, token: &Address) -> i128 {
+    env.storage()
+        .persistent()
+        .get(&DataKey::CollateralBalance(trader.clone(), token.clone()))
+        .unwrap_or(0)
+}
+
+pub fn set_collateral_balance(env: &Env, trader: &Address, token: &Address, amount: i128) {
+    let key = DataKey::CollateralBalance(trader.clone(), token.clone());
+    env.storage().persistent().set(&key, &amount);
+    extend_persistent_ttl(env, &key);
+}
+
+pub fn get_seized_collateral(env: &Env, token: &Address) -> i128 {
+    env.storage()
+        .persistent()
+        .get(&DataKey::SeizedCollateral(token.clone()))
+        .unwrap_or(0)
+}
+
+pub fn set_seized_collateral(env: &Env, token: &Address, amount: i128) {
+    let key = DataKey::SeizedCollateral(token.clone());
+    env.storage().persistent().set(&key, &amount);
+    extend_persistent_ttl(env, &key);
+}
+
+pub fn get_unrecovered_bad_debt(env: &Env) -> i128 {
+    env.storage().persistent().get(&DataKey::UnrecoveredBadDebt).unwrap_or(0)
+}
+
+pub fn set_unrecovered_bad_debt(env: &Env, amount: i128) {
+    env.storage().persistent().set(&DataKey::UnrecoveredBadDebt, &amount);
+    extend_persistent_ttl(env, &DataKey::UnrecoveredBadDebt);
+}
+
+pub fn get_referral_code(env: &Env, code: &Symbol) -> Option<ReferralCode> {
+    env.storage().persistent().get(&DataKey::ReferralCode(code.clone()))
+}
+
+pub fn set_referral_code(env: &Env, code: &Symbol, referral: &ReferralCode) {
+    let key = DataKey::ReferralCode(code.clone());
+    env.storage().persistent().set(&key, referral);
+    extend_persistent_ttl(env, &key);
+}
+
+pub fn get_referrer(env: &Env, trader: &Address) -> Option<Symbol> {
+    env.storage().persistent().get(&DataKey::Referrer(trader.clone()))
+}
+
+pub fn set_referrer(env: &Env, trader: &Address, code: &Symbol) {
+    let key = DataKey::Referrer(trader.clone());
+    env.storage().persistent().set(&key, code);
+    extend_persistent_ttl(env, &key);
+}
+
+pub fn get_referral_stats(env: &Env, owner: &Address) -> ReferralStats {
+    env.storage()
+        .persistent()
+        .get(&DataKey::ReferralStats(owner.clone()))
+        .unwrap_or_default()
+}
+
+pub fn set_referral_stats(env: &Env, owner: &Address, stats: &ReferralStats) {
+    let key = DataKey::ReferralStats(owner.clone());
+    env.storage().persistent().set(&key, stats);
+    extend_persistent_ttl(env, &key);
 }
 
 // ═══════════════════════════════════════════════════════════════════════════
@@ -219,10 +516,18 @@ pub fn save_position(env: &Env, position: &Position) {
         all_positions.push_back(position.id);
         env.storage().persistent().set(&DataKey::AllPositions, &all_positions);
         extend_persistent_ttl(env, &DataKey::AllPositions);
+
+        set_position_count(env, &position.asset, get_position_count(env, &position.asset) + 1);
     }
 }
 
 pub fn delete_position(env: &Env, id: u64, trader: &Address) {
+    // Decrement the asset's open position count
+    if let Some(position) = get_position(env, id) {
+        let count = get_position_count(env, &position.asset);
+        set_position_count(env, &position.asset, count.saturating_sub(1));
+    }
+
     // Remove from storage
     env.storage().persistent().remove(&DataKey::Position(id));
 
@@ -283,8 +588,28 @@ pub fn get_all_position_ids(env: &Env) -> Vec<u64> {
         .unwrap_or(Vec::new(env))
 }
 
-pub fn get_position_count(env: &Env) -> u64 {
-    get_all_position_ids(env).len() as u64
+/// Sum open interest (long + short size) across all listed markets.
+pub fn get_total_open_interest(env: &Env) -> i128 {
+    let assets = get_market_assets(env);
+    let mut total = 0i128;
+    for i in 0..assets.len() {
+        let asset = assets.get(i).unwrap();
+        total += get_total_long_size(env, &asset) + get_total_short_size(env, &asset);
+    }
+    total
+}
+
+pub fn get_position_count(env: &Env, asset: &Symbol) -> u64 {
+    env.storage()
+        .persistent()
+        .get(&DataKey::PositionCount(asset.clone()))
+        .unwrap_or(0)
+}
+
+pub fn set_position_count(env: &Env, asset: &Symbol, count: u64) {
+    let key = DataKey::PositionCount(asset.clone());
+    env.storage().persistent().set(&key, &count);
+    extend_persistent_ttl(env, &key);
 }
 
 // ═══════════════════════════════════════════════════════════════════════════
@@ -312,6 +637,14 @@ pub fn require_admin(env: &Env) -> Result<(), NoetherError> {
     Ok(())
 }
 
+pub fn require_market(env: &Env, asset: &Symbol) -> Result<MarketConfig, NoetherError> {
+    get_market_config(env, asset).ok_or(NoetherError::MarketNotFound)
+}
+
+pub fn require_collateral(env: &Env, token: &Address) -> Result<CollateralConfig, NoetherError> {
+    get_collateral_config(env, token).ok_or(NoetherError::CollateralNotFound)
+}
+
 // ═══════════════════════════════════════════════════════════════════════════
 // TTL Management
 // ═══════════════════════════════════════════════════════════════════════════
@@ -503,3 +836,34 @@ pub fn set_position_take_profit(env: &Env, position_id: u64, order_id: u64) {
 pub fn remove_position_take_profit(env: &Env, position_id: u64) {
     env.storage().persistent().remove(&DataKey::PositionTakeProfit(position_id));
 }
+
+// ═══════════════════════════════════════════════════════════════════════════
+// Operator Storage
+// ═══════════════════════════════════════════════════════════════════════════
+
+pub fn get_operator_approval(
+    env: &Env,
+    trader: &Address,
+    operator: &Address,
+) -> Option<OperatorApproval> {
+    env.storage()
+        .persistent()
+        .get(&DataKey::Operator(trader.clone(), operator.clone()))
+}
+
+pub fn set_operator_approval(
+    env: &Env,
+    trader: &Address,
+    operator: &Address,
+    approval: &OperatorApproval,
+) {
+    let key = DataKey::Operator(trader.clone(), operator.clone());
+    env.storage().persistent().set(&key, approval);
+    extend_persistent_ttl(env, &key);
+}
+
+pub fn remove_operator_approval(env: &Env, trader: &Address, operator: &Address) {
+    env.storage()
+        .persistent()
+        .remove(&DataKey::Operator(trader.clone(), operator.clone()));
+}
@@ -0,0 +1,1165 @@
+//! # Market Contract Tests
+//!
+//! Contract-level tests that run the market against a USDC Stellar asset
+//! contract and minimal vault and oracle contracts standing in for the
+//! real ones.
+
+use super::*;
+use noether_common::PRECISION;
+use soroban_sdk::testutils::{Address as _, Ledger};
+use soroban_sdk::token::{StellarAssetClient, TokenClient};
+use soroban_sdk::{contract, contracttype};
+
+// ═══════════════════════════════════════════════════════════════════════════
+// Mock Contracts
+// ═══════════════════════════════════════════════════════════════════════════
+
+#[contract]
+pub struct MockOracle;
+
+#[contractimpl]
+impl MockOracle {
+    pub fn set_price(env: Env, asset: Symbol, price: i128) {
+        env.storage().instance().set(&asset, &price);
+    }
+
+    pub fn lastprice(env: Env, asset: Symbol) -> (i128, u64) {
+        let price: i128 = env.storage().instance().get(&asset).unwrap();
+        (price, env.ledger().timestamp())
+    }
+}
+
+#[contracttype]
+#[derive(Clone)]
+enum VaultKey {
+    Market,
+    Usdc,
+    TotalUsdc,
+    Reserved,
+    LastFunding,
+}
+
+/// Vault keeping the real vault's accounting: `total_usdc` tracks what
+/// LPs are owed and moves with settled PnL and funding.
+#[contract]
+pub struct MockVault;
+
+#[contractimpl]
+impl MockVault {
+    pub fn init(env: Env, market: Address, usdc: Address, total_usdc: i128) {
+        env.storage().instance().set(&VaultKey::Market, &market);
+        env.storage().instance().set(&VaultKey::Usdc, &usdc);
+        env.storage().instance().set(&VaultKey::TotalUsdc, &total_usdc);
+        env.storage().instance().set(&VaultKey::Reserved, &0i128);
+    }
+
+    pub fn settle_pnl(env: Env, pnl: i128) {
+        if pnl > 0 {
+            Self::pay_market(&env, pnl);
+        }
+        Self::set_total(&env, Self::get_total_usdc(env.clone()) - pnl);
+    }
+
+    pub fn settle_funding(env: Env, amount: i128) {
+        if amount < 0 {
+            Self::pay_market(&env, -amount);
+        }
+        Self::set_total(&env, Self::get_total_usdc(env.clone()) + amount);
+        env.storage().instance().set(&VaultKey::LastFunding, &amount);
+    }
+
+    /// Amount passed to the last `settle_funding` call
+    pub fn last_funding(env: Env) -> i128 {
+        env.storage().instance().get(&VaultKey::LastFunding).unwrap_or(0)
+    }
+
+    pub fn reserve_for_position(env: Env, amount: i128) -> Result<(), NoetherError> {
+        let reserved = Self::reserved(&env) + amount;
+        if reserved > Self::get_total_usdc(env.clone()) {
+            return Err(NoetherError::InsufficientLiquidity);
+        }
+        env.storage().instance().set(&VaultKey::Reserved, &reserved);
+        Ok(())
+    }
+
+    pub fn release_reservation(env: Env, amount: i128) -> Result<(), NoetherError> {
+        let reserved = Self::reserved(&env) - amount;
+        if reserved < 0 {
+            return Err(NoetherError::InvalidAmount);
+        }
+        env.storage().instance().set(&VaultKey::Reserved, &reserved);
+        Ok(())
+    }
+
+    pub fn get_reserved_usdc(env: Env) -> i128 {
+        Self::reserved(&env)
+    }
+
+    pub fn get_total_usdc(env: Env) -> i128 {
+        env.storage().instance().get(&VaultKey::TotalUsdc).unwrap_or(0)
+    }
+
+    pub fn get_aum(env: Env) -> i128 {
+        Self::get_total_usdc(env)
+    }
+
+    pub fn get_utilization(env: Env) -> i128 {
+        let total = Self::get_total_usdc(env.clone());
+        if total <= 0 {
+            return 0;
+        }
+        Self::reserved(&env) * (BASIS_POINTS as i128) / total
+    }
+
+    fn reserved(env: &Env) -> i128 {
+        env.storage().instance().get(&VaultKey::Reserved).unwrap_or(0)
+    }
+
+    fn set_total(env: &Env, total: i128) {
+        env.storage().instance().set(&VaultKey::TotalUsdc, &total);
+    }
+
+    fn pay_market(env: &Env, amount: i128) {
+        let usdc: Address = env.storage().instance().get(&VaultKey::Usdc).unwrap();
+        let market: Address = env.storage().instance().get(&VaultKey::Market).unwrap();
+        token::Client::new(env, &usdc).transfer(&env.current_contract_address(), &market, &amount);
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+// Setup
+// ═══════════════════════════════════════════════════════════════════════════
+
+const VAULT_USDC: i128 = 1_000_000 * PRECISION;
+
+struct Setup<'a> {
+    env: Env,
+    market: MarketContractClient<'a>,
+    vault: MockVaultClient<'a>,
+    oracle: MockOracleClient<'a>,
+    usdc: TokenClient<'a>,
+    usdc_admin: StellarAssetClient<'a>,
+    asset: Symbol,
+}
+
+fn market_config() -> MarketConfig {
+    MarketConfig {
+        min_collateral: 10 * PRECISION,
+        max_leverage: 10,
+        maintenance_margin_bps: 100,
+        initial_margin_bps: 1000,
+        liquidation_fee_bps: 500,
+        liquidation_buffer_bps: 100,
+        insurance_fee_share_bps: 0,
+        trading_fee_bps: 0,
+        base_funding_rate_bps: 0,
+        borrow_base_rate_bps: 0,
+        borrow_slope_bps: 0,
+        borrow_kink_slope_bps: 0,
+        optimal_utilization_bps: 8000,
+        price_impact_factor: 0,
+        price_impact_exponent: 2,
+        max_position_size: 100_000 * PRECISION,
+        max_long_oi: 1_000_000 * PRECISION,
+        max_short_oi: 1_000_000 * PRECISION,
+        adl_threshold_bps: 2000,
+        max_price_staleness: 60,
+        max_oracle_deviation_bps: 100,
+    }
+}
+
+fn setup<'a>() -> Setup<'a> {
+    setup_with_config(market_config())
+}
+
+fn setup_with_config<'a>(config: MarketConfig) -> Setup<'a> {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let admin = Address::generate(&env);
+    let usdc_address = env.register_stellar_asset_contract_v2(admin.clone()).address();
+    let usdc = TokenClient::new(&env, &usdc_address);
+    let usdc_admin = StellarAssetClient::new(&env, &usdc_address);
+
+    let oracle = MockOracleClient::new(&env, &env.register_contract(None, MockOracle));
+    let vault = MockVaultClient::new(&env, &env.register_contract(None, MockVault));
+    let market = MarketContractClient::new(&env, &env.register_contract(None, MarketContract));
+
+    vault.init(&market.address, &usdc_address, &VAULT_USDC);
+    usdc_admin.mint(&vault.address, &VAULT_USDC);
+
+    let asset = Symbol::new(&env, "XLM");
+    oracle.set_price(&asset, &PRECISION);
+
+    market.initialize(&admin, &oracle.address, &vault.address, &usdc_address, &config);
+    market.add_market(&asset, &config);
+
+    Setup { env, market, vault, oracle, usdc, usdc_admin, asset }
+}
+
+impl Setup<'_> {
+    fn trader(&self, usdc: i128) -> Address {
+        let trader = Address::generate(&self.env);
+        self.usdc_admin.mint(&trader, &usdc);
+        trader
+    }
+
+    fn open(&self, trader: &Address, collateral: i128, leverage: u32, direction: Direction) -> u64 {
+        self.market
+            .open_position(trader, trader, &self.asset, &collateral, &leverage, &direction)
+            .id
+    }
+
+    fn set_price(&self, price: i128) {
+        self.oracle.set_price(&self.asset, &price);
+    }
+
+    fn advance(&self, seconds: u64) {
+        self.env.ledger().with_mut(|ledger| ledger.timestamp += seconds);
+    }
+
+    /// List a SAC token priced at $1 as margin collateral
+    fn list_token(&self, haircut_bps: u32) -> Address {
+        let token = self.env.register_stellar_asset_contract_v2(self.market.get_admin()).address();
+        let token_symbol = Symbol::new(&self.env, "TOK");
+        self.oracle.set_price(&token_symbol, &PRECISION);
+        self.market.add_collateral_token(&CollateralConfig {
+            token: token.clone(),
+            oracle_symbol: token_symbol,
+            haircut_bps,
+            deposit_cap: 1_000_000 * PRECISION,
+        });
+        token
+    }
+
+    /// USDC the vault holds beyond what its accounting says LPs are owed
+    fn vault_surplus(&self) -> i128 {
+        self.usdc.balance(&self.vault.address) - self.vault.get_total_usdc()
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+// Position Tests
+// ═══════════════════════════════════════════════════════════════════════════
+
+#[test]
+fn test_partial_close_realizes_slice_and_keeps_rest_open() {
+    let s = setup();
+    let trader = s.trader(1_000 * PRECISION);
+    let id = s.open(&trader, 100 * PRECISION, 10, Direction::Long);
+
+    // +10% on 1,000 USDC of size: closing half realizes 50 USDC
+    s.set_price(PRECISION * 110 / 100);
+    assert_eq!(s.market.close_position_partial(&trader, &id, &5000), 50 * PRECISION);
+
+    let position = s.market.get_position(&id).unwrap();
+    assert_eq!(position.size, 500 * PRECISION);
+    assert_eq!(position.collateral, 50 * PRECISION);
+    assert_eq!(s.market.get_margin_account(&trader).balance, 100 * PRECISION);
+
+    // Full closes must go through close_position
+    assert!(s.market.try_close_position_partial(&trader, &id, &10_000).is_err());
+}
+
+#[test]
+fn test_increase_position_averages_entry_price() {
+    let s = setup();
+    let trader = s.trader(1_000 * PRECISION);
+    let id = s.open(&trader, 100 * PRECISION, 10, Direction::Long);
+
+    // Add 1,000 USDC of size at $1.25: 1,000 + 800 units for 2,000 USDC
+    s.set_price(PRECISION * 125 / 100);
+    let position = s.market.increase_position(&trader, &id, &(100 * PRECISION), &10);
+
+    assert_eq!(position.size, 2_000 * PRECISION);
+    assert_eq!(position.collateral, 200 * PRECISION);
+    assert_eq!(position.leverage, 10);
+    assert_eq!(position.entry_price, 2_000 * PRECISION / 1_800);
+}
+
+#[test]
+fn test_remove_collateral_checks_margin_on_live_equity() {
+    let s = setup();
+    let trader = s.trader(1_000 * PRECISION);
+    let id = s.open(&trader, 100 * PRECISION, 5, Direction::Long);
+
+    // -5% leaves 75 USDC of equity against a 50 USDC initial margin
+    s.set_price(PRECISION * 95 / 100);
+    assert_eq!(
+        s.market.try_remove_collateral(&trader, &id, &(30 * PRECISION)),
+        Err(Ok(NoetherError::InsufficientMargin))
+    );
+
+    s.market.remove_collateral(&trader, &id, &(20 * PRECISION));
+    let position = s.market.get_position(&id).unwrap();
+    assert_eq!(position.collateral, 80 * PRECISION);
+    assert_eq!(s.market.get_margin_account(&trader).balance, 20 * PRECISION);
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+// Fee Tests
+// ═══════════════════════════════════════════════════════════════════════════
+
+#[test]
+fn test_funding_accrues_per_second_from_the_heavy_side() {
+    let mut config = market_config();
+    config.base_funding_rate_bps = 10;
+    let s = setup_with_config(config);
+    let trader = s.trader(1_000 * PRECISION);
+
+    // 1,000 USDC long against 500 USDC short: 50% imbalance, longs pay 5 bps/hour
+    let long = s.open(&trader, 100 * PRECISION, 10, Direction::Long);
+    let short = s.open(&trader, 100 * PRECISION, 5, Direction::Short);
+    assert_eq!(s.market.get_funding_rate(&s.asset), 5);
+
+    s.advance(2 * 3600);
+    assert_eq!(s.market.get_position_pnl(&long), -PRECISION);
+    assert_eq!(s.market.get_position_pnl(&short), PRECISION / 2);
+
+    // Accrual is per second, not per whole period
+    s.advance(1800);
+    assert_eq!(s.market.get_position_pnl(&long), -PRECISION * 5 / 4);
+}
+
+#[test]
+fn test_close_settles_funding_with_vault_by_side() {
+    let mut config = market_config();
+    config.base_funding_rate_bps = 10;
+    let s = setup_with_config(config);
+    let trader = s.trader(1_000 * PRECISION);
+
+    let long = s.open(&trader, 100 * PRECISION, 10, Direction::Long);
+    let short = s.open(&trader, 100 * PRECISION, 5, Direction::Short);
+    s.advance(2 * 3600);
+
+    // The long paid 1 USDC of funding: it flows into the pool
+    s.market.close_position(&trader, &long);
+    assert_eq!(s.vault.last_funding(), PRECISION);
+    assert_eq!(s.market.get_margin_account(&trader).balance, 99 * PRECISION);
+
+    // The short is owed 0.5 USDC: the pool pays it out
+    s.market.close_position(&trader, &short);
+    assert_eq!(s.vault.last_funding(), -PRECISION / 2);
+    assert_eq!(s.market.get_margin_account(&trader).balance, 199 * PRECISION + PRECISION / 2);
+
+    assert_eq!(s.vault.get_total_usdc(), VAULT_USDC + PRECISION / 2);
+    assert_eq!(s.vault_surplus(), 0);
+}
+
+#[test]
+fn test_borrow_fee_accrues_on_both_sides_to_the_vault() {
+    let mut config = market_config();
+    config.borrow_base_rate_bps = 1000;
+    let s = setup_with_config(config);
+    let trader = s.trader(1_000 * PRECISION);
+
+    // A balanced book pays no funding but still pays borrow fees
+    let long = s.open(&trader, 100 * PRECISION, 10, Direction::Long);
+    let short = s.open(&trader, 100 * PRECISION, 10, Direction::Short);
+    assert_eq!(s.market.get_market_stats(&s.asset).borrow_rate_bps, 1000);
+
+    // 10% a year on 1,000 USDC for a tenth of a year
+    s.advance(365 * 24 * 3600 / 10);
+    assert_eq!(s.market.get_position_pnl(&long), -10 * PRECISION);
+    assert_eq!(s.market.get_position_pnl(&short), -10 * PRECISION);
+
+    s.market.close_position(&trader, &long);
+    s.market.close_position(&trader, &short);
+    assert_eq!(s.market.get_margin_account(&trader).balance, 180 * PRECISION);
+    assert_eq!(s.vault.get_total_usdc(), VAULT_USDC + 20 * PRECISION);
+    assert_eq!(s.vault_surplus(), 0);
+}
+
+#[test]
+fn test_price_impact_on_open_and_close() {
+    // 1,000 USDC of skew costs 10 USDC of impact
+    let mut config = market_config();
+    config.price_impact_factor = 10_000_000_000_000;
+    let s = setup_with_config(config);
+    let trader = s.trader(1_000 * PRECISION);
+
+    // Opening into an empty book pays 1% on entry
+    let first = s.open(&trader, 100 * PRECISION, 10, Direction::Long);
+    assert_eq!(s.market.get_position(&first).unwrap().entry_price, PRECISION * 101 / 100);
+
+    // Pushing the skew further costs more: 1,000 -> 2,000 is 30 USDC
+    let second = s.open(&trader, 100 * PRECISION, 10, Direction::Long);
+    assert_eq!(s.market.get_position(&second).unwrap().entry_price, PRECISION * 103 / 100);
+
+    // Closing unwinds the skew and earns the impact back
+    s.market.close_position(&trader, &second);
+    assert_eq!(s.market.close_position(&trader, &first), 0);
+    assert_eq!(s.market.get_margin_account(&trader).balance, 200 * PRECISION);
+}
+
+#[test]
+fn test_open_interest_caps_reject_opens() {
+    let mut config = market_config();
+    config.max_long_oi = 1_500 * PRECISION;
+    let s = setup_with_config(config);
+    let trader = s.trader(1_000 * PRECISION);
+
+    // Global cap at 0.25% of the 1,000,000 USDC vault: 2,500 USDC
+    s.market.set_max_global_oi(&25);
+
+    s.open(&trader, 100 * PRECISION, 10, Direction::Long);
+    let over_long = s.market.try_open_position(
+        &trader, &trader, &s.asset, &(100 * PRECISION), &10, &Direction::Long,
+    );
+    assert_eq!(over_long.err(), Some(Ok(NoetherError::OpenInterestCapExceeded)));
+
+    // The short side has its own cap, but the global cap still binds
+    s.open(&trader, 100 * PRECISION, 10, Direction::Short);
+    let over_global = s.market.try_open_position(
+        &trader, &trader, &s.asset, &(100 * PRECISION), &10, &Direction::Short,
+    );
+    assert_eq!(over_global.err(), Some(Ok(NoetherError::OpenInterestCapExceeded)));
+
+    let stats = s.market.get_market_stats(&s.asset);
+    assert_eq!(stats.remaining_long_oi, 500 * PRECISION);
+    assert_eq!(stats.remaining_global_oi, 500 * PRECISION);
+}
+
+#[test]
+fn test_position_releases_exactly_what_it_reserved() {
+    let s = setup();
+    let trader = s.trader(1_000 * PRECISION);
+
+    let id = s.open(&trader, 100 * PRECISION, 10, Direction::Long);
+    assert_eq!(s.vault.get_reserved_usdc(), 1_000 * PRECISION);
+
+    s.market.increase_position(&trader, &id, &(50 * PRECISION), &10);
+    assert_eq!(s.market.get_position(&id).unwrap().reserved, 1_500 * PRECISION);
+    assert_eq!(s.vault.get_reserved_usdc(), 1_500 * PRECISION);
+
+    s.market.close_position_partial(&trader, &id, &2000);
+    assert_eq!(s.market.get_position(&id).unwrap().reserved, 1_200 * PRECISION);
+    assert_eq!(s.vault.get_reserved_usdc(), 1_200 * PRECISION);
+
+    s.market.close_position(&trader, &id);
+    assert_eq!(s.vault.get_reserved_usdc(), 0);
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+// Liquidation Tests
+// ═══════════════════════════════════════════════════════════════════════════
+
+#[test]
+fn test_liquidation_closes_only_enough_to_restore_margin() {
+    let s = setup();
+    let trader = s.trader(1_000 * PRECISION);
+    let keeper = Address::generate(&s.env);
+    let id = s.open(&trader, 100 * PRECISION, 10, Direction::Long);
+
+    // 5 USDC of equity on 1,000 USDC of size is below the 1% maintenance margin
+    s.set_price(PRECISION * 905 / 1000);
+    assert!(s.market.is_liquidatable(&id));
+
+    // Closing 75.76% lifts the margin ratio back to maintenance + buffer (2%)
+    let reward = s.market.liquidate(&keeper, &id);
+    let position = s.market.get_position(&id).unwrap();
+    assert_eq!(position.size, 2_424_000_000);
+    assert_eq!(position.collateral, 278_386_000);
+    assert!(!s.market.is_liquidatable(&id));
+
+    // The keeper earns the same share of the 0.25 USDC full reward
+    assert_eq!(reward, 1_894_000);
+    assert_eq!(s.usdc.balance(&keeper), reward);
+
+    // The vault books the loss realized on the liquidated slice
+    assert_eq!(s.vault.get_total_usdc(), VAULT_USDC + 719_720_000);
+    assert_eq!(s.vault_surplus(), 0);
+}
+
+#[test]
+fn test_insurance_fund_covers_bad_debt_before_lps() {
+    let s = setup_with_config(MarketConfig {
+        trading_fee_bps: 10,
+        insurance_fee_share_bps: 5000,
+        ..market_config()
+    });
+    let trader = s.trader(1_000 * PRECISION);
+    let keeper = Address::generate(&s.env);
+
+    // Half of the 1 USDC opening fee feeds the fund, the admin adds 29.5
+    let id = s.open(&trader, 100 * PRECISION, 10, Direction::Long);
+    assert_eq!(s.market.get_insurance_fund(), PRECISION / 2);
+    s.usdc_admin.mint(&s.usdc_admin.admin(), &(295 * PRECISION / 10));
+    assert_eq!(s.market.top_up_insurance_fund(&(295 * PRECISION / 10)), 30 * PRECISION);
+
+    // A 15% drop leaves 51 USDC of losses beyond the 99 USDC of collateral
+    s.set_price(PRECISION * 85 / 100);
+    let vault_before = s.vault.get_total_usdc();
+    assert_eq!(s.market.liquidate(&keeper, &id), 0);
+
+    // The fund pays 30 of the bad debt and LPs absorb the remaining 21
+    assert_eq!(s.market.get_insurance_fund(), 0);
+    assert_eq!(s.vault.get_total_usdc(), vault_before + 129 * PRECISION);
+    assert!(s.market.get_position(&id).is_none());
+}
+
+#[test]
+fn test_funding_owed_makes_position_liquidatable_at_a_fixed_price() {
+    let s = setup_with_config(MarketConfig { base_funding_rate_bps: 10, ..market_config() });
+    let trader = s.trader(1_000 * PRECISION);
+    let keeper = Address::generate(&s.env);
+
+    // The long pays 0.50 USDC of funding an hour to the short
+    let long = s.open(&trader, 100 * PRECISION, 10, Direction::Long);
+    s.open(&trader, 100 * PRECISION, 5, Direction::Short);
+    assert_eq!(s.market.get_liquidation_price(&long), PRECISION * 91 / 100);
+
+    // 15 USDC of equity clears the 10 USDC maintenance margin
+    s.set_price(PRECISION * 915 / 1000);
+    assert!(!s.market.is_liquidatable(&long));
+    assert_eq!(
+        s.market.try_liquidate(&keeper, &long),
+        Err(Ok(NoetherError::NotLiquidatable))
+    );
+
+    // 12 hours of funding eat 6 USDC and lift the liquidation price past the mark
+    s.advance(12 * 3600);
+    assert_eq!(s.market.get_position(&long).unwrap().liquidation_price, PRECISION * 916 / 1000);
+    assert!(s.market.is_liquidatable(&long));
+    s.market.liquidate(&keeper, &long);
+    assert!(s.market.get_position(&long).unwrap().size < 1_000 * PRECISION);
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+// ADL Tests
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// Two longs 50% in profit against a 500 USDC ADL threshold:
+/// 500 USDC of PnL on 100 collateral and 250 USDC on 100 collateral.
+fn adl_setup<'a>(config: MarketConfig) -> (Setup<'a>, u64, u64) {
+    let s = setup_with_config(MarketConfig { adl_threshold_bps: 5, ..config });
+    let trader = s.trader(1_000 * PRECISION);
+    let top = s.open(&trader, 100 * PRECISION, 10, Direction::Long);
+    let second = s.open(&trader, 100 * PRECISION, 5, Direction::Long);
+    s.set_price(PRECISION * 3 / 2);
+    (s, top, second)
+}
+
+#[test]
+fn test_adl_closes_top_ranked_down_to_threshold() {
+    let (s, top, second) = adl_setup(market_config());
+    let keeper = Address::generate(&s.env);
+    assert_eq!(s.market.get_adl_rank(&top), 1);
+    assert_eq!(s.market.get_adl_rank(&second), 2);
+
+    // A single slot only reaches the top ranked position
+    assert_eq!(
+        s.market.try_auto_deleverage(&keeper, &s.asset, &Vec::from_array(&s.env, [second])),
+        Err(Ok(NoetherError::NotAdlEligible))
+    );
+
+    // 250 USDC above the threshold closes half of the top position
+    let realized = s.market.auto_deleverage(&keeper, &s.asset, &Vec::from_array(&s.env, [top]));
+    assert_eq!(realized, 250 * PRECISION);
+    assert_eq!(s.market.get_position(&top).unwrap().size, 500 * PRECISION);
+    assert_eq!(s.market.get_position(&second).unwrap().size, 500 * PRECISION);
+    assert_eq!(s.vault.get_total_usdc(), VAULT_USDC - 250 * PRECISION);
+}
+
+#[test]
+fn test_adl_measures_pnl_net_of_pending_fees() {
+    let (s, top, _) = adl_setup(MarketConfig { borrow_base_rate_bps: 20_000, ..market_config() });
+    let keeper = Address::generate(&s.env);
+
+    // 20% of size in borrow fees takes 300 USDC off the 750 USDC of raw PnL
+    s.advance(365 * 24 * 3600 / 10);
+    assert_eq!(
+        s.market.try_auto_deleverage(&keeper, &s.asset, &Vec::from_array(&s.env, [top])),
+        Err(Ok(NoetherError::AdlNotRequired))
+    );
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+// Batch Tests
+// ═══════════════════════════════════════════════════════════════════════════
+
+#[test]
+fn test_liquidate_batch_skips_ineligible_ids() {
+    let s = setup();
+    let trader = s.trader(1_000 * PRECISION);
+
+    let risky = s.open(&trader, 100 * PRECISION, 10, Direction::Long);
+    let safe = s.open(&trader, 100 * PRECISION, 2, Direction::Long);
+
+    // 10x long is underwater at -15%, the 2x long is not
+    s.set_price(PRECISION * 85 / 100);
+
+    let keeper = Address::generate(&s.env);
+    let ids = Vec::from_array(&s.env, [risky, safe, 999]);
+    let results = s.market.liquidate_batch(&keeper, &ids);
+
+    assert_eq!(results.len(), 3);
+    assert!(results.get(0).unwrap().executed);
+    assert!(!results.get(1).unwrap().executed);
+    assert!(!results.get(2).unwrap().executed);
+
+    assert!(s.market.get_position(&risky).is_none());
+    assert!(s.market.get_position(&safe).is_some());
+}
+
+#[test]
+fn test_execute_orders_batch_skips_failed_fills() {
+    // Room for one 1,000 USDC long but not two
+    let mut config = market_config();
+    config.max_long_oi = 1_500 * PRECISION;
+    let s = setup_with_config(config);
+    let trader = s.trader(1_000 * PRECISION);
+
+    let mut ids = Vec::new(&s.env);
+    for _ in 0..2 {
+        let order = s.market.place_limit_order(
+            &trader,
+            &trader,
+            &s.asset,
+            &Direction::Long,
+            &(100 * PRECISION),
+            &10,
+            &PRECISION,
+            &false,
+            &LimitOrderOptions { slippage_tolerance_bps: 100, ..Default::default() },
+        );
+        ids.push_back(order.id);
+    }
+
+    // The second fill breaches the OI cap: it is skipped, the first still fills
+    let keeper = Address::generate(&s.env);
+    let results = s.market.execute_orders_batch(&keeper, &ids);
+
+    assert!(results.get(0).unwrap().executed);
+    assert!(!results.get(1).unwrap().executed);
+    assert_eq!(s.market.get_order(&ids.get(0).unwrap()).unwrap().status, OrderStatus::Executed);
+    assert_eq!(s.market.get_order(&ids.get(1).unwrap()).unwrap().status, OrderStatus::Pending);
+    assert_eq!(s.market.get_positions(&trader).len(), 1);
+}
+
+#[test]
+fn test_orders_left_without_their_position_are_cancelled() {
+    let s = setup();
+    let trader = s.trader(1_000 * PRECISION);
+    let keeper = Address::generate(&s.env);
+
+    // Stops left behind by positions removed without their orders
+    let mut stops = Vec::new(&s.env);
+    for _ in 0..2 {
+        let id = s.open(&trader, 100 * PRECISION, 10, Direction::Long);
+        let stop = s.market.set_stop_loss(&trader, &id, &(PRECISION * 95 / 100), &100, &None);
+        s.env.as_contract(&s.market.address, || delete_position(&s.env, id, &trader));
+        stops.push_back(stop.id);
+    }
+
+    // Neither is triggered, yet both come off the book instead of staying pending
+    assert_eq!(s.market.execute_order(&keeper, &stops.get(0).unwrap()), 0);
+    let results = s.market.execute_orders_batch(&keeper, &stops.slice(1..));
+    assert!(!results.get(0).unwrap().executed);
+
+    for stop in stops.iter() {
+        assert_eq!(s.market.get_order(&stop).unwrap().status, OrderStatus::Cancelled);
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+// Order Tests
+// ═══════════════════════════════════════════════════════════════════════════
+
+#[test]
+fn test_bracket_invalid_at_fill_cancels_order() {
+    let s = setup();
+    let trader = s.trader(1_000 * PRECISION);
+
+    // Long entry at $1.00 with a stop-loss at $0.995
+    let options = LimitOrderOptions {
+        slippage_tolerance_bps: 200,
+        stop_loss_price: Some(PRECISION * 995 / 1000),
+        ..Default::default()
+    };
+    let order = s.market.place_limit_order(
+        &trader,
+        &trader,
+        &s.asset,
+        &Direction::Long,
+        &(100 * PRECISION),
+        &10,
+        &PRECISION,
+        &false,
+        &options,
+    );
+
+    // Filling at $0.99 would put the stop-loss above entry
+    s.set_price(PRECISION * 99 / 100);
+    let keeper = Address::generate(&s.env);
+    assert_eq!(s.market.execute_order(&keeper, &order.id), 0);
+
+    assert_eq!(s.market.get_order(&order.id).unwrap().status, OrderStatus::Cancelled);
+    assert_eq!(s.market.get_margin_account(&trader).balance, 100 * PRECISION);
+    assert_eq!(s.market.get_positions(&trader).len(), 0);
+}
+
+#[test]
+fn test_keeper_order_fee_applies_referral_discount() {
+    let s = setup();
+    let trader = s.trader(1_000 * PRECISION);
+
+    // Half off fees for traders referred with the code
+    let tiers = Vec::from_array(&s.env, [ReferralTier { discount_bps: 5000, rebate_bps: 0 }]);
+    s.market.set_referral_tiers(&tiers);
+    let code = Symbol::new(&s.env, "FRIEND");
+    s.market.register_code(&Address::generate(&s.env), &code);
+    s.market.set_referrer(&trader, &code);
+
+    let order = s.market.place_limit_order(
+        &trader,
+        &trader,
+        &s.asset,
+        &Direction::Long,
+        &(100 * PRECISION),
+        &10,
+        &PRECISION,
+        &false,
+        &LimitOrderOptions { slippage_tolerance_bps: 100, ..Default::default() },
+    );
+
+    // 0.50 USDC base + 0.05% of 1,000 USDC, halved
+    let keeper = Address::generate(&s.env);
+    assert_eq!(s.market.execute_order(&keeper, &order.id), PRECISION / 2);
+    assert_eq!(s.usdc.balance(&keeper), PRECISION / 2);
+}
+
+#[test]
+fn test_expired_orders_come_off_the_book_with_a_refund() {
+    let s = setup();
+    let trader = s.trader(1_000 * PRECISION);
+    let keeper = Address::generate(&s.env);
+
+    let place = |expires_at: u64| {
+        s.market.try_place_limit_order(
+            &trader,
+            &trader,
+            &s.asset,
+            &Direction::Long,
+            &(100 * PRECISION),
+            &10,
+            &(PRECISION * 9 / 10),
+            &false,
+            &LimitOrderOptions {
+                slippage_tolerance_bps: 100,
+                expires_at: Some(expires_at),
+                ..Default::default()
+            },
+        )
+    };
+
+    // Expiry must be in the future
+    let now = s.env.ledger().timestamp();
+    assert_eq!(place(now).err(), Some(Ok(NoetherError::InvalidParameter)));
+    let hour = place(now + 3600).unwrap().unwrap();
+    let day = place(now + 24 * 3600).unwrap().unwrap();
+
+    // Only the order past its expiry is expired, and its collateral refunded
+    s.advance(3600);
+    let ids = Vec::from_array(&s.env, [hour.id, day.id]);
+    assert_eq!(s.market.expire_orders(&ids), Vec::from_array(&s.env, [hour.id]));
+    assert_eq!(s.market.get_order(&hour.id).unwrap().status, OrderStatus::Expired);
+    assert_eq!(s.market.get_order(&day.id).unwrap().status, OrderStatus::Pending);
+    assert_eq!(s.market.get_margin_account(&trader).balance, 100 * PRECISION);
+
+    // A keeper reaching a triggered order after its expiry expires it instead of filling it
+    s.advance(24 * 3600);
+    s.set_price(PRECISION * 9 / 10);
+    assert_eq!(s.market.execute_order(&keeper, &day.id), 0);
+    assert_eq!(s.market.get_order(&day.id).unwrap().status, OrderStatus::Expired);
+    assert_eq!(s.market.get_margin_account(&trader).balance, 200 * PRECISION);
+    assert_eq!(s.market.get_positions(&trader).len(), 0);
+}
+
+#[test]
+fn test_modify_order_moves_only_the_collateral_difference() {
+    let s = setup();
+    let trader = s.trader(1_000 * PRECISION);
+
+    let order = s.market.place_limit_order(
+        &trader,
+        &trader,
+        &s.asset,
+        &Direction::Long,
+        &(100 * PRECISION),
+        &10,
+        &(PRECISION * 9 / 10),
+        &false,
+        &LimitOrderOptions { slippage_tolerance_bps: 100, ..Default::default() },
+    );
+    assert_eq!(s.usdc.balance(&trader), 900 * PRECISION);
+
+    // Raising the collateral pulls only the extra 50 USDC
+    let new_trigger = PRECISION * 95 / 100;
+    let modified = s.market.modify_order(&trader, &order.id, &new_trigger, &200, &(150 * PRECISION));
+    assert_eq!(modified.trigger_price, new_trigger);
+    assert_eq!(modified.slippage_tolerance_bps, 200);
+    assert_eq!(s.usdc.balance(&trader), 850 * PRECISION);
+
+    // Lowering it returns the 70 USDC difference to the margin account
+    s.market.modify_order(&trader, &order.id, &new_trigger, &200, &(80 * PRECISION));
+    assert_eq!(s.market.get_order(&order.id).unwrap().collateral, 80 * PRECISION);
+    assert_eq!(s.market.get_margin_account(&trader).balance, 70 * PRECISION);
+
+    // Stop-losses keep the side check of set_stop_loss
+    let id = s.open(&trader, 100 * PRECISION, 10, Direction::Long);
+    let stop = s.market.set_stop_loss(&trader, &id, &(PRECISION * 95 / 100), &100, &None);
+    assert_eq!(
+        s.market.try_modify_order(&trader, &stop.id, &(PRECISION * 105 / 100), &100, &0).err(),
+        Some(Ok(NoetherError::InvalidTriggerPrice))
+    );
+    let stop = s.market.modify_order(&trader, &stop.id, &(PRECISION * 97 / 100), &100, &0);
+    assert_eq!(stop.trigger_price, PRECISION * 97 / 100);
+}
+
+#[test]
+fn test_trailing_stop_ratchets_and_fires_on_retracement() {
+    let s = setup();
+    let trader = s.trader(1_000 * PRECISION);
+    let keeper = Address::generate(&s.env);
+
+    // 5% trail under a $1.00 mark
+    let id = s.open(&trader, 100 * PRECISION, 10, Direction::Long);
+    let stop = s.market.set_trailing_stop(&trader, &id, &500, &0, &100, &None);
+    assert_eq!(stop.trigger_price, PRECISION * 95 / 100);
+
+    // The mark follows the price up but never back down
+    s.set_price(PRECISION * 12 / 10);
+    assert_eq!(s.market.update_trailing_stop(&stop.id), PRECISION * 114 / 100);
+    s.set_price(PRECISION * 115 / 100);
+    assert_eq!(s.market.update_trailing_stop(&stop.id), PRECISION * 114 / 100);
+    assert_eq!(
+        s.market.try_execute_order(&keeper, &stop.id),
+        Err(Ok(NoetherError::OrderNotTriggered))
+    );
+
+    // A retracement to the trigger locks in 140 USDC, less the 1 USDC keeper fee
+    s.set_price(PRECISION * 114 / 100);
+    assert_eq!(s.market.execute_order(&keeper, &stop.id), PRECISION);
+    assert_eq!(s.market.get_order(&stop.id).unwrap().status, OrderStatus::Executed);
+    assert!(s.market.get_position(&id).is_none());
+    assert_eq!(s.market.get_margin_account(&trader).balance, 239 * PRECISION);
+}
+
+#[test]
+fn test_closing_a_position_cancels_its_sibling_orders() {
+    let s = setup();
+    let trader = s.trader(1_000 * PRECISION);
+    let keeper = Address::generate(&s.env);
+
+    // One position with a stop-loss and take-profit left to trigger
+    let filled = s.open(&trader, 100 * PRECISION, 10, Direction::Long);
+    let stop = s.market.set_stop_loss(&trader, &filled, &(PRECISION * 95 / 100), &100, &None);
+    let target = s.market.set_take_profit(&trader, &filled, &(PRECISION * 11 / 10), &100, &None);
+
+    // Manual close: both of its orders are cancelled
+    let closed = s.open(&trader, 100 * PRECISION, 10, Direction::Long);
+    let closed_stop = s.market.set_stop_loss(&trader, &closed, &(PRECISION * 95 / 100), &100, &None);
+    let closed_target = s.market.set_take_profit(&trader, &closed, &(PRECISION * 11 / 10), &100, &None);
+    s.market.close_position(&trader, &closed);
+
+    // Take-profit fills: the stop-loss is cancelled with it
+    s.set_price(PRECISION * 11 / 10);
+    s.market.execute_order(&keeper, &target.id);
+
+    assert_eq!(s.market.get_order(&target.id).unwrap().status, OrderStatus::Executed);
+    for order in [stop.id, closed_stop.id, closed_target.id] {
+        assert_eq!(s.market.get_order(&order).unwrap().status, OrderStatus::Cancelled);
+    }
+    assert_eq!(s.market.get_all_order_ids().len(), 0);
+    assert_eq!(s.market.get_orders(&trader).len(), 0);
+    assert_eq!(s.market.get_position_sl(&filled), None);
+}
+
+#[test]
+fn test_operator_is_limited_to_its_scopes() {
+    let s = setup();
+    let trader = s.trader(1_000 * PRECISION);
+    let operator = Address::generate(&s.env);
+    s.usdc.approve(&trader, &s.market.address, &(1_000 * PRECISION), &1_000);
+
+    let orders_only = OperatorPermissions {
+        open: false,
+        close: false,
+        manage_orders: true,
+        add_collateral: false,
+    };
+    s.market.approve_operator(&trader, &operator, &orders_only, &None);
+
+    // Opening needs the open permission
+    assert_eq!(
+        s.market
+            .try_open_position(&operator, &trader, &s.asset, &(100 * PRECISION), &10, &Direction::Long)
+            .err(),
+        Some(Ok(NoetherError::OperatorNotPermitted))
+    );
+
+    let order = s.market.place_limit_order(
+        &trader,
+        &trader,
+        &s.asset,
+        &Direction::Long,
+        &(100 * PRECISION),
+        &10,
+        &(PRECISION * 9 / 10),
+        &false,
+        &LimitOrderOptions { slippage_tolerance_bps: 100, ..Default::default() },
+    );
+    let trigger = PRECISION * 95 / 100;
+
+    // Managing orders may move the trigger, but not pull more from the wallet
+    assert_eq!(
+        s.market.try_modify_order(&operator, &order.id, &trigger, &100, &(150 * PRECISION)).err(),
+        Some(Ok(NoetherError::OperatorNotPermitted))
+    );
+    s.market.modify_order(&operator, &order.id, &trigger, &100, &(100 * PRECISION));
+    assert_eq!(s.usdc.balance(&trader), 900 * PRECISION);
+
+    // With the open permission as well it can
+    let with_open = OperatorPermissions { open: true, ..orders_only };
+    s.market.approve_operator(&trader, &operator, &with_open, &None);
+    s.market.modify_order(&operator, &order.id, &trigger, &100, &(150 * PRECISION));
+    assert_eq!(s.usdc.balance(&trader), 850 * PRECISION);
+
+    // Revoked operators can do nothing
+    s.market.revoke_operator(&trader, &operator);
+    assert_eq!(
+        s.market.try_cancel_order(&operator, &order.id),
+        Err(Ok(NoetherError::NotOrderOwner))
+    );
+}
+
+#[test]
+fn test_referral_discounts_trading_fee_and_accrues_claimable_rebate() {
+    let s = setup_with_config(MarketConfig { trading_fee_bps: 10, ..market_config() });
+    let trader = s.trader(1_000 * PRECISION);
+    let referrer = Address::generate(&s.env);
+
+    // 20% off for the trader, half of what is left to the referrer
+    let tiers = Vec::from_array(&s.env, [ReferralTier { discount_bps: 2000, rebate_bps: 5000 }]);
+    s.market.set_referral_tiers(&tiers);
+    let code = Symbol::new(&s.env, "FRIEND");
+    s.market.register_code(&referrer, &code);
+    s.market.set_referrer(&trader, &code);
+
+    // The 1 USDC fee on 1,000 USDC of size drops to 0.80, split with the referrer
+    let id = s.open(&trader, 100 * PRECISION, 10, Direction::Long);
+    assert_eq!(s.market.get_position(&id).unwrap().collateral, PRECISION * 992 / 10);
+    assert_eq!(s.vault_surplus(), PRECISION * 4 / 10);
+
+    let stats = s.market.get_referral_stats(&referrer);
+    assert_eq!(stats.referred_traders, 1);
+    assert_eq!(stats.total_rewards, PRECISION * 4 / 10);
+    assert_eq!(stats.claimable, PRECISION * 4 / 10);
+
+    // Claiming pays out the rebate once
+    assert_eq!(s.market.claim_referral_rewards(&referrer), PRECISION * 4 / 10);
+    assert_eq!(s.usdc.balance(&referrer), PRECISION * 4 / 10);
+    assert_eq!(s.market.get_referral_stats(&referrer).claimable, 0);
+    assert_eq!(
+        s.market.try_claim_referral_rewards(&referrer),
+        Err(Ok(NoetherError::NoReferralRewards))
+    );
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+// Margin Account Tests
+// ═══════════════════════════════════════════════════════════════════════════
+
+#[test]
+fn test_margin_account_funds_trades_without_wallet_transfers() {
+    let s = setup();
+    let trader = s.trader(1_000 * PRECISION);
+    assert_eq!(s.market.deposit_margin(&trader, &(500 * PRECISION)), 500 * PRECISION);
+
+    // Positions and orders draw from the balance, leaving the wallet alone
+    let id = s.open(&trader, 100 * PRECISION, 10, Direction::Long);
+    s.market.place_limit_order(
+        &trader,
+        &trader,
+        &s.asset,
+        &Direction::Long,
+        &(50 * PRECISION),
+        &10,
+        &(PRECISION * 9 / 10),
+        &false,
+        &LimitOrderOptions { slippage_tolerance_bps: 100, ..Default::default() },
+    );
+    let account = s.market.get_margin_account(&trader);
+    assert_eq!(account.balance, 350 * PRECISION);
+    assert_eq!(account.position_collateral, 100 * PRECISION);
+    assert_eq!(account.order_collateral, 50 * PRECISION);
+    assert_eq!(s.usdc.balance(&trader), 500 * PRECISION);
+
+    // Closing credits collateral and profit back to the balance
+    s.set_price(PRECISION * 11 / 10);
+    s.market.close_position(&trader, &id);
+    assert_eq!(s.market.get_margin_account(&trader).balance, 550 * PRECISION);
+    assert_eq!(s.usdc.balance(&trader), 500 * PRECISION);
+
+    // Only the free balance can be withdrawn
+    assert_eq!(
+        s.market.try_withdraw_margin(&trader, &(551 * PRECISION)),
+        Err(Ok(NoetherError::InsufficientBalance))
+    );
+    assert_eq!(s.market.withdraw_margin(&trader, &(550 * PRECISION)), 0);
+    assert_eq!(s.usdc.balance(&trader), 1_050 * PRECISION);
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+// Cross Margin Tests
+// ═══════════════════════════════════════════════════════════════════════════
+
+#[test]
+fn test_cross_close_draws_loss_beyond_collateral_from_balance() {
+    let s = setup();
+    let trader = s.trader(1_000 * PRECISION);
+    s.market.set_margin_mode(&trader, &MarginMode::Cross);
+    s.market.deposit_margin(&trader, &(1_000 * PRECISION));
+
+    // A hedged account: the short's gain keeps it healthy while the long sinks
+    let long = s.open(&trader, 100 * PRECISION, 10, Direction::Long);
+    let short = s.open(&trader, 100 * PRECISION, 10, Direction::Short);
+
+    // -20%: the long loses 200 USDC on 100 of collateral
+    s.set_price(PRECISION * 80 / 100);
+    assert_eq!(s.market.close_position(&trader, &long), -200 * PRECISION);
+
+    // The vault is owed the full loss, the extra 100 coming from the margin balance
+    assert_eq!(s.vault.get_total_usdc(), VAULT_USDC + 200 * PRECISION);
+    assert_eq!(s.vault_surplus(), 0);
+    assert_eq!(s.market.get_margin_account(&trader).balance, 700 * PRECISION);
+
+    // Closing the short pays its gain back, leaving both sides whole
+    s.market.close_position(&trader, &short);
+    assert_eq!(s.vault.get_total_usdc(), VAULT_USDC);
+    assert_eq!(s.vault_surplus(), 0);
+    assert_eq!(s.market.get_margin_account(&trader).balance, 1_000 * PRECISION);
+}
+
+#[test]
+fn test_cross_account_liquidation_closes_worst_position_first() {
+    let s = setup();
+    let trader = s.trader(1_000 * PRECISION);
+    let keeper = Address::generate(&s.env);
+    s.market.set_margin_mode(&trader, &MarginMode::Cross);
+    s.market.deposit_margin(&trader, &(300 * PRECISION));
+
+    // 100 USDC free next to a 10x long and a 1x short
+    let long = s.open(&trader, 100 * PRECISION, 10, Direction::Long);
+    let short = s.open(&trader, 100 * PRECISION, 1, Direction::Short);
+
+    // At $0.70 the short's gain and the free balance carry the long
+    s.set_price(PRECISION * 7 / 10);
+    let health = s.market.get_account_health(&trader);
+    assert_eq!(health.equity, 30 * PRECISION);
+    assert_eq!(health.maintenance_margin, 11 * PRECISION);
+    assert!(!health.liquidatable);
+    assert_eq!(
+        s.market.try_liquidate_account(&keeper, &trader),
+        Err(Ok(NoetherError::NotLiquidatable))
+    );
+
+    // At $0.65 the account is underwater; its positions are only liquidated together
+    s.set_price(PRECISION * 65 / 100);
+    assert!(s.market.get_account_health(&trader).liquidatable);
+    assert_eq!(s.market.try_liquidate(&keeper, &long), Err(Ok(NoetherError::NotLiquidatable)));
+
+    // Closing the long alone restores the account; the short stays open
+    s.market.liquidate_account(&keeper, &trader);
+    assert!(s.market.get_position(&long).is_none());
+    assert!(s.market.get_position(&short).is_some());
+    assert_eq!(s.market.get_margin_account(&trader).balance, 0);
+    let health = s.market.get_account_health(&trader);
+    assert_eq!(health.equity, 135 * PRECISION);
+    assert!(!health.liquidatable);
+}
+
+#[test]
+fn test_seized_token_collateral_is_not_booked_as_usdc() {
+    let s = setup();
+    let admin = s.market.get_admin();
+    let token = s.list_token(1000);
+
+    s.usdc_admin.mint(&admin, &(1_000 * PRECISION));
+    s.market.top_up_insurance_fund(&(1_000 * PRECISION));
+
+    let trader = s.trader(100 * PRECISION);
+    StellarAssetClient::new(&s.env, &token).mint(&trader, &(100 * PRECISION));
+    s.market.set_margin_mode(&trader, &MarginMode::Cross);
+    s.market.deposit_margin_token(&trader, &token, &(100 * PRECISION));
+    s.open(&trader, 100 * PRECISION, 10, Direction::Long);
+
+    // -20%: the long owes 200 USDC on 100 of collateral and there is no USDC balance
+    s.set_price(PRECISION * 80 / 100);
+    let keeper = Address::generate(&s.env);
+    s.market.liquidate_account(&keeper, &trader);
+
+    // The vault is paid the full loss in USDC, the fund covering the shortfall
+    assert_eq!(s.vault.get_total_usdc(), VAULT_USDC + 200 * PRECISION);
+    assert_eq!(s.vault_surplus(), 0);
+    assert_eq!(s.market.get_insurance_fund(), 900 * PRECISION);
+
+    // The tokens backing the shortfall are held by the market, not the vault
+    let token_client = TokenClient::new(&s.env, &token);
+    assert_eq!(token_client.balance(&s.vault.address), 0);
+    assert_eq!(s.market.get_seized_collateral(&token), 100 * PRECISION);
+    assert_eq!(s.market.get_margin_token_balance(&trader, &token), 0);
+}
+
+#[test]
+fn test_token_collateral_counts_at_its_haircut_value() {
+    let s = setup();
+    let token = s.list_token(1000);
+    let trader = s.trader(0);
+    StellarAssetClient::new(&s.env, &token).mint(&trader, &(100 * PRECISION));
+    s.market.set_margin_mode(&trader, &MarginMode::Cross);
+    s.market.deposit_margin_token(&trader, &token, &(100 * PRECISION));
+
+    // 100 tokens at $1 less 10%
+    assert_eq!(s.market.get_margin_account(&trader).token_value, 90 * PRECISION);
+    assert_eq!(s.market.get_account_health(&trader).equity, 90 * PRECISION);
+
+    // The haircut value follows the oracle
+    s.oracle.set_price(&Symbol::new(&s.env, "TOK"), &(PRECISION / 2));
+    assert_eq!(s.market.get_margin_account(&trader).token_value, 45 * PRECISION);
+}
+
+#[test]
+fn test_swept_collateral_repays_lps_before_the_fund() {
+    let s = setup();
+    let admin = s.market.get_admin();
+    let token = s.list_token(1000);
+
+    // The fund holds only half of the bad debt to come
+    s.usdc_admin.mint(&admin, &(150 * PRECISION));
+    s.market.top_up_insurance_fund(&(50 * PRECISION));
+
+    let trader = s.trader(100 * PRECISION);
+    StellarAssetClient::new(&s.env, &token).mint(&trader, &(100 * PRECISION));
+    s.market.set_margin_mode(&trader, &MarginMode::Cross);
+    s.market.deposit_margin_token(&trader, &token, &(100 * PRECISION));
+    s.open(&trader, 100 * PRECISION, 10, Direction::Long);
+
+    // -20%: 100 USDC of bad debt, 50 paid by the fund and 50 absorbed by LPs
+    s.set_price(PRECISION * 80 / 100);
+    s.market.liquidate_account(&Address::generate(&s.env), &trader);
+    assert_eq!(s.vault.get_total_usdc(), VAULT_USDC + 150 * PRECISION);
+    assert_eq!(s.market.get_insurance_fund(), 0);
+    assert_eq!(s.market.get_seized_collateral(&token), 100 * PRECISION);
+    assert_eq!(s.market.get_unrecovered_bad_debt(), 50 * PRECISION);
+
+    // Selling the tokens at $1 repays LPs their 50, then refills the fund
+    assert_eq!(s.market.sweep_seized_collateral(&token, &(100 * PRECISION)), 0);
+    assert_eq!(s.vault.get_total_usdc(), VAULT_USDC + 200 * PRECISION);
+    assert_eq!(s.vault_surplus(), 0);
+    assert_eq!(s.market.get_insurance_fund(), 50 * PRECISION);
+    assert_eq!(s.market.get_unrecovered_bad_debt(), 0);
+    assert_eq!(s.usdc.balance(&admin), 0);
+    assert_eq!(TokenClient::new(&s.env, &token).balance(&admin), 100 * PRECISION);
+}
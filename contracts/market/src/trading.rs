@@ -78,7 +78,8 @@ pub fn calculate_partial_close(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use soroban_sdk::{Env, Address, Symbol};
+    use noether_common::PRECISION;
+    use soroban_sdk::{testutils::Address as _, Env, Address, Symbol};
 
     fn create_test_position(env: &Env) -> Position {
         Position {
@@ -94,6 +95,10 @@ mod tests {
             timestamp: 1000000,
             last_funding_time: 1000000,
             accumulated_funding: 0,
+            funding_index: 0,
+            accumulated_borrow_fee: 0,
+            borrow_index: 0,
+            reserved: 0,
         }
     }
 
@@ -0,0 +1,105 @@
+//! # Trailing Stop Logic
+//!
+//! Calculations for trailing stop orders, which follow the price as it moves
+//! in the position's favour and close the position when it retraces.
+//!
+//! ## Water Mark
+//!
+//! Each trailing stop keeps the best price seen since it was placed:
+//! - Long: high-water mark (highest price)
+//! - Short: low-water mark (lowest price)
+//!
+//! ## Trigger
+//!
+//! The trail distance is either a share of the water mark (bps) or a fixed
+//! price amount:
+//! ```
+//! long:  trigger = water_mark - trail
+//! short: trigger = water_mark + trail
+//! ```
+
+use noether_common::{Direction, BASIS_POINTS};
+
+/// Calculate the trail distance at a water mark.
+///
+/// # Arguments
+/// * `water_mark` - Best price seen (7 decimals)
+/// * `trail_bps` - Trail as a share of the water mark (0 = use `trail_amount`)
+/// * `trail_amount` - Trail as a fixed price distance (7 decimals)
+pub fn calculate_trail_distance(water_mark: i128, trail_bps: u32, trail_amount: i128) -> i128 {
+    if trail_bps > 0 {
+        water_mark * (trail_bps as i128) / (BASIS_POINTS as i128)
+    } else {
+        trail_amount
+    }
+}
+
+/// Calculate the trigger price of a trailing stop.
+///
+/// # Returns
+/// Trigger price (7 decimals), never negative
+pub fn calculate_trailing_trigger(
+    water_mark: i128,
+    direction: Direction,
+    trail_bps: u32,
+    trail_amount: i128,
+) -> i128 {
+    let distance = calculate_trail_distance(water_mark, trail_bps, trail_amount);
+
+    match direction {
+        Direction::Long => (water_mark - distance).max(0),
+        Direction::Short => water_mark + distance,
+    }
+}
+
+/// Ratchet a water mark towards the current price.
+///
+/// # Returns
+/// New water mark (only ever moves in the position's favour)
+pub fn update_water_mark(water_mark: i128, current_price: i128, direction: Direction) -> i128 {
+    match direction {
+        Direction::Long => water_mark.max(current_price),
+        Direction::Short => water_mark.min(current_price),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use noether_common::PRECISION;
+
+    #[test]
+    fn test_trailing_trigger_bps() {
+        // 5% below a $2.00 high
+        assert_eq!(
+            calculate_trailing_trigger(2 * PRECISION, Direction::Long, 500, 0),
+            19 * PRECISION / 10
+        );
+        // 5% above a $2.00 low
+        assert_eq!(
+            calculate_trailing_trigger(2 * PRECISION, Direction::Short, 500, 0),
+            21 * PRECISION / 10
+        );
+    }
+
+    #[test]
+    fn test_trailing_trigger_absolute() {
+        // $0.25 below a $2.00 high
+        assert_eq!(
+            calculate_trailing_trigger(2 * PRECISION, Direction::Long, 0, PRECISION / 4),
+            175 * PRECISION / 100
+        );
+        // Never below zero
+        assert_eq!(calculate_trailing_trigger(PRECISION, Direction::Long, 0, 2 * PRECISION), 0);
+    }
+
+    #[test]
+    fn test_water_mark_ratchets() {
+        // Longs follow new highs but not retracements
+        assert_eq!(update_water_mark(2 * PRECISION, 3 * PRECISION, Direction::Long), 3 * PRECISION);
+        assert_eq!(update_water_mark(2 * PRECISION, PRECISION, Direction::Long), 2 * PRECISION);
+        // Shorts follow new lows
+        assert_eq!(update_water_mark(2 * PRECISION, PRECISION, Direction::Short), PRECISION);
+        assert_eq!(update_water_mark(2 * PRECISION, 3 * PRECISION, Direction::Short), 2 * PRECISION);
+    }
+}
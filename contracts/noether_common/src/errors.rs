@@ -45,6 +45,8 @@ pub enum NoetherError {
     NotPositionOwner = 24,
     /// Position has insufficient margin for operation
     InsufficientMargin = 25,
+    /// Trade would exceed the asset's or the global open interest cap
+    OpenInterestCapExceeded = 26,
 
     // ═══════════════════════════════════════════════════════════════
     // Oracle Errors (30-39)
@@ -76,6 +78,10 @@ pub enum NoetherError {
     NotLiquidatable = 50,
     /// Liquidation operation failed
     LiquidationFailed = 51,
+    /// Trader PnL exposure is below the auto-deleveraging threshold
+    AdlNotRequired = 52,
+    /// Position is not profitable or not among the highest ranked for auto-deleveraging
+    NotAdlEligible = 53,
 
     // ═══════════════════════════════════════════════════════════════
     // Funding Rate Errors (55-59)
@@ -104,4 +110,50 @@ pub enum NoetherError {
     InvalidSlippageTolerance = 66,
     /// Position already has this type of order attached
     OrderAlreadyExists = 67,
+
+    // ═══════════════════════════════════════════════════════════════
+    // Market Registry Errors (80-89)
+    // ═══════════════════════════════════════════════════════════════
+
+    /// Asset is not listed in the market registry
+    MarketNotFound = 80,
+    /// Asset is already listed in the market registry
+    MarketAlreadyExists = 81,
+
+    // ═══════════════════════════════════════════════════════════════
+    // Operator Errors (90-99)
+    // ═══════════════════════════════════════════════════════════════
+
+    /// Operator has not been approved by the trader
+    OperatorNotApproved = 90,
+    /// Operator approval has expired or does not cover this action
+    OperatorNotPermitted = 91,
+
+    // ═══════════════════════════════════════════════════════════════
+    // Margin Account Errors (100-109)
+    // ═══════════════════════════════════════════════════════════════
+
+    /// Margin mode cannot change while the trader has open positions
+    OpenPositionsExist = 100,
+    /// Trader's account is not in cross-margin mode
+    NotCrossMargin = 101,
+    /// Token is not listed as margin collateral
+    CollateralNotFound = 102,
+    /// Token is already listed as margin collateral
+    CollateralAlreadyListed = 103,
+    /// Deposit would exceed the token's deposit cap
+    DepositCapExceeded = 104,
+
+    // ═══════════════════════════════════════════════════════════════
+    // Referral Errors (110-119)
+    // ═══════════════════════════════════════════════════════════════
+
+    /// Referral code is already registered
+    ReferralCodeTaken = 110,
+    /// Referral code is not registered
+    ReferralCodeNotFound = 111,
+    /// Traders cannot use their own referral code
+    SelfReferral = 112,
+    /// Referrer has no rewards to claim
+    NoReferralRewards = 113,
 }
@@ -107,23 +107,140 @@ pub fn calculate_position_value(
     current_price: i128,
 ) -> Result<i128, NoetherError> {
     let pnl = calculate_pnl(position, current_price)?;
-    let value = position.collateral + pnl - position.accumulated_funding;
+    let value = position.collateral + pnl - position.accumulated_funding - position.accumulated_borrow_fee;
     Ok(value)
 }
 
-/// Check if a position should be liquidated.
+/// Calculate the average entry price after adding size to a position.
+///
+/// # Formula
+/// avg_entry = (size + added_size) / (size / entry_price + added_size / price)
+///
+/// Sizes are USD notionals, so each leg is weighted by the asset units it
+/// bought. This keeps the PnL of the combined position equal to the sum of
+/// the PnL of both legs.
 ///
 /// # Arguments
-/// * `position` - The position to check
-/// * `current_price` - Current market price (7 decimals)
+/// * `size` - Current position size (7 decimals)
+/// * `entry_price` - Current entry price (7 decimals)
+/// * `added_size` - Size being added (7 decimals)
+/// * `price` - Execution price of the added size (7 decimals)
+///
+/// # Returns
+/// New entry price (7 decimals)
+pub fn calculate_average_entry_price(
+    size: i128,
+    entry_price: i128,
+    added_size: i128,
+    price: i128,
+) -> Result<i128, NoetherError> {
+    if entry_price <= 0 || price <= 0 {
+        return Err(NoetherError::InvalidPrice);
+    }
+
+    // Asset units held by each leg (scaled by PRECISION)
+    let units = size * PRECISION / entry_price;
+    let added_units = added_size * PRECISION / price;
+    let total_units = units + added_units;
+
+    if total_units == 0 {
+        return Err(NoetherError::DivisionByZero);
+    }
+
+    Ok((size + added_size) * PRECISION / total_units)
+}
+
+/// Calculate vault utilization.
+///
+/// # Arguments
+/// * `reserved` - USDC reserved for open positions
+/// * `total` - Total USDC in the vault
+///
+/// # Returns
+/// Utilization in basis points, capped at 100%
+pub fn calculate_utilization_bps(reserved: i128, total: i128) -> i128 {
+    if reserved <= 0 {
+        return 0;
+    }
+    if total <= 0 || reserved >= total {
+        return BASIS_POINTS as i128;
+    }
+
+    reserved * (BASIS_POINTS as i128) / total
+}
+
+/// Precision of price impact factors (10^18).
+pub const PRICE_IMPACT_FACTOR_PRECISION: i128 = 1_000_000_000_000_000_000;
+
+/// Calculate the price impact of a trade from the change in open interest skew.
+///
+/// # Formula
+/// impact = factor × (|skew_before|^exponent - |skew_after|^exponent)
+///
+/// Skew is long minus short open interest, taken in whole USD for the power.
+/// A round trip that pushes the skew out and back nets to zero impact.
+///
+/// # Arguments
+/// * `skew_before` - Long minus short open interest before the trade (7 decimals)
+/// * `skew_after` - Long minus short open interest after the trade (7 decimals)
+/// * `impact_factor` - Impact factor (scaled by PRICE_IMPACT_FACTOR_PRECISION), 0 = disabled
+/// * `impact_exponent` - Impact exponent
 ///
 /// # Returns
-/// true if position should be liquidated
-pub fn should_liquidate(position: &Position, current_price: i128) -> bool {
-    match position.direction {
-        Direction::Long => current_price <= position.liquidation_price,
-        Direction::Short => current_price >= position.liquidation_price,
+/// Impact in USD (7 decimals), positive = favorable (skew reduced), negative = adverse
+pub fn calculate_price_impact(
+    skew_before: i128,
+    skew_after: i128,
+    impact_factor: i128,
+    impact_exponent: u32,
+) -> Result<i128, NoetherError> {
+    if impact_factor == 0 {
+        return Ok(0);
     }
+
+    let skew_term = |skew: i128| -> Result<i128, NoetherError> {
+        let usd = skew.abs() / PRECISION;
+        usd.checked_pow(impact_exponent)
+            .and_then(|v| v.checked_mul(impact_factor))
+            .map(|v| v / (PRICE_IMPACT_FACTOR_PRECISION / PRECISION))
+            .ok_or(NoetherError::Overflow)
+    };
+
+    Ok(skew_term(skew_before)? - skew_term(skew_after)?)
+}
+
+/// Calculate the execution price of a trade after price impact.
+///
+/// # Formula
+/// Buy:  exec_price = price × (1 - impact / size)
+/// Sell: exec_price = price × (1 + impact / size)
+///
+/// # Arguments
+/// * `price` - Oracle price (7 decimals)
+/// * `size` - Trade size (7 decimals)
+/// * `impact` - Price impact in USD (positive = favorable)
+/// * `is_buy` - true for opening longs / closing shorts
+///
+/// # Returns
+/// Execution price (7 decimals)
+pub fn calculate_execution_price(
+    price: i128,
+    size: i128,
+    impact: i128,
+    is_buy: bool,
+) -> Result<i128, NoetherError> {
+    if impact == 0 || size == 0 {
+        return Ok(price);
+    }
+
+    let adjustment = price * impact / size;
+    let execution_price = if is_buy { price - adjustment } else { price + adjustment };
+
+    if execution_price <= 0 {
+        return Err(NoetherError::InvalidPrice);
+    }
+
+    Ok(execution_price)
 }
 
 /// Calculate keeper reward for liquidation.
@@ -319,7 +436,7 @@ pub fn safe_sub(a: i128, b: i128) -> Result<i128, NoetherError> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use soroban_sdk::{Env, Address, Symbol};
+    use soroban_sdk::{testutils::Address as _, Env, Address, Symbol};
 
     fn create_test_position(env: &Env, direction: Direction) -> Position {
         Position {
@@ -335,6 +452,10 @@ mod tests {
             timestamp: 1000000,
             last_funding_time: 1000000,
             accumulated_funding: 0,
+            funding_index: 0,
+            accumulated_borrow_fee: 0,
+            borrow_index: 0,
+            reserved: 0,
         }
     }
 
@@ -426,6 +547,84 @@ mod tests {
         assert_eq!(pnl, 100 * PRECISION);
     }
 
+    #[test]
+    fn test_utilization() {
+        assert_eq!(calculate_utilization_bps(0, 1000 * PRECISION), 0);
+        assert_eq!(calculate_utilization_bps(250 * PRECISION, 1000 * PRECISION), 2500);
+        assert_eq!(calculate_utilization_bps(2000 * PRECISION, 1000 * PRECISION), 10_000);
+        assert_eq!(calculate_utilization_bps(100 * PRECISION, 0), 10_000);
+    }
+
+    #[test]
+    fn test_price_impact() {
+        // factor 1e-8 per USD with exponent 2: $100k skew => $100 impact
+        let factor = PRICE_IMPACT_FACTOR_PRECISION / 100_000_000;
+
+        // Growing the skew from 0 to $100k is adverse
+        let impact = calculate_price_impact(0, 100_000 * PRECISION, factor, 2).unwrap();
+        assert_eq!(impact, -100 * PRECISION);
+
+        // Unwinding it is favorable by the same amount
+        let impact = calculate_price_impact(100_000 * PRECISION, 0, factor, 2).unwrap();
+        assert_eq!(impact, 100 * PRECISION);
+
+        // Crossing to the other side only pays for the new skew
+        let impact = calculate_price_impact(
+            50_000 * PRECISION,
+            -50_000 * PRECISION,
+            factor,
+            2,
+        ).unwrap();
+        assert_eq!(impact, 0);
+
+        // Disabled
+        assert_eq!(calculate_price_impact(0, 100_000 * PRECISION, 0, 2).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_execution_price() {
+        // $100 adverse impact on a $100k buy at $1.00 => $1.001
+        let price = calculate_execution_price(
+            PRECISION,
+            100_000 * PRECISION,
+            -100 * PRECISION,
+            true,
+        ).unwrap();
+        assert_eq!(price, PRECISION + PRECISION / 1000);
+
+        // Same impact on a sell => $0.999
+        let price = calculate_execution_price(
+            PRECISION,
+            100_000 * PRECISION,
+            -100 * PRECISION,
+            false,
+        ).unwrap();
+        assert_eq!(price, PRECISION - PRECISION / 1000);
+
+        assert_eq!(calculate_execution_price(PRECISION, 0, 0, true).unwrap(), PRECISION);
+    }
+
+    #[test]
+    fn test_average_entry_price() {
+        // $1000 at $1.00 plus $1000 at $2.00 = 1000 + 500 units for $2000
+        let entry = calculate_average_entry_price(
+            1000 * PRECISION,
+            PRECISION,
+            1000 * PRECISION,
+            2 * PRECISION,
+        ).unwrap();
+        assert_eq!(entry, PRECISION * 4 / 3);
+
+        // Adding at the same price leaves entry unchanged
+        let entry = calculate_average_entry_price(
+            1000 * PRECISION,
+            PRECISION,
+            500 * PRECISION,
+            PRECISION,
+        ).unwrap();
+        assert_eq!(entry, PRECISION);
+    }
+
     #[test]
     fn test_glp_first_deposit() {
         let usdc_amount = 1000 * PRECISION;
@@ -64,6 +64,14 @@ pub struct Position {
     pub last_funding_time: u64,
     /// Accumulated funding payments (positive = paid, negative = received)
     pub accumulated_funding: i128,
+    /// Cumulative funding index of this side when funding was last settled
+    pub funding_index: i128,
+    /// Accumulated borrow fees owed to the vault (7 decimals)
+    pub accumulated_borrow_fee: i128,
+    /// Cumulative borrow index when borrow fees were last settled
+    pub borrow_index: i128,
+    /// USDC reserved in the vault for this position's payout (7 decimals)
+    pub reserved: i128,
 }
 
 /// Price data from oracles
@@ -107,9 +115,13 @@ pub struct PoolInfo {
     pub unrealized_pnl: i128,
     /// Total fees collected (7 decimals)
     pub total_fees: i128,
+    /// USDC reserved for potential payouts of open positions (7 decimals)
+    pub reserved_usdc: i128,
+    /// Utilization (reserved / total USDC) in basis points
+    pub utilization_bps: i128,
 }
 
-/// Market statistics
+/// Market statistics for a single asset
 #[contracttype]
 #[derive(Clone, Debug)]
 pub struct MarketStats {
@@ -125,9 +137,41 @@ pub struct MarketStats {
     pub funding_rate: i128,
     /// Last time funding was applied
     pub last_funding_time: u64,
+    /// Cumulative funding index for longs (scaled by 10^18)
+    pub cumulative_funding_long: i128,
+    /// Cumulative funding index for shorts (scaled by 10^18)
+    pub cumulative_funding_short: i128,
+    /// Vault utilization (reserved / total USDC) in basis points
+    pub utilization_bps: i128,
+    /// Current borrow rate (basis points per year)
+    pub borrow_rate_bps: i128,
+    /// Cumulative borrow index (scaled by 10^18)
+    pub cumulative_borrow: i128,
+    /// Long open interest that can still be opened (7 decimals)
+    pub remaining_long_oi: i128,
+    /// Short open interest that can still be opened (7 decimals)
+    pub remaining_short_oi: i128,
+    /// Open interest that can still be opened across all markets (7 decimals)
+    pub remaining_global_oi: i128,
 }
 
-/// Configuration for the Market contract
+/// Funding ledger for a single asset
+#[contracttype]
+#[derive(Clone, Debug)]
+pub struct FundingLedger {
+    /// Funding realized by paying positions and sent to the vault (7 decimals)
+    pub total_paid: i128,
+    /// Funding realized by receiving positions and paid by the vault (7 decimals)
+    pub total_received: i128,
+    /// Funding owed by open positions, not yet realized (7 decimals)
+    /// Positive = open positions owe the pool
+    pub pending: i128,
+    /// Net funding owed to the pool: total_paid - total_received + pending
+    /// Negative = the pool owes traders
+    pub net: i128,
+}
+
+/// Configuration for a listed market (one per asset)
 #[contracttype]
 #[derive(Clone, Debug)]
 pub struct MarketConfig {
@@ -137,14 +181,38 @@ pub struct MarketConfig {
     pub max_leverage: u32,
     /// Maintenance margin in basis points (e.g., 100 = 1%)
     pub maintenance_margin_bps: u32,
+    /// Margin that must remain after withdrawing collateral, in basis points (e.g., 1000 = 10%)
+    pub initial_margin_bps: u32,
     /// Liquidation fee in basis points (e.g., 500 = 5%)
     pub liquidation_fee_bps: u32,
+    /// Margin above maintenance restored by partial liquidations, in basis points (e.g., 100 = 1%)
+    pub liquidation_buffer_bps: u32,
+    /// Share of trading and liquidation fees sent to the insurance fund, in basis points
+    pub insurance_fee_share_bps: u32,
     /// Trading fee in basis points (e.g., 10 = 0.1%)
     pub trading_fee_bps: u32,
     /// Base funding rate in basis points per hour
     pub base_funding_rate_bps: u32,
+    /// Borrow rate at zero vault utilization, in basis points per year
+    pub borrow_base_rate_bps: u32,
+    /// Borrow rate added up to optimal utilization, in basis points per year
+    pub borrow_slope_bps: u32,
+    /// Borrow rate added from optimal to full utilization, in basis points per year
+    pub borrow_kink_slope_bps: u32,
+    /// Vault utilization at which the borrow curve steepens, in basis points
+    pub optimal_utilization_bps: u32,
+    /// Price impact factor (scaled by 10^18), 0 = no price impact
+    pub price_impact_factor: i128,
+    /// Price impact exponent applied to the open interest skew (1-3)
+    pub price_impact_exponent: u32,
     /// Maximum position size in USD (7 decimals)
     pub max_position_size: i128,
+    /// Maximum total long open interest for the asset in USD (7 decimals)
+    pub max_long_oi: i128,
+    /// Maximum total short open interest for the asset in USD (7 decimals)
+    pub max_short_oi: i128,
+    /// Trader PnL on the asset, as a share of vault USDC, that enables auto-deleveraging (bps)
+    pub adl_threshold_bps: u32,
     /// Oracle staleness threshold in seconds
     pub max_price_staleness: u64,
     /// Maximum allowed oracle deviation in basis points
@@ -157,10 +225,22 @@ impl Default for MarketConfig {
             min_collateral: 10 * PRECISION,          // 10 USDC minimum
             max_leverage: 10,                         // 10x max
             maintenance_margin_bps: 100,              // 1% maintenance margin
+            initial_margin_bps: 1000,                 // 10% initial margin
             liquidation_fee_bps: 500,                 // 5% liquidation fee
+            liquidation_buffer_bps: 100,              // Restore maintenance + 1%
+            insurance_fee_share_bps: 1000,            // 10% of fees to insurance
             trading_fee_bps: 10,                      // 0.1% trading fee
             base_funding_rate_bps: 1,                 // 0.01% per hour base rate
+            borrow_base_rate_bps: 0,                  // 0% per year at zero utilization
+            borrow_slope_bps: 1000,                   // +10% per year up to optimal
+            borrow_kink_slope_bps: 10_000,            // +100% per year above optimal
+            optimal_utilization_bps: 8000,            // 80% optimal utilization
+            price_impact_factor: 0,                   // No price impact
+            price_impact_exponent: 2,                 // Quadratic in skew
             max_position_size: 100_000 * PRECISION,  // 100,000 USDC max position
+            max_long_oi: 1_000_000 * PRECISION,      // 1,000,000 USDC max long OI
+            max_short_oi: 1_000_000 * PRECISION,     // 1,000,000 USDC max short OI
+            adl_threshold_bps: 2000,                  // ADL above 20% of vault USDC
             max_price_staleness: 60,                  // 60 seconds max staleness
             max_oracle_deviation_bps: 100,            // 1% max oracle deviation
         }
@@ -194,7 +274,7 @@ pub struct OracleConfig {
 }
 
 // ═══════════════════════════════════════════════════════════════════════════
-// Order Types (Limit Orders, Stop-Loss, Take-Profit)
+// Order Types (Limit Orders, Stop-Loss, Take-Profit, Trailing Stop)
 // ═══════════════════════════════════════════════════════════════════════════
 
 /// Type of conditional order
@@ -207,6 +287,8 @@ pub enum OrderType {
     StopLoss = 1,
     /// Take-profit order - close position to lock in profits
     TakeProfit = 2,
+    /// Trailing stop - close position when price retraces from its best level
+    TrailingStop = 3,
 }
 
 /// Trigger condition for order execution
@@ -231,11 +313,11 @@ pub enum OrderStatus {
     Cancelled = 2,
     /// Order was cancelled due to slippage exceeded
     CancelledSlippage = 3,
-    /// Order expired (for future use)
+    /// Order passed its expires_at timestamp before executing
     Expired = 4,
 }
 
-/// A conditional order (limit entry, stop-loss, or take-profit)
+/// A conditional order (limit entry, stop-loss, take-profit, or trailing stop)
 #[contracttype]
 #[derive(Clone, Debug)]
 pub struct Order {
@@ -245,7 +327,7 @@ pub struct Order {
     pub trader: Address,
     /// Trading asset symbol (e.g., "BTC", "ETH", "XLM")
     pub asset: Symbol,
-    /// Type of order (LimitEntry, StopLoss, TakeProfit)
+    /// Type of order (LimitEntry, StopLoss, TakeProfit, TrailingStop)
     pub order_type: OrderType,
     /// Direction for the position (Long or Short) - used for LimitEntry
     pub direction: Direction,
@@ -265,10 +347,145 @@ pub struct Order {
     pub has_position: bool,
     /// Timestamp when order was created (Unix seconds)
     pub created_at: u64,
+    /// Timestamp after which the order can no longer execute (None = good till cancelled)
+    pub expires_at: Option<u64>,
+    /// Trailing stop distance as a share of the water mark, in basis points (0 = use trail_amount)
+    pub trail_bps: u32,
+    /// Trailing stop distance as a fixed price amount (7 decimals)
+    pub trail_amount: i128,
+    /// Best price seen by a trailing stop: high for longs, low for shorts (7 decimals)
+    pub water_mark: i128,
+    /// Stop-loss to attach when a LimitEntry fills (bracket order)
+    pub stop_loss_price: Option<i128>,
+    /// Take-profit to attach when a LimitEntry fills (bracket order)
+    pub take_profit_price: Option<i128>,
     /// Current status of the order
     pub status: OrderStatus,
 }
 
+/// Execution terms of a limit entry order
+#[contracttype]
+#[derive(Clone, Debug, Default)]
+pub struct LimitOrderOptions {
+    /// Max allowed slippage in basis points (e.g., 100 = 1%)
+    pub slippage_tolerance_bps: u32,
+    /// Timestamp after which the order expires (None = good till cancelled)
+    pub expires_at: Option<u64>,
+    /// Stop-loss to attach when the order fills (None = no stop-loss)
+    pub stop_loss_price: Option<i128>,
+    /// Take-profit to attach when the order fills (None = no take-profit)
+    pub take_profit_price: Option<i128>,
+}
+
+/// Scopes a trader grants to an operator.
+/// There is no withdraw scope: operators can never take collateral out.
+#[contracttype]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct OperatorPermissions {
+    /// Open and increase positions, place limit orders
+    pub open: bool,
+    /// Close positions, fully or partially
+    pub close: bool,
+    /// Set, modify and cancel SL/TP/trailing orders
+    pub manage_orders: bool,
+    /// Add collateral to positions
+    pub add_collateral: bool,
+}
+
+/// Operator approval granted by a trader
+#[contracttype]
+#[derive(Clone, Debug)]
+pub struct OperatorApproval {
+    /// Scopes the operator may act in
+    pub permissions: OperatorPermissions,
+    /// Timestamp after which the approval lapses (None = until revoked)
+    pub expires_at: Option<u64>,
+}
+
+/// How a trader's positions are margined
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq, Copy)]
+pub enum MarginMode {
+    /// Each position is backed only by its own collateral
+    Isolated = 0,
+    /// The margin balance backs all positions, liquidated as one account
+    Cross = 1,
+}
+
+/// Live health of a trader's cross-margin account
+#[contracttype]
+#[derive(Clone, Debug)]
+pub struct AccountHealth {
+    /// Margin balance + token collateral value + value of all positions (7 decimals)
+    pub equity: i128,
+    /// Summed maintenance margin of all positions (7 decimals)
+    pub maintenance_margin: i128,
+    /// Summed initial margin of all positions (7 decimals)
+    pub initial_margin: i128,
+    /// Whether equity has fallen to the maintenance margin
+    pub liquidatable: bool,
+}
+
+/// Trader's margin account held by the market
+#[contracttype]
+#[derive(Clone, Debug, Default)]
+pub struct MarginAccount {
+    /// Free USDC available to positions and orders (7 decimals)
+    pub balance: i128,
+    /// Haircut value of non-USDC collateral in the account (7 decimals)
+    pub token_value: i128,
+    /// USDC locked as collateral in open positions (7 decimals)
+    pub position_collateral: i128,
+    /// USDC escrowed by pending limit orders (7 decimals)
+    pub order_collateral: i128,
+}
+
+/// Non-USDC token accepted as margin collateral
+#[contracttype]
+#[derive(Clone, Debug)]
+pub struct CollateralConfig {
+    /// Token contract (SAC, 7 decimals)
+    pub token: Address,
+    /// Oracle symbol pricing the token in USDC (e.g., "XLM")
+    pub oracle_symbol: Symbol,
+    /// Discount applied to the oracle value, in basis points
+    pub haircut_bps: u32,
+    /// Maximum total amount deposited across all traders (7 decimals)
+    pub deposit_cap: i128,
+}
+
+/// Fee discount and referrer rebate of a referral tier
+#[contracttype]
+#[derive(Clone, Debug)]
+pub struct ReferralTier {
+    /// Discount on referred traders' trading fees, in basis points
+    pub discount_bps: u32,
+    /// Share of the discounted fee paid to the referrer, in basis points
+    pub rebate_bps: u32,
+}
+
+/// Registered referral code
+#[contracttype]
+#[derive(Clone, Debug)]
+pub struct ReferralCode {
+    /// Referrer who registered the code and earns its rebates
+    pub owner: Address,
+    /// Index into the referral tiers (0 unless set by admin)
+    pub tier: u32,
+}
+
+/// Referrer's referral statistics
+#[contracttype]
+#[derive(Clone, Debug, Default)]
+pub struct ReferralStats {
+    /// Traders currently using one of the referrer's codes
+    pub referred_traders: u32,
+    /// Rebates earned over all time (7 decimals)
+    pub total_rewards: i128,
+    /// Rebates not yet claimed (7 decimals)
+    pub claimable: i128,
+}
+
 /// Keeper fee configuration for order execution
 #[contracttype]
 #[derive(Clone, Debug)]
@@ -287,3 +504,15 @@ impl Default for KeeperFeeConfig {
         }
     }
 }
+
+/// Outcome of one ID in a keeper batch (liquidate_batch, execute_orders_batch)
+#[contracttype]
+#[derive(Clone, Debug)]
+pub struct BatchResult {
+    /// Position or order ID
+    pub id: u64,
+    /// Whether the ID was eligible and processed (false = skipped)
+    pub executed: bool,
+    /// Keeper reward earned for this ID (7 decimals)
+    pub reward: i128,
+}
@@ -41,6 +41,15 @@
 //!
 //! This design respects Soroban's authorization model where contracts
 //! can only transfer their OWN tokens, not tokens from other contracts.
+//!
+//! **Reservations:**
+//! - Market reserves USDC for the maximum payout of every position it opens
+//! - Reserved USDC cannot be withdrawn by LPs until released on close
+//!
+//! **Funding:**
+//! - Funding paid by one side of a market is owed to the other side
+//! - Market settles realized funding through `settle_funding()`, so the
+//!   pool only carries the imbalance between what was paid and received
 
 #![no_std]
 
@@ -48,6 +57,7 @@ use soroban_sdk::{contract, contractimpl, token, Address, Env, Symbol};
 use noether_common::{
     NoetherError, PoolInfo, BASIS_POINTS,
     calculate_glp_for_deposit, calculate_usdc_for_withdrawal, calculate_glp_price,
+    calculate_utilization_bps,
 };
 
 mod storage;
@@ -213,6 +223,7 @@ impl VaultContract {
     ///
     /// # Note
     /// User must approve the vault to spend their NOE tokens before calling.
+    /// USDC reserved for open positions is excluded from withdrawable liquidity.
     pub fn withdraw(env: Env, withdrawer: Address, noe_amount: i128) -> Result<i128, NoetherError> {
         require_initialized(&env)?;
         require_not_paused(&env)?;
@@ -245,12 +256,18 @@ impl VaultContract {
             return Err(NoetherError::InvalidAmount);
         }
 
+        // Reserved USDC backs open positions and cannot be withdrawn
+        let reserved = get_reserved_usdc(&env);
+        if gross_usdc > get_total_usdc(&env) - reserved {
+            return Err(NoetherError::InsufficientLiquidity);
+        }
+
         // Check USDC liquidity (actual token balance in vault)
         let usdc_token = get_usdc_token(&env);
         let token_client = token::Client::new(&env, &usdc_token);
         let vault_balance = token_client.balance(&env.current_contract_address());
 
-        if net_usdc > vault_balance {
+        if net_usdc > vault_balance - reserved {
             return Err(NoetherError::InsufficientLiquidity);
         }
 
@@ -384,6 +401,60 @@ impl VaultContract {
         Ok(())
     }
 
+    /// Settle realized funding between Market and Vault.
+    /// Called by Market when a position's funding is realized.
+    ///
+    /// # Arguments
+    /// * `amount` - Funding realized by the position
+    ///   - Positive: trader paid funding, Market transfers it to Vault
+    ///   - Negative: trader is owed funding, Vault transfers it to Market
+    ///
+    /// Mirrors `settle_pnl()`: for payments into the pool only accounting is
+    /// updated here and Market transfers the tokens itself.
+    pub fn settle_funding(env: Env, amount: i128) -> Result<(), NoetherError> {
+        require_initialized(&env)?;
+
+        if amount == 0 {
+            return Err(NoetherError::InvalidAmount);
+        }
+
+        // Only market contract can call this
+        let market_contract = get_market_contract(&env);
+        market_contract.require_auth();
+
+        let total_usdc = get_total_usdc(&env);
+
+        if amount < 0 {
+            // Pool pays the receiving side
+            let payout = -amount;
+            if payout > total_usdc {
+                return Err(NoetherError::InsufficientLiquidity);
+            }
+
+            let usdc_token = get_usdc_token(&env);
+            let token_client = token::Client::new(&env, &usdc_token);
+            if payout > token_client.balance(&env.current_contract_address()) {
+                return Err(NoetherError::InsufficientLiquidity);
+            }
+
+            token_client.transfer(&env.current_contract_address(), &market_contract, &payout);
+        }
+
+        set_total_usdc(&env, total_usdc + amount);
+
+        let net_funding = get_net_funding(&env) + amount;
+        set_net_funding(&env, net_funding);
+
+        env.events().publish(
+            (Symbol::new(&env, "funding_settled"),),
+            (amount, net_funding),
+        );
+
+        extend_instance_ttl(&env);
+
+        Ok(())
+    }
+
     /// Update unrealized PnL tracking.
     /// Called by Market contract to keep track of open position PnL.
     ///
@@ -407,13 +478,14 @@ impl VaultContract {
         Ok(())
     }
 
-    /// Reserve USDC for a position being opened.
+    /// Reserve USDC for a position being opened or increased.
     /// Called when a trader opens a position to ensure liquidity exists.
     ///
     /// # Arguments
     /// * `amount` - Maximum potential payout needed for this position
     ///
-    /// This is a check-only function - no actual fund movement.
+    /// No funds move, but the amount is recorded as reserved and can no
+    /// longer back other positions or be withdrawn until released.
     pub fn reserve_for_position(env: Env, amount: i128) -> Result<(), NoetherError> {
         require_initialized(&env)?;
 
@@ -424,9 +496,10 @@ impl VaultContract {
         let market_contract = get_market_contract(&env);
         market_contract.require_auth();
 
-        // Check we have enough liquidity to potentially pay out
+        // Check we have enough unreserved liquidity to potentially pay out
         let total_usdc = get_total_usdc(&env);
-        if amount > total_usdc {
+        let reserved = get_reserved_usdc(&env);
+        if amount > total_usdc - reserved {
             return Err(NoetherError::InsufficientLiquidity);
         }
 
@@ -435,10 +508,54 @@ impl VaultContract {
         let token_client = token::Client::new(&env, &usdc_token);
         let vault_balance = token_client.balance(&env.current_contract_address());
 
-        if amount > vault_balance {
+        if amount > vault_balance - reserved {
             return Err(NoetherError::InsufficientLiquidity);
         }
 
+        set_reserved_usdc(&env, reserved + amount);
+
+        env.events().publish(
+            (Symbol::new(&env, "liquidity_reserved"),),
+            (amount, reserved + amount),
+        );
+
+        extend_instance_ttl(&env);
+
+        Ok(())
+    }
+
+    /// Release USDC reserved for a position being closed, reduced or liquidated.
+    ///
+    /// # Arguments
+    /// * `amount` - Reservation to release, as recorded on the position
+    ///
+    /// Releasing more than is reserved means the Market's records and the
+    /// Vault's have diverged, so it fails instead of clamping.
+    pub fn release_reservation(env: Env, amount: i128) -> Result<(), NoetherError> {
+        require_initialized(&env)?;
+
+        if amount <= 0 {
+            return Err(NoetherError::InvalidAmount);
+        }
+
+        let market_contract = get_market_contract(&env);
+        market_contract.require_auth();
+
+        let reserved = get_reserved_usdc(&env);
+        if amount > reserved {
+            return Err(NoetherError::InvalidAmount);
+        }
+
+        let reserved = reserved - amount;
+        set_reserved_usdc(&env, reserved);
+
+        env.events().publish(
+            (Symbol::new(&env, "reservation_released"),),
+            (amount, reserved),
+        );
+
+        extend_instance_ttl(&env);
+
         Ok(())
     }
 
@@ -456,9 +573,27 @@ impl VaultContract {
             aum: Self::calculate_aum_internal(&env),
             unrealized_pnl: get_unrealized_pnl(&env),
             total_fees: get_total_fees(&env),
+            reserved_usdc: get_reserved_usdc(&env),
+            utilization_bps: Self::calculate_utilization_internal(&env),
         })
     }
 
+    /// Get USDC reserved for open positions.
+    pub fn get_reserved_usdc(env: Env) -> i128 {
+        get_reserved_usdc(&env)
+    }
+
+    /// Get pool utilization (reserved / total USDC) in basis points.
+    pub fn get_utilization(env: Env) -> i128 {
+        Self::calculate_utilization_internal(&env)
+    }
+
+    /// Get net funding settled with the pool.
+    /// Positive = pool has received more funding than it paid out.
+    pub fn get_net_funding(env: Env) -> i128 {
+        get_net_funding(&env)
+    }
+
     /// Get current NOE price in USDC.
     /// Returns price with 7 decimals (1.0 = 10_000_000).
     pub fn get_noe_price(env: Env) -> Result<i128, NoetherError> {
@@ -699,6 +834,11 @@ impl VaultContract {
             aum
         }
     }
+
+    /// Calculate utilization of pool liquidity by open positions.
+    fn calculate_utilization_internal(env: &Env) -> i128 {
+        calculate_utilization_bps(get_reserved_usdc(env), get_total_usdc(env))
+    }
 }
 
 // ═══════════════════════════════════════════════════════════════════════════
@@ -708,8 +848,116 @@ impl VaultContract {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use soroban_sdk::testutils::{Address as _, Ledger};
+    use noether_common::PRECISION;
+    use soroban_sdk::testutils::Address as _;
+    use soroban_sdk::token::{StellarAssetClient, TokenClient};
+
+    const LP_DEPOSIT: i128 = 1_000 * PRECISION;
+
+    struct Setup<'a> {
+        vault: VaultContractClient<'a>,
+        usdc: TokenClient<'a>,
+        usdc_admin: StellarAssetClient<'a>,
+        market: Address,
+        lp: Address,
+    }
+
+    /// Vault with one LP deposit and a plain address standing in for the market
+    fn setup<'a>() -> Setup<'a> {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let admin = Address::generate(&env);
+        let market = Address::generate(&env);
+        let usdc_address = env.register_stellar_asset_contract_v2(admin.clone()).address();
+        let noe_address = env.register_stellar_asset_contract_v2(admin.clone()).address();
+        let usdc = TokenClient::new(&env, &usdc_address);
+        let usdc_admin = StellarAssetClient::new(&env, &usdc_address);
+
+        let vault = VaultContractClient::new(&env, &env.register_contract(None, VaultContract));
+        vault.initialize(&admin, &usdc_address, &noe_address, &market, &0, &0);
+        StellarAssetClient::new(&env, &noe_address).mint(&vault.address, &(1_000_000 * PRECISION));
+
+        // LPs approve the vault to take NOE back on withdrawal
+        let lp = Address::generate(&env);
+        usdc_admin.mint(&lp, &LP_DEPOSIT);
+        vault.deposit(&lp, &LP_DEPOSIT);
+        TokenClient::new(&env, &noe_address).approve(&lp, &vault.address, &LP_DEPOSIT, &1_000);
+
+        Setup { vault, usdc, usdc_admin, market, lp }
+    }
+
+    #[test]
+    fn test_reservation_blocks_withdrawal_until_released() {
+        let s = setup();
 
-    // Tests will be added in integration test file
-    // as they require token contract setup
+        s.vault.reserve_for_position(&(600 * PRECISION));
+        assert_eq!(s.vault.get_reserved_usdc(), 600 * PRECISION);
+        assert_eq!(s.vault.get_utilization(), 6000);
+
+        // Only the unreserved 400 USDC can leave the pool
+        assert_eq!(
+            s.vault.try_withdraw(&s.lp, &(500 * PRECISION)),
+            Err(Ok(NoetherError::InsufficientLiquidity))
+        );
+        assert_eq!(
+            s.vault.try_reserve_for_position(&(500 * PRECISION)),
+            Err(Ok(NoetherError::InsufficientLiquidity))
+        );
+
+        s.vault.release_reservation(&(600 * PRECISION));
+        assert_eq!(s.vault.get_reserved_usdc(), 0);
+        s.vault.withdraw(&s.lp, &(500 * PRECISION));
+        assert_eq!(s.usdc.balance(&s.lp), 500 * PRECISION);
+    }
+
+    #[test]
+    fn test_release_more_than_reserved_fails() {
+        let s = setup();
+
+        s.vault.reserve_for_position(&(100 * PRECISION));
+        assert_eq!(
+            s.vault.try_release_reservation(&(150 * PRECISION)),
+            Err(Ok(NoetherError::InvalidAmount))
+        );
+        assert_eq!(s.vault.get_reserved_usdc(), 100 * PRECISION);
+    }
+
+    #[test]
+    fn test_settle_funding_paid_into_pool() {
+        let s = setup();
+
+        // The market forwards funding a trader paid, then books it
+        s.usdc_admin.mint(&s.market, &(10 * PRECISION));
+        s.usdc.transfer(&s.market, &s.vault.address, &(10 * PRECISION));
+        s.vault.settle_funding(&(10 * PRECISION));
+
+        assert_eq!(s.vault.get_total_usdc(), LP_DEPOSIT + 10 * PRECISION);
+        assert_eq!(s.vault.get_net_funding(), 10 * PRECISION);
+        assert_eq!(s.usdc.balance(&s.vault.address), LP_DEPOSIT + 10 * PRECISION);
+    }
+
+    #[test]
+    fn test_settle_funding_paid_out_of_pool() {
+        let s = setup();
+
+        // Funding owed to a trader is paid from the pool to the market
+        s.vault.settle_funding(&(-10 * PRECISION));
+
+        assert_eq!(s.vault.get_total_usdc(), LP_DEPOSIT - 10 * PRECISION);
+        assert_eq!(s.vault.get_net_funding(), -10 * PRECISION);
+        assert_eq!(s.usdc.balance(&s.market), 10 * PRECISION);
+        assert_eq!(s.usdc.balance(&s.vault.address), LP_DEPOSIT - 10 * PRECISION);
+    }
+
+    #[test]
+    fn test_settle_funding_rejects_payout_beyond_pool() {
+        let s = setup();
+
+        assert_eq!(
+            s.vault.try_settle_funding(&(-2 * LP_DEPOSIT)),
+            Err(Ok(NoetherError::InsufficientLiquidity))
+        );
+        assert_eq!(s.vault.try_settle_funding(&0), Err(Ok(NoetherError::InvalidAmount)));
+    }
 }
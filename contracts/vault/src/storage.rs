@@ -28,6 +28,11 @@ pub enum DataKey {
     UnrealizedPnl,
     /// Total fees collected (7 decimals)
     TotalFees,
+    /// USDC reserved for open positions (7 decimals)
+    ReservedUsdc,
+    /// Net funding settled with the pool (7 decimals)
+    /// Positive = pool received more funding than it paid out
+    NetFunding,
     /// Deposit fee in basis points
     DepositFeeBps,
     /// Withdrawal fee in basis points
@@ -146,6 +151,24 @@ pub fn set_total_fees(env: &Env, amount: i128) {
     env.storage().persistent().extend_ttl(&DataKey::TotalFees, 2_592_000, 2_592_000);
 }
 
+pub fn get_reserved_usdc(env: &Env) -> i128 {
+    env.storage().persistent().get(&DataKey::ReservedUsdc).unwrap_or(0)
+}
+
+pub fn set_reserved_usdc(env: &Env, amount: i128) {
+    env.storage().persistent().set(&DataKey::ReservedUsdc, &amount);
+    env.storage().persistent().extend_ttl(&DataKey::ReservedUsdc, 2_592_000, 2_592_000);
+}
+
+pub fn get_net_funding(env: &Env) -> i128 {
+    env.storage().persistent().get(&DataKey::NetFunding).unwrap_or(0)
+}
+
+pub fn set_net_funding(env: &Env, amount: i128) {
+    env.storage().persistent().set(&DataKey::NetFunding, &amount);
+    env.storage().persistent().extend_ttl(&DataKey::NetFunding, 2_592_000, 2_592_000);
+}
+
 // ═══════════════════════════════════════════════════════════════════════════
 // Authorization Helpers
 // ═══════════════════════════════════════════════════════════════════════════
@@ -206,10 +206,22 @@ CONFIG='{
     "min_collateral": 100000000,
     "max_leverage": 10,
     "maintenance_margin_bps": 100,
+    "initial_margin_bps": 1000,
     "liquidation_fee_bps": 500,
+    "liquidation_buffer_bps": 100,
+    "insurance_fee_share_bps": 1000,
     "trading_fee_bps": 10,
     "base_funding_rate_bps": 1,
+    "borrow_base_rate_bps": 0,
+    "borrow_slope_bps": 1000,
+    "borrow_kink_slope_bps": 10000,
+    "optimal_utilization_bps": 8000,
+    "price_impact_factor": 0,
+    "price_impact_exponent": 2,
     "max_position_size": 1000000000000,
+    "max_long_oi": 10000000000000,
+    "max_short_oi": 10000000000000,
+    "adl_threshold_bps": 2000,
     "max_price_staleness": 60,
     "max_oracle_deviation_bps": 100
 }'
@@ -225,6 +237,18 @@ $CLI contract invoke \
     --usdc_token "$USDC_TOKEN_ID" \
     --config "$CONFIG"
 echo -e "${GREEN}✓ Market initialized${NC}"
+
+# List tradable assets (each market starts with the default config)
+for ASSET in XLM BTC ETH; do
+    $CLI contract invoke \
+        --id "$MARKET_ID" \
+        --source "$IDENTITY" \
+        --network testnet \
+        -- add_market \
+        --asset "$ASSET" \
+        --config "$CONFIG"
+    echo -e "${GREEN}✓ Market listed: $ASSET${NC}"
+done
 echo ""
 
 # ═══════════════════════════════════════════════════════════════════════════════
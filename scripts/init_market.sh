@@ -10,4 +10,15 @@ stellar contract invoke \
     --oracle_adapter CBDH7R4PBFHMN4AER74O4RG7VHUWUMFI67UKDIY6ISNQP4H5KFKMSBS4 \
     --vault CB2KKOV3DL3KCBIB272ITDUY3LIBD3RLMR3WZ2VAPNUZV3HIVKHT43SG \
     --usdc_token CA63EPM4EEXUVUANF6FQUJEJ37RWRYIXCARWFXYUMPP7RLZWFNLTVNR4 \
-    --config '{"min_collateral":"100000000","max_leverage":10,"maintenance_margin_bps":100,"liquidation_fee_bps":500,"trading_fee_bps":10,"base_funding_rate_bps":1,"max_position_size":"1000000000000","max_price_staleness":60,"max_oracle_deviation_bps":100}'
+    --config '{"min_collateral":"100000000","max_leverage":10,"maintenance_margin_bps":100,"initial_margin_bps":1000,"liquidation_fee_bps":500,"liquidation_buffer_bps":100,"insurance_fee_share_bps":1000,"trading_fee_bps":10,"base_funding_rate_bps":1,"borrow_base_rate_bps":0,"borrow_slope_bps":1000,"borrow_kink_slope_bps":10000,"optimal_utilization_bps":8000,"price_impact_factor":"0","price_impact_exponent":2,"max_position_size":"1000000000000","max_long_oi":"10000000000000","max_short_oi":"10000000000000","adl_threshold_bps":2000,"max_price_staleness":60,"max_oracle_deviation_bps":100}'
+
+for asset in XLM BTC ETH; do
+    stellar contract invoke \
+        --id CD4ZEYKAS6OICSECQDTRZU3GDIJYTJYO7UMRP6KULXPHOD6SXGNMHMMO \
+        --source-account noether_admin \
+        --network testnet \
+        -- \
+        add_market \
+        --asset "$asset" \
+        --config '{"min_collateral":"100000000","max_leverage":10,"maintenance_margin_bps":100,"initial_margin_bps":1000,"liquidation_fee_bps":500,"liquidation_buffer_bps":100,"insurance_fee_share_bps":1000,"trading_fee_bps":10,"base_funding_rate_bps":1,"borrow_base_rate_bps":0,"borrow_slope_bps":1000,"borrow_kink_slope_bps":10000,"optimal_utilization_bps":8000,"price_impact_factor":"0","price_impact_exponent":2,"max_position_size":"1000000000000","max_long_oi":"10000000000000","max_short_oi":"10000000000000","adl_threshold_bps":2000,"max_price_staleness":60,"max_oracle_deviation_bps":100}'
+done
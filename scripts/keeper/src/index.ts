@@ -262,6 +262,31 @@ class KeeperBot {
         // Position might have been closed, ignore
       }
     }
+
+    // Cross-margin positions are liquidated per account, not per position
+    const accounts = await this.stellar.getLiquidatableAccounts();
+    for (const trader of accounts) {
+      console.log(`\n⚠️  Cross-margin account ${trader} is liquidatable!`);
+      await this.executeAccountLiquidation(trader);
+    }
+  }
+
+  /**
+   * Execute a cross-margin account liquidation
+   */
+  private async executeAccountLiquidation(trader: string): Promise<void> {
+    const result = await this.stellar.liquidateAccount(trader);
+
+    if (result.success) {
+      this.stats.liquidationsExecuted++;
+      if (result.reward) {
+        this.stats.totalRewardsEarned += result.reward;
+      }
+      console.log(`   ✅ Account liquidation successful!`);
+      console.log(`   Transaction: ${result.txHash}`);
+    } else {
+      console.log(`   ❌ Account liquidation failed: ${result.error}`);
+    }
   }
 
   /**
@@ -309,6 +334,8 @@ class KeeperBot {
             console.log(`\n📋 Order ${orderId} triggered! (${order.order_type} ${order.direction} ${order.asset})`);
             await this.executeOrder(orderId, order.order_type);
           }
+        } else {
+          await this.ratchetTrailingStop(orderId);
         }
       } catch (error) {
         // Order might have been cancelled or executed, ignore
@@ -316,6 +343,23 @@ class KeeperBot {
     }
   }
 
+  /**
+   * Move a trailing stop's water mark if the price moved in the position's favour
+   */
+  private async ratchetTrailingStop(orderId: bigint): Promise<void> {
+    const order = await this.stellar.getOrder(orderId);
+    if (!order || order.order_type !== 'TrailingStop' || order.status !== 'Pending') return;
+
+    const { price } = await this.stellar.getOraclePrice(order.asset);
+    const improved = order.direction === 'Long' ? price > order.water_mark : price < order.water_mark;
+    if (!improved) return;
+
+    const result = await this.stellar.updateTrailingStop(orderId);
+    if (!result.success) {
+      console.log(`   ❌ Trailing stop ${orderId} update failed: ${result.error}`);
+    }
+  }
+
   /**
    * Execute a triggered order
    */
@@ -360,14 +404,16 @@ class KeeperBot {
   private async applyFundingRate(): Promise<void> {
     console.log('\n⏰ Applying hourly funding rate...');
 
-    const result = await this.stellar.applyFunding();
+    for (const asset of this.config.assets) {
+      const result = await this.stellar.applyFunding(asset.symbol);
 
-    if (result.success) {
-      console.log('   ✅ Funding rate applied');
-    } else if (result.error?.includes('FundingIntervalNotElapsed') || result.error?.includes('#55')) {
-      // Not yet time, ignore silently
-    } else {
-      console.log(`   ❌ Funding rate application failed: ${result.error}`);
+      if (result.success) {
+        console.log(`   ✅ Funding rate applied for ${asset.symbol}`);
+      } else if (result.error?.includes('FundingIntervalNotElapsed') || result.error?.includes('#55')) {
+        // Not yet time, ignore silently
+      } else {
+        console.log(`   ❌ Funding rate application failed for ${asset.symbol}: ${result.error}`);
+      }
     }
   }
 
@@ -150,6 +150,36 @@ export class StellarClient {
     );
   }
 
+  /**
+   * Get cross-margin traders whose account is liquidatable
+   */
+  async getLiquidatableAccounts(): Promise<string[]> {
+    try {
+      return await this.invokeContractRead<string[]>(
+        this.marketContract,
+        'get_liquidatable_accounts',
+        []
+      );
+    } catch (error) {
+      console.error('Error fetching liquidatable accounts:', error);
+      return [];
+    }
+  }
+
+  /**
+   * Liquidate a cross-margin account (closes its worst positions first)
+   */
+  async liquidateAccount(trader: string): Promise<ExecutionResult> {
+    return this.invokeContractWriteWithRetry(
+      this.marketContract,
+      'liquidate_account',
+      [
+        new Address(this.publicKey).toScVal(),
+        new Address(trader).toScVal(),
+      ]
+    );
+  }
+
   // ═══════════════════════════════════════════════════════════════════════
   // Order Functions
   // ═══════════════════════════════════════════════════════════════════════
@@ -213,6 +243,17 @@ export class StellarClient {
     }
   }
 
+  /**
+   * Ratchet a trailing stop's water mark to the current oracle price
+   */
+  async updateTrailingStop(orderId: bigint): Promise<ExecutionResult> {
+    return this.invokeContractWriteWithRetry(
+      this.marketContract,
+      'update_trailing_stop',
+      [nativeToScVal(orderId, { type: 'u64' })]
+    );
+  }
+
   /**
    * Execute an order
    */
@@ -232,13 +273,13 @@ export class StellarClient {
   // ═══════════════════════════════════════════════════════════════════════
 
   /**
-   * Apply funding rate (hourly)
+   * Apply funding rate for an asset (hourly)
    */
-  async applyFunding(): Promise<ExecutionResult> {
+  async applyFunding(asset: string): Promise<ExecutionResult> {
     return this.invokeContractWriteWithRetry(
       this.marketContract,
       'apply_funding',
-      []
+      [nativeToScVal(asset, { type: 'symbol' })]
     );
   }
 
@@ -395,6 +436,9 @@ export class StellarClient {
       timestamp: BigInt(raw.timestamp),
       last_funding_time: BigInt(raw.last_funding_time),
       accumulated_funding: BigInt(raw.accumulated_funding),
+      funding_index: BigInt(raw.funding_index ?? 0),
+      accumulated_borrow_fee: BigInt(raw.accumulated_borrow_fee ?? 0),
+      borrow_index: BigInt(raw.borrow_index ?? 0),
     };
   }
 
@@ -406,6 +450,7 @@ export class StellarClient {
       0: 'LimitEntry',
       1: 'StopLoss',
       2: 'TakeProfit',
+      3: 'TrailingStop',
     };
 
     const statusMap: Record<number, Order['status']> = {
@@ -430,6 +475,8 @@ export class StellarClient {
       position_id: BigInt(raw.position_id),
       has_position: Boolean(raw.has_position),
       created_at: BigInt(raw.created_at),
+      expires_at: raw.expires_at != null ? BigInt(raw.expires_at) : undefined,
+      water_mark: BigInt(raw.water_mark ?? 0),
       status: statusMap[raw.status] || 'Pending',
     };
   }
@@ -6,7 +6,7 @@
 export type Direction = 'Long' | 'Short';
 
 // Order type matching contract
-export type OrderType = 'LimitEntry' | 'StopLoss' | 'TakeProfit';
+export type OrderType = 'LimitEntry' | 'StopLoss' | 'TakeProfit' | 'TrailingStop';
 
 // Order status matching contract
 export type OrderStatus = 'Pending' | 'Executed' | 'Cancelled' | 'CancelledSlippage' | 'Expired';
@@ -28,6 +28,9 @@ export interface Position {
   timestamp: bigint;
   last_funding_time: bigint;
   accumulated_funding: bigint;
+  funding_index: bigint;
+  accumulated_borrow_fee: bigint;
+  borrow_index: bigint;
 }
 
 // Order from contract
@@ -45,6 +48,8 @@ export interface Order {
   position_id: bigint;
   has_position: boolean;
   created_at: bigint;
+  expires_at?: bigint;
+  water_mark: bigint;
   status: OrderStatus;
 }
 
@@ -233,7 +233,7 @@ echo -e "${GREEN}✓${NC}"
 
 # Initialize Market (with config struct)
 echo -n "  Initializing Market... "
-CONFIG='{"min_collateral":100000000,"max_leverage":10,"maintenance_margin_bps":100,"liquidation_fee_bps":500,"trading_fee_bps":10,"base_funding_rate_bps":1,"max_position_size":1000000000000,"max_price_staleness":60,"max_oracle_deviation_bps":100}'
+CONFIG='{"min_collateral":100000000,"max_leverage":10,"maintenance_margin_bps":100,"initial_margin_bps":1000,"liquidation_fee_bps":500,"liquidation_buffer_bps":100,"insurance_fee_share_bps":1000,"trading_fee_bps":10,"base_funding_rate_bps":1,"borrow_base_rate_bps":0,"borrow_slope_bps":1000,"borrow_kink_slope_bps":10000,"optimal_utilization_bps":8000,"price_impact_factor":0,"price_impact_exponent":2,"max_position_size":1000000000000,"max_long_oi":10000000000000,"max_short_oi":10000000000000,"adl_threshold_bps":2000,"max_price_staleness":60,"max_oracle_deviation_bps":100}'
 $CLI contract invoke --id "$MARKET_ID" $SOURCE_ARG --network testnet \
     -- initialize \
     --admin "$ADMIN_PUBLIC_KEY" \
@@ -243,6 +243,16 @@ $CLI contract invoke --id "$MARKET_ID" $SOURCE_ARG --network testnet \
     --config "$CONFIG" >/dev/null 2>&1
 echo -e "${GREEN}✓${NC}"
 
+# List tradable assets
+for ASSET in XLM BTC ETH; do
+    echo -n "  Listing $ASSET market... "
+    $CLI contract invoke --id "$MARKET_ID" $SOURCE_ARG --network testnet \
+        -- add_market \
+        --asset "$ASSET" \
+        --config "$CONFIG" >/dev/null 2>&1
+    echo -e "${GREEN}✓${NC}"
+done
+
 echo ""
 
 # ═══════════════════════════════════════════════════════════════════════════════
@@ -78,6 +78,7 @@ export function OrdersList({
       case 'LimitEntry':
         return <ArrowDownCircle className="w-4 h-4" />;
       case 'StopLoss':
+      case 'TrailingStop':
         return <Shield className="w-4 h-4" />;
       case 'TakeProfit':
         return <Target className="w-4 h-4" />;
@@ -94,6 +95,8 @@ export function OrdersList({
         return 'SL';
       case 'TakeProfit':
         return 'TP';
+      case 'TrailingStop':
+        return 'Trail';
       default:
         return orderType;
     }
@@ -102,6 +105,7 @@ export function OrdersList({
   const getOrderTypeColor = (orderType: string) => {
     switch (orderType) {
       case 'StopLoss':
+      case 'TrailingStop':
         return 'text-[#ef4444] bg-[#ef4444]/10';
       case 'TakeProfit':
         return 'text-[#22c55e] bg-[#22c55e]/10';
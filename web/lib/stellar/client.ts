@@ -143,6 +143,15 @@ export function toScVal(value: unknown, type: string): xdr.ScVal {
       return nativeToScVal(value as number, { type: 'u32' });
     case 'u64':
       return nativeToScVal(BigInt(value as number), { type: 'u64' });
+    case 'option_u64':
+      // Option<u64>: None is encoded as void, Some(x) as the u64 itself
+      return value === undefined || value === null
+        ? xdr.ScVal.scvVoid()
+        : nativeToScVal(BigInt(value as number), { type: 'u64' });
+    case 'option_i128':
+      return value === undefined || value === null
+        ? xdr.ScVal.scvVoid()
+        : nativeToScVal(BigInt(value as number | bigint), { type: 'i128' });
     case 'bool':
       return nativeToScVal(value as boolean, { type: 'bool' });
     case 'direction':
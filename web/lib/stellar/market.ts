@@ -1,5 +1,5 @@
 import { marketContract, usdcTokenContract, buildTransaction, submitTransaction, toScVal, rpc as sorobanRpc } from './client';
-import type { Position, DisplayPosition, MarketConfig, Direction, Trade, Order, DisplayOrder, OrderType, TriggerCondition, OrderStatus } from '@/types';
+import type { Position, DisplayPosition, MarketConfig, Direction, Trade, Order, DisplayOrder, OrderType, TriggerCondition, OrderStatus, MarginAccount, MarginMode, AccountHealth, CollateralConfig, ReferralStats } from '@/types';
 import { fromPrecision, calculatePnL } from '@/lib/utils/format';
 import { rpc, scValToNative, xdr, Horizon, TransactionBuilder, BASE_FEE } from '@stellar/stellar-sdk';
 import { CONTRACTS, NETWORK } from '@/lib/utils/constants';
@@ -172,8 +172,9 @@ export async function openPosition(
   console.log('[DEBUG] Step 3: Opening position...');
 
   // Build arguments matching contract signature:
-  // open_position(trader: Address, asset: Symbol, collateral: i128, leverage: u32, direction: Direction)
+  // open_position(caller: Address, trader: Address, asset: Symbol, collateral: i128, leverage: u32, direction: Direction)
   const args = [
+    toScVal(signerPublicKey, 'address'),  // caller: Address (the trader signs)
     toScVal(signerPublicKey, 'address'),  // trader: Address
     toScVal(params.asset, 'symbol'),       // asset: Symbol (e.g., "XLM", "BTC")
     toScVal(params.collateral, 'i128'),    // collateral: i128 (7 decimals)
@@ -277,6 +278,288 @@ export async function addCollateral(
   await submitTransaction(signedXdr);
 }
 
+/**
+ * Deposit USDC into the trader's margin account.
+ * Positions and orders draw from this balance first; closes and refunds are credited to it.
+ */
+export async function depositMargin(
+  signerPublicKey: string,
+  signTransaction: (xdr: string) => Promise<string>,
+  amount: bigint
+): Promise<void> {
+  // Contract signature: deposit_margin(trader: Address, amount: i128)
+  const args = [
+    toScVal(signerPublicKey, 'address'),
+    toScVal(amount, 'i128'),
+  ];
+
+  const xdr = await buildTransaction(signerPublicKey, marketContract, 'deposit_margin', args);
+  const signedXdr = await signTransaction(xdr);
+  await submitTransaction(signedXdr);
+}
+
+/**
+ * Withdraw free USDC from the trader's margin account to their wallet
+ */
+export async function withdrawMargin(
+  signerPublicKey: string,
+  signTransaction: (xdr: string) => Promise<string>,
+  amount: bigint
+): Promise<void> {
+  // Contract signature: withdraw_margin(trader: Address, amount: i128)
+  const args = [
+    toScVal(signerPublicKey, 'address'),
+    toScVal(amount, 'i128'),
+  ];
+
+  const xdr = await buildTransaction(signerPublicKey, marketContract, 'withdraw_margin', args);
+  const signedXdr = await signTransaction(xdr);
+  await submitTransaction(signedXdr);
+}
+
+/**
+ * Deposit a listed non-USDC token (e.g. XLM) into the trader's margin account
+ */
+export async function depositMarginToken(
+  signerPublicKey: string,
+  signTransaction: (xdr: string) => Promise<string>,
+  token: string,
+  amount: bigint
+): Promise<void> {
+  // Contract signature: deposit_margin_token(trader: Address, token: Address, amount: i128)
+  const args = [
+    toScVal(signerPublicKey, 'address'),
+    toScVal(token, 'address'),
+    toScVal(amount, 'i128'),
+  ];
+
+  const xdr = await buildTransaction(signerPublicKey, marketContract, 'deposit_margin_token', args);
+  const signedXdr = await signTransaction(xdr);
+  await submitTransaction(signedXdr);
+}
+
+/**
+ * Withdraw a non-USDC token from the trader's margin account to their wallet
+ */
+export async function withdrawMarginToken(
+  signerPublicKey: string,
+  signTransaction: (xdr: string) => Promise<string>,
+  token: string,
+  amount: bigint
+): Promise<void> {
+  // Contract signature: withdraw_margin_token(trader: Address, token: Address, amount: i128)
+  const args = [
+    toScVal(signerPublicKey, 'address'),
+    toScVal(token, 'address'),
+    toScVal(amount, 'i128'),
+  ];
+
+  const xdr = await buildTransaction(signerPublicKey, marketContract, 'withdraw_margin_token', args);
+  const signedXdr = await signTransaction(xdr);
+  await submitTransaction(signedXdr);
+}
+
+/**
+ * Get all tokens accepted as margin collateral (read-only)
+ */
+export async function getCollateralTokens(traderPublicKey: string): Promise<CollateralConfig[]> {
+  try {
+    const result = await sorobanRpc.simulateTransaction(
+      await buildSimulateTransaction(traderPublicKey, 'get_collateral_tokens', [])
+    );
+
+    if (rpc.Api.isSimulationSuccess(result) && result.result?.retval) {
+      const raw = scValToNative(result.result.retval) as Array<{
+        token: string;
+        oracle_symbol: string;
+        haircut_bps: number;
+        deposit_cap: bigint;
+      }>;
+      return raw.map((config) => ({
+        token: config.token,
+        oracleSymbol: config.oracle_symbol,
+        haircutBps: config.haircut_bps,
+        depositCap: config.deposit_cap,
+      }));
+    }
+
+    return [];
+  } catch (error) {
+    console.error('Error fetching collateral tokens:', error);
+    return [];
+  }
+}
+
+/**
+ * Get the trader's margin account (read-only)
+ */
+export async function getMarginAccount(traderPublicKey: string): Promise<MarginAccount | null> {
+  try {
+    const args = [toScVal(traderPublicKey, 'address')];
+
+    const result = await sorobanRpc.simulateTransaction(
+      await buildSimulateTransaction(traderPublicKey, 'get_margin_account', args)
+    );
+
+    if (rpc.Api.isSimulationSuccess(result) && result.result?.retval) {
+      const raw = scValToNative(result.result.retval) as {
+        balance: bigint;
+        token_value: bigint;
+        position_collateral: bigint;
+        order_collateral: bigint;
+      };
+      return {
+        balance: raw.balance,
+        tokenValue: raw.token_value,
+        positionCollateral: raw.position_collateral,
+        orderCollateral: raw.order_collateral,
+      };
+    }
+
+    return null;
+  } catch (error) {
+    console.error('Error fetching margin account:', error);
+    return null;
+  }
+}
+
+/**
+ * Switch the trader's account between isolated and cross margin (no open positions allowed)
+ */
+export async function setMarginMode(
+  signerPublicKey: string,
+  signTransaction: (xdr: string) => Promise<string>,
+  mode: MarginMode
+): Promise<void> {
+  // Contract signature: set_margin_mode(trader: Address, mode: MarginMode)
+  // MarginMode enum is encoded as u32: Isolated = 0, Cross = 1
+  const args = [
+    toScVal(signerPublicKey, 'address'),
+    toScVal(mode === 'Cross' ? 1 : 0, 'u32'),
+  ];
+
+  const xdr = await buildTransaction(signerPublicKey, marketContract, 'set_margin_mode', args);
+  const signedXdr = await signTransaction(xdr);
+  await submitTransaction(signedXdr);
+}
+
+/**
+ * Get live account health: equity vs summed margin requirements (read-only)
+ */
+export async function getAccountHealth(traderPublicKey: string): Promise<AccountHealth | null> {
+  try {
+    const args = [toScVal(traderPublicKey, 'address')];
+
+    const result = await sorobanRpc.simulateTransaction(
+      await buildSimulateTransaction(traderPublicKey, 'get_account_health', args)
+    );
+
+    if (rpc.Api.isSimulationSuccess(result) && result.result?.retval) {
+      const raw = scValToNative(result.result.retval) as {
+        equity: bigint;
+        maintenance_margin: bigint;
+        initial_margin: bigint;
+        liquidatable: boolean;
+      };
+      return {
+        equity: raw.equity,
+        maintenanceMargin: raw.maintenance_margin,
+        initialMargin: raw.initial_margin,
+        liquidatable: raw.liquidatable,
+      };
+    }
+
+    return null;
+  } catch (error) {
+    console.error('Error fetching account health:', error);
+    return null;
+  }
+}
+
+/**
+ * Register a referral code owned by the signer
+ */
+export async function registerReferralCode(
+  signerPublicKey: string,
+  signTransaction: (xdr: string) => Promise<string>,
+  code: string
+): Promise<void> {
+  // Contract signature: register_code(owner: Address, code: Symbol)
+  const args = [
+    toScVal(signerPublicKey, 'address'),
+    toScVal(code, 'symbol'),
+  ];
+
+  const xdr = await buildTransaction(signerPublicKey, marketContract, 'register_code', args);
+  const signedXdr = await signTransaction(xdr);
+  await submitTransaction(signedXdr);
+}
+
+/**
+ * Set the referral code applied to the signer's trading fees
+ */
+export async function setReferrer(
+  signerPublicKey: string,
+  signTransaction: (xdr: string) => Promise<string>,
+  code: string
+): Promise<void> {
+  // Contract signature: set_referrer(trader: Address, code: Symbol)
+  const args = [
+    toScVal(signerPublicKey, 'address'),
+    toScVal(code, 'symbol'),
+  ];
+
+  const xdr = await buildTransaction(signerPublicKey, marketContract, 'set_referrer', args);
+  const signedXdr = await signTransaction(xdr);
+  await submitTransaction(signedXdr);
+}
+
+/**
+ * Claim the signer's accrued referral rebates in USDC
+ */
+export async function claimReferralRewards(
+  signerPublicKey: string,
+  signTransaction: (xdr: string) => Promise<string>
+): Promise<void> {
+  // Contract signature: claim_referral_rewards(owner: Address)
+  const args = [toScVal(signerPublicKey, 'address')];
+
+  const xdr = await buildTransaction(signerPublicKey, marketContract, 'claim_referral_rewards', args);
+  const signedXdr = await signTransaction(xdr);
+  await submitTransaction(signedXdr);
+}
+
+/**
+ * Get a referrer's referred trader count and rebates (read-only)
+ */
+export async function getReferralStats(ownerPublicKey: string): Promise<ReferralStats | null> {
+  try {
+    const args = [toScVal(ownerPublicKey, 'address')];
+
+    const result = await sorobanRpc.simulateTransaction(
+      await buildSimulateTransaction(ownerPublicKey, 'get_referral_stats', args)
+    );
+
+    if (rpc.Api.isSimulationSuccess(result) && result.result?.retval) {
+      const raw = scValToNative(result.result.retval) as {
+        referred_traders: number;
+        total_rewards: bigint;
+        claimable: bigint;
+      };
+      return {
+        referredTraders: raw.referred_traders,
+        totalRewards: raw.total_rewards,
+        claimable: raw.claimable,
+      };
+    }
+
+    return null;
+  } catch (error) {
+    console.error('Error fetching referral stats:', error);
+    return null;
+  }
+}
+
 /**
  * Get all positions for a trader (read-only)
  */
@@ -657,7 +940,7 @@ interface RawOrder {
   id: number | bigint;
   trader: string;
   asset: string;
-  order_type: number | bigint; // 0 = LimitEntry, 1 = StopLoss, 2 = TakeProfit
+  order_type: number | bigint; // 0 = LimitEntry, 1 = StopLoss, 2 = TakeProfit, 3 = TrailingStop
   direction: number | bigint; // 0 = Long, 1 = Short
   collateral: bigint;
   leverage: number | bigint;
@@ -667,6 +950,9 @@ interface RawOrder {
   position_id: number | bigint;
   has_position: boolean;
   created_at: number | bigint;
+  expires_at?: number | bigint | null;
+  stop_loss_price?: bigint | null;
+  take_profit_price?: bigint | null;
   status: number | bigint; // 0 = Pending, 1 = Executed, 2 = Cancelled, 3 = CancelledSlippage, 4 = Expired
 }
 
@@ -678,6 +964,7 @@ function parseOrder(raw: RawOrder): Order {
     0: 'LimitEntry',
     1: 'StopLoss',
     2: 'TakeProfit',
+    3: 'TrailingStop',
   };
 
   const statusMap: Record<number, OrderStatus> = {
@@ -702,6 +989,9 @@ function parseOrder(raw: RawOrder): Order {
     positionId: Number(raw.position_id),
     hasPosition: raw.has_position,
     createdAt: Number(raw.created_at),
+    expiresAt: raw.expires_at != null ? Number(raw.expires_at) : undefined,
+    stopLossPrice: raw.stop_loss_price ?? undefined,
+    takeProfitPrice: raw.take_profit_price ?? undefined,
     status: statusMap[Number(raw.status)] || 'Pending',
   };
 }
@@ -752,6 +1042,9 @@ export async function placeLimitOrder(
     triggerPrice: bigint;
     triggerCondition: TriggerCondition;
     slippageToleranceBps: number;
+    expiresAt?: number; // Unix seconds, omit for good-till-cancelled
+    stopLossPrice?: bigint; // Bracket stop-loss attached on fill
+    takeProfitPrice?: bigint; // Bracket take-profit attached on fill
   }
 ): Promise<Order> {
   // Step 1: Check current allowance
@@ -780,9 +1073,10 @@ export async function placeLimitOrder(
   // Step 3: Place the limit order
   console.log('[DEBUG] Step 3: Placing limit order...');
 
-  // Contract signature: place_limit_order(trader, asset, direction, collateral, leverage, trigger_price, trigger_above, slippage_tolerance_bps)
+  // Contract signature: place_limit_order(caller, trader, asset, direction, collateral, leverage, trigger_price, trigger_above, options)
   // trigger_above is a boolean: true = trigger when price >= trigger_price, false = trigger when price <= trigger_price
   const args = [
+    toScVal(signerPublicKey, 'address'),  // caller: the trader signs
     toScVal(signerPublicKey, 'address'),
     toScVal(params.asset, 'symbol'),
     toScVal(params.direction, 'direction'),
@@ -790,7 +1084,25 @@ export async function placeLimitOrder(
     toScVal(params.leverage, 'u32'),
     toScVal(params.triggerPrice, 'i128'),
     toScVal(params.triggerCondition === 'Above', 'bool'),  // trigger_above: bool
-    toScVal(params.slippageToleranceBps, 'u32'),
+    // options: LimitOrderOptions (fields must be in alphabetical order)
+    xdr.ScVal.scvMap([
+      new xdr.ScMapEntry({
+        key: xdr.ScVal.scvSymbol('expires_at'),
+        val: toScVal(params.expiresAt, 'option_u64'),
+      }),
+      new xdr.ScMapEntry({
+        key: xdr.ScVal.scvSymbol('slippage_tolerance_bps'),
+        val: toScVal(params.slippageToleranceBps, 'u32'),
+      }),
+      new xdr.ScMapEntry({
+        key: xdr.ScVal.scvSymbol('stop_loss_price'),
+        val: toScVal(params.stopLossPrice, 'option_i128'),
+      }),
+      new xdr.ScMapEntry({
+        key: xdr.ScVal.scvSymbol('take_profit_price'),
+        val: toScVal(params.takeProfitPrice, 'option_i128'),
+      }),
+    ]),
   ];
 
   const xdrStr = await buildTransaction(signerPublicKey, marketContract, 'place_limit_order', args);
@@ -816,16 +1128,18 @@ export async function setStopLoss(
     positionId: number;
     triggerPrice: bigint;
     slippageToleranceBps: number;
+    expiresAt?: number;
   }
 ): Promise<Order> {
   console.log('[DEBUG] Setting stop-loss for position:', params.positionId);
 
-  // Contract signature: set_stop_loss(trader, position_id, trigger_price, slippage_tolerance_bps)
+  // Contract signature: set_stop_loss(trader, position_id, trigger_price, slippage_tolerance_bps, expires_at)
   const args = [
     toScVal(signerPublicKey, 'address'),
     toScVal(params.positionId, 'u64'),
     toScVal(params.triggerPrice, 'i128'),
     toScVal(params.slippageToleranceBps, 'u32'),
+    toScVal(params.expiresAt, 'option_u64'),
   ];
 
   const xdrStr = await buildTransaction(signerPublicKey, marketContract, 'set_stop_loss', args);
@@ -851,16 +1165,18 @@ export async function setTakeProfit(
     positionId: number;
     triggerPrice: bigint;
     slippageToleranceBps: number;
+    expiresAt?: number;
   }
 ): Promise<Order> {
   console.log('[DEBUG] Setting take-profit for position:', params.positionId);
 
-  // Contract signature: set_take_profit(trader, position_id, trigger_price, slippage_tolerance_bps)
+  // Contract signature: set_take_profit(trader, position_id, trigger_price, slippage_tolerance_bps, expires_at)
   const args = [
     toScVal(signerPublicKey, 'address'),
     toScVal(params.positionId, 'u64'),
     toScVal(params.triggerPrice, 'i128'),
     toScVal(params.slippageToleranceBps, 'u32'),
+    toScVal(params.expiresAt, 'option_u64'),
   ];
 
   const xdrStr = await buildTransaction(signerPublicKey, marketContract, 'set_take_profit', args);
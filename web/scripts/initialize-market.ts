@@ -35,10 +35,22 @@ const MARKET_CONFIG = {
   min_collateral: BigInt(10) * BigInt(10_000_000), // 10 USDC minimum
   max_leverage: 10,
   maintenance_margin_bps: 100, // 1% maintenance margin
+  initial_margin_bps: 1000, // 10% initial margin
   liquidation_fee_bps: 500, // 5% liquidation fee
+  liquidation_buffer_bps: 100, // partial liquidations restore margin to maintenance + 1%
+  insurance_fee_share_bps: 1000, // 10% of trading and liquidation fees to the insurance fund
   trading_fee_bps: 10, // 0.1%
   base_funding_rate_bps: 1, // 0.01% per hour
+  borrow_base_rate_bps: 0, // 0% per year at zero utilization
+  borrow_slope_bps: 1000, // +10% per year up to optimal utilization
+  borrow_kink_slope_bps: 10000, // +100% per year from optimal to full utilization
+  optimal_utilization_bps: 8000, // 80% optimal utilization
+  price_impact_factor: BigInt(0), // price impact disabled
+  price_impact_exponent: 2, // quadratic impact in skew
   max_position_size: BigInt(100_000) * BigInt(10_000_000), // 100,000 USDC max
+  max_long_oi: BigInt(1_000_000) * BigInt(10_000_000), // 1,000,000 USDC max long open interest
+  max_short_oi: BigInt(1_000_000) * BigInt(10_000_000), // 1,000,000 USDC max short open interest
+  adl_threshold_bps: 2000, // ADL when trader PnL exceeds 20% of vault USDC
   max_price_staleness: 60, // 60 seconds
   max_oracle_deviation_bps: 100, // 1%
 };
@@ -46,10 +58,38 @@ const MARKET_CONFIG = {
 function createMarketConfigScVal(config: typeof MARKET_CONFIG): xdr.ScVal {
   // Fields must be in alphabetical order for Soroban struct matching
   return xdr.ScVal.scvMap([
+    new xdr.ScMapEntry({
+      key: xdr.ScVal.scvSymbol('adl_threshold_bps'),
+      val: nativeToScVal(config.adl_threshold_bps, { type: 'u32' }),
+    }),
     new xdr.ScMapEntry({
       key: xdr.ScVal.scvSymbol('base_funding_rate_bps'),
       val: nativeToScVal(config.base_funding_rate_bps, { type: 'u32' }),
     }),
+    new xdr.ScMapEntry({
+      key: xdr.ScVal.scvSymbol('borrow_base_rate_bps'),
+      val: nativeToScVal(config.borrow_base_rate_bps, { type: 'u32' }),
+    }),
+    new xdr.ScMapEntry({
+      key: xdr.ScVal.scvSymbol('borrow_kink_slope_bps'),
+      val: nativeToScVal(config.borrow_kink_slope_bps, { type: 'u32' }),
+    }),
+    new xdr.ScMapEntry({
+      key: xdr.ScVal.scvSymbol('borrow_slope_bps'),
+      val: nativeToScVal(config.borrow_slope_bps, { type: 'u32' }),
+    }),
+    new xdr.ScMapEntry({
+      key: xdr.ScVal.scvSymbol('initial_margin_bps'),
+      val: nativeToScVal(config.initial_margin_bps, { type: 'u32' }),
+    }),
+    new xdr.ScMapEntry({
+      key: xdr.ScVal.scvSymbol('insurance_fee_share_bps'),
+      val: nativeToScVal(config.insurance_fee_share_bps, { type: 'u32' }),
+    }),
+    new xdr.ScMapEntry({
+      key: xdr.ScVal.scvSymbol('liquidation_buffer_bps'),
+      val: nativeToScVal(config.liquidation_buffer_bps, { type: 'u32' }),
+    }),
     new xdr.ScMapEntry({
       key: xdr.ScVal.scvSymbol('liquidation_fee_bps'),
       val: nativeToScVal(config.liquidation_fee_bps, { type: 'u32' }),
@@ -62,6 +102,10 @@ function createMarketConfigScVal(config: typeof MARKET_CONFIG): xdr.ScVal {
       key: xdr.ScVal.scvSymbol('max_leverage'),
       val: nativeToScVal(config.max_leverage, { type: 'u32' }),
     }),
+    new xdr.ScMapEntry({
+      key: xdr.ScVal.scvSymbol('max_long_oi'),
+      val: nativeToScVal(config.max_long_oi, { type: 'i128' }),
+    }),
     new xdr.ScMapEntry({
       key: xdr.ScVal.scvSymbol('max_oracle_deviation_bps'),
       val: nativeToScVal(config.max_oracle_deviation_bps, { type: 'u32' }),
@@ -74,10 +118,26 @@ function createMarketConfigScVal(config: typeof MARKET_CONFIG): xdr.ScVal {
       key: xdr.ScVal.scvSymbol('max_price_staleness'),
       val: nativeToScVal(config.max_price_staleness, { type: 'u64' }),
     }),
+    new xdr.ScMapEntry({
+      key: xdr.ScVal.scvSymbol('max_short_oi'),
+      val: nativeToScVal(config.max_short_oi, { type: 'i128' }),
+    }),
     new xdr.ScMapEntry({
       key: xdr.ScVal.scvSymbol('min_collateral'),
       val: nativeToScVal(config.min_collateral, { type: 'i128' }),
     }),
+    new xdr.ScMapEntry({
+      key: xdr.ScVal.scvSymbol('optimal_utilization_bps'),
+      val: nativeToScVal(config.optimal_utilization_bps, { type: 'u32' }),
+    }),
+    new xdr.ScMapEntry({
+      key: xdr.ScVal.scvSymbol('price_impact_exponent'),
+      val: nativeToScVal(config.price_impact_exponent, { type: 'u32' }),
+    }),
+    new xdr.ScMapEntry({
+      key: xdr.ScVal.scvSymbol('price_impact_factor'),
+      val: nativeToScVal(config.price_impact_factor, { type: 'i128' }),
+    }),
     new xdr.ScMapEntry({
       key: xdr.ScVal.scvSymbol('trading_fee_bps'),
       val: nativeToScVal(config.trading_fee_bps, { type: 'u32' }),
@@ -2,7 +2,7 @@
 export type Direction = 'Long' | 'Short';
 
 // Order type
-export type OrderType = 'LimitEntry' | 'StopLoss' | 'TakeProfit';
+export type OrderType = 'LimitEntry' | 'StopLoss' | 'TakeProfit' | 'TrailingStop';
 
 // Order status
 export type OrderStatus = 'Pending' | 'Executed' | 'Cancelled' | 'CancelledSlippage' | 'Expired';
@@ -25,6 +25,39 @@ export interface Position {
   accumulatedFunding: bigint;
 }
 
+// Margin account from market contract
+export interface MarginAccount {
+  balance: bigint; // Free USDC
+  tokenValue: bigint; // Haircut value of non-USDC collateral
+  positionCollateral: bigint; // Locked in open positions
+  orderCollateral: bigint; // Escrowed by pending limit orders
+}
+
+export type MarginMode = 'Isolated' | 'Cross';
+
+// Non-USDC token accepted as margin collateral
+export interface CollateralConfig {
+  token: string;
+  oracleSymbol: string;
+  haircutBps: number;
+  depositCap: bigint;
+}
+
+// Cross-margin account health from market contract
+export interface AccountHealth {
+  equity: bigint; // Margin balance + token collateral value + position values
+  maintenanceMargin: bigint;
+  initialMargin: bigint;
+  liquidatable: boolean;
+}
+
+// Referrer's referral statistics from market contract
+export interface ReferralStats {
+  referredTraders: number;
+  totalRewards: bigint; // Rebates earned over all time
+  claimable: bigint; // Rebates not yet claimed
+}
+
 // Order from market contract
 export interface Order {
   id: number;
@@ -40,6 +73,9 @@ export interface Order {
   positionId: number;
   hasPosition: boolean;
   createdAt: number;
+  expiresAt?: number;
+  stopLossPrice?: bigint; // Bracket legs of a LimitEntry
+  takeProfitPrice?: bigint;
   status: OrderStatus;
 }
 
@@ -68,10 +104,22 @@ export interface MarketConfig {
   minCollateral: bigint;
   maxLeverage: number;
   maintenanceMarginBps: number;
+  initialMarginBps: number;
   liquidationFeeBps: number;
+  liquidationBufferBps: number;
+  insuranceFeeShareBps: number;
   tradingFeeBps: number;
   baseFundingRateBps: number;
+  borrowBaseRateBps: number;
+  borrowSlopeBps: number;
+  borrowKinkSlopeBps: number;
+  optimalUtilizationBps: number;
+  priceImpactFactor: bigint;
+  priceImpactExponent: number;
   maxPositionSize: bigint;
+  maxLongOi: bigint;
+  maxShortOi: bigint;
+  adlThresholdBps: number;
   maxPriceStaleness: number;
   maxOracleDeviationBps: number;
 }
@@ -83,6 +131,8 @@ export interface PoolInfo {
   unrealizedPnl: bigint;
   totalFees: bigint;
   noePrice: bigint;
+  reservedUsdc: bigint;
+  utilizationBps: bigint;
 }
 
 // Price data from oracle